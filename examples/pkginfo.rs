@@ -8,7 +8,7 @@ use std::process;
 fn main() {
     let args: Vec<String> = env::args().collect();
     if args.len() != 2 {
-        println!("usage: pkgname <path>");
+        println!("usage: pkginfo <path>");
         process::exit(1);
     }
 
@@ -43,44 +43,15 @@ fn main() {
         }
     };
 
-    let control_tar = match pkg.control() {
-        Ok(tar) => tar,
+    match pkg.info() {
+        Ok(info) => print!("{info}"),
         Err(e) => {
             println!(
-                "ERROR: Failed to get control tar from debian file \"{}\"",
+                "ERROR: Failed to read debian file \"{}\"",
                 deb_path.display()
             );
             println!("       {e}");
             process::exit(1);
         }
-    };
-
-    let control = match debpkg::Control::extract(control_tar) {
-        Ok(control) => control,
-        Err(e) => {
-            println!(
-                "ERROR: Failed to parse debian control file \"{}\"",
-                deb_path.display()
-            );
-            println!("       {e}");
-            process::exit(1);
-        }
-    };
-
-    let tags = control.tags();
-
-    for tag in tags {
-        if tag.to_lowercase() == "description" {
-            println!("{}: {}", tag, control.short_description().unwrap());
-            let long_desc = control
-                .long_description()
-                .unwrap()
-                .split('\n')
-                .collect::<std::vec::Vec<&str>>()
-                .join("\n ");
-            println!(" {long_desc}");
-        } else {
-            println!("{}: {}", tag, control.get(tag).unwrap());
-        }
     }
 }
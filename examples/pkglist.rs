@@ -43,13 +43,17 @@ fn main() {
         }
     };
 
-    let mut data = pkg.data().unwrap();
-    let entries = data.entries().unwrap();
-    let files: Vec<std::path::PathBuf> = entries
-        .map(|e| e.unwrap().path().unwrap().into_owned())
-        .collect();
-
-    for file in files {
-        println!("{}", file.display());
-    }
+    let entries = match pkg.contents() {
+        Ok(entries) => entries,
+        Err(e) => {
+            println!(
+                "ERROR: Failed to read data archive of \"{}\"",
+                deb_path.display()
+            );
+            println!("       {e}");
+            process::exit(1);
+        }
+    };
+
+    print!("{}", debpkg::format_contents(&entries));
 }
@@ -0,0 +1,64 @@
+extern crate debpkg;
+
+use std::env;
+use std::fs::File;
+use std::path::Path;
+use std::process;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 3 || (args[1] != "spdx" && args[1] != "cyclonedx") {
+        println!("usage: pkgsbom <spdx|cyclonedx> <path>");
+        process::exit(1);
+    }
+
+    let deb_path = Path::new(&args[2]);
+
+    let deb_file = match File::open(deb_path) {
+        Ok(file) => file,
+        Err(e) => {
+            println!(
+                "ERROR: Failed to open debian file \"{}\"",
+                deb_path.display()
+            );
+            println!("       {e}");
+            process::exit(1);
+        }
+    };
+
+    let mut pkg = match debpkg::DebPkg::parse(deb_file) {
+        Ok(pkg) => pkg,
+        Err(e) => {
+            println!(
+                "ERROR: Failed to parse debian file \"{}\"",
+                deb_path.display()
+            );
+            println!("       {e}");
+            process::exit(1);
+        }
+    };
+
+    let mut options = debpkg::SbomOptions::new();
+    if let Some(epoch) = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse().ok())
+    {
+        options.created(std::time::UNIX_EPOCH + std::time::Duration::from_secs(epoch));
+    }
+
+    let stdout = std::io::stdout();
+    let result = if args[1] == "spdx" {
+        pkg.write_spdx(stdout.lock(), &options)
+    } else {
+        pkg.write_cyclonedx(stdout.lock(), &options)
+    };
+    if let Err(e) = result {
+        println!(
+            "ERROR: Failed to read debian file \"{}\"",
+            deb_path.display()
+        );
+        println!("       {e}");
+        process::exit(1);
+    }
+    println!();
+}
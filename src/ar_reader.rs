@@ -0,0 +1,249 @@
+use std::io::Read;
+
+use crate::{Error, Result};
+
+pub(crate) const AR_MAGIC: &[u8; 8] = b"!<arch>\n";
+pub(crate) const AR_HEADER_LEN: usize = 60;
+
+/// A member of the ar archive of a debian package other than
+/// `debian-binary`, `control.tar` and `data.tar`, such as the `_gpgorigin`
+/// signature added by debsigs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArMember {
+    /// The member name, e.g. `_gpgorigin`
+    pub name: String,
+
+    /// The raw contents of the member
+    pub contents: Vec<u8>,
+}
+
+fn invalid_data(msg: &str) -> Error {
+    Error::Io(std::io::Error::new(std::io::ErrorKind::InvalidData, msg))
+}
+
+// The header of an ar member
+pub(crate) struct MemberHeader {
+    pub(crate) name: String,
+    pub(crate) size: u64,
+    // The length of a BSD style `#1/<len>` name, which is stored at the start
+    // of the member data and is included in `size`
+    pub(crate) name_len: u64,
+}
+
+// Parses a 60 byte ar member header without doing any IO so it can be shared
+// by the blocking and async readers. Once `name_len` bytes of the member
+// data are read, pass them to `parse_bsd_name` to get the real name.
+pub(crate) fn parse_member_header(header: &[u8; AR_HEADER_LEN]) -> Result<MemberHeader> {
+    if &header[58..60] != b"`\n" {
+        return Err(invalid_data("invalid ar member header"));
+    }
+
+    let name = String::from_utf8_lossy(&header[0..16]);
+    let name = name.trim_end();
+    // GNU ar terminates names with a slash
+    let name = name.strip_suffix('/').unwrap_or(name).to_owned();
+
+    let size = std::str::from_utf8(&header[48..58])
+        .ok()
+        .and_then(|size| size.trim().parse().ok())
+        .ok_or_else(|| invalid_data("invalid ar member size"))?;
+
+    let name_len = match name.strip_prefix("#1/") {
+        Some(len) => len
+            .parse()
+            .ok()
+            .filter(|len| *len <= size)
+            .ok_or_else(|| invalid_data("invalid ar member name"))?,
+        None => 0,
+    };
+    Ok(MemberHeader {
+        name,
+        size,
+        name_len,
+    })
+}
+
+// Returns the name of a member from the BSD style name stored at the start
+// of its data
+pub(crate) fn parse_bsd_name(name: &[u8]) -> String {
+    String::from_utf8_lossy(name)
+        .trim_end_matches('\0')
+        .to_owned()
+}
+
+// A forward only ar reader. Unlike `ar::Archive`, it does not borrow itself
+// to read a member, so members can be inspected and skipped in a loop.
+pub(crate) struct ArReader<R: Read> {
+    reader: R,
+    started: bool,
+    remaining: u64,
+    padding: u64,
+    members: Vec<(String, u64)>,
+}
+
+impl<R: Read> ArReader<R> {
+    pub(crate) fn new(reader: R) -> ArReader<R> {
+        ArReader {
+            reader,
+            started: false,
+            remaining: 0,
+            padding: 0,
+            members: Vec::new(),
+        }
+    }
+
+    // Skips whatever is left of the current member, then reads the next
+    // member header and returns its name. Returns None at the end of the
+    // archive.
+    pub(crate) fn next_member(&mut self) -> Result<Option<String>> {
+        if !self.started {
+            let mut magic = [0; AR_MAGIC.len()];
+            self.reader.read_exact(&mut magic)?;
+            if &magic != AR_MAGIC {
+                return Err(invalid_data("invalid ar archive signature"));
+            }
+            self.started = true;
+        }
+
+        std::io::copy(self, &mut std::io::sink())?;
+        if self.padding != 0 {
+            self.padding = 0;
+            // Some writers omit the padding of the last member
+            if self.reader.read(&mut [0])? == 0 {
+                return Ok(None);
+            }
+        }
+
+        let mut header = [0; AR_HEADER_LEN];
+        let mut read = 0;
+        while read < header.len() {
+            match self.reader.read(&mut header[read..])? {
+                0 if read == 0 => return Ok(None),
+                0 => return Err(Error::Io(std::io::ErrorKind::UnexpectedEof.into())),
+                n => read += n,
+            }
+        }
+
+        let MemberHeader {
+            mut name,
+            size,
+            name_len,
+        } = parse_member_header(&header)?;
+        // Members are padded to an even size
+        self.remaining = size;
+        self.padding = size % 2;
+
+        if name_len != 0 {
+            let mut bsd_name = vec![0; name_len as usize];
+            self.read_exact(&mut bsd_name)?;
+            name = parse_bsd_name(&bsd_name);
+        }
+        self.members.push((name.clone(), size));
+        Ok(Some(name))
+    }
+
+    // Returns the names and sizes of the members read so far
+    pub(crate) fn members(&self) -> &[(String, u64)] {
+        &self.members
+    }
+
+    // Reads the rest of the current member into memory
+    pub(crate) fn read_member(&mut self, name: String) -> Result<ArMember> {
+        let mut contents = Vec::new();
+        self.read_to_end(&mut contents)?;
+        Ok(ArMember { name, contents })
+    }
+}
+
+// Reads the current member, returning EOF at its end
+impl<R: Read> Read for ArReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let max = std::cmp::min(buf.len() as u64, self.remaining) as usize;
+        if max == 0 {
+            return Ok(0);
+        }
+        let read = self.reader.read(&mut buf[..max])?;
+        if read == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        self.remaining -= read as u64;
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+
+    fn build(members: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = ar::Builder::new(Vec::new());
+        for (name, contents) in members {
+            let header = ar::Header::new(name.as_bytes().to_vec(), contents.len() as u64);
+            builder.append(&header, *contents).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    #[test]
+    fn reads_members_in_order() {
+        let archive = build(&[("debian-binary", b"2.0\n"), ("odd", b"abc"), ("_sig", b"s")]);
+        let mut reader = ArReader::new(&archive[..]);
+
+        assert_eq!(reader.next_member().unwrap().unwrap(), "debian-binary");
+        // Skip the rest of debian-binary without reading it
+        assert_eq!(reader.next_member().unwrap().unwrap(), "odd");
+        let member = reader.read_member("odd".to_owned()).unwrap();
+        assert_eq!(member.contents, b"abc");
+        assert_eq!(reader.next_member().unwrap().unwrap(), "_sig");
+        let mut contents = Vec::new();
+        reader.read_to_end(&mut contents).unwrap();
+        assert_eq!(contents, b"s");
+        assert!(reader.next_member().unwrap().is_none());
+    }
+
+    #[test]
+    fn gnu_and_bsd_names() {
+        let mut archive = AR_MAGIC.to_vec();
+        archive.extend_from_slice(
+            format!(
+                "{:<16}{:<12}{:<6}{:<6}{:<8}{:<10}`\n",
+                "gnu/", 0, 0, 0, 644, 2
+            )
+            .as_bytes(),
+        );
+        archive.extend_from_slice(b"ab");
+        archive.extend_from_slice(
+            format!(
+                "{:<16}{:<12}{:<6}{:<6}{:<8}{:<10}`\n",
+                "#1/8", 0, 0, 0, 644, 9
+            )
+            .as_bytes(),
+        );
+        archive.extend_from_slice(b"bsd_name1\n");
+        let mut reader = ArReader::new(&archive[..]);
+
+        assert_eq!(reader.next_member().unwrap().unwrap(), "gnu");
+        assert_eq!(reader.next_member().unwrap().unwrap(), "bsd_name");
+        let member = reader.read_member("bsd_name".to_owned()).unwrap();
+        assert_eq!(member.contents, b"1");
+        assert!(reader.next_member().unwrap().is_none());
+    }
+
+    #[test]
+    fn truncated_member_fails() {
+        let mut archive = build(&[("debian-binary", b"2.0\n")]);
+        archive.truncate(archive.len() - 2);
+        let mut reader = ArReader::new(&archive[..]);
+        reader.next_member().unwrap();
+        assert_matches!(reader.next_member(), Err(Error::Io(_)));
+    }
+
+    #[test]
+    fn bad_signature_fails() {
+        let mut reader = ArReader::new(&b"!<arch>"[..]);
+        assert_matches!(reader.next_member(), Err(Error::Io(_)));
+        let mut reader = ArReader::new(&b"!<arxh>\n"[..]);
+        assert_matches!(reader.next_member(), Err(Error::Io(_)));
+    }
+}
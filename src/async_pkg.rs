@@ -0,0 +1,324 @@
+use std::io::Cursor;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncReadExt, ReadBuf};
+
+use crate::ar_reader::{
+    parse_bsd_name, parse_member_header, MemberHeader, AR_HEADER_LEN, AR_MAGIC,
+};
+use crate::debian_binary::{parse_debian_binary_contents, DebianBinaryVersion};
+use crate::{ArMember, Compression, Error, ReadState, Result};
+
+/// The tar archive type returned by [`AsyncDebPkg::control`] and
+/// [`AsyncDebPkg::data`]
+pub type AsyncTarArchive<'a> = tokio_tar::Archive<Box<dyn AsyncRead + Unpin + Send + 'a>>;
+
+/// An async counterpart of [`DebPkg`](crate::DebPkg) reading from a
+/// `tokio::io::AsyncRead`, requires the `async` feature
+///
+/// Like `DebPkg`, the package is read in a single forward pass so the control
+/// archive must be read before the data archive.
+pub struct AsyncDebPkg<R: AsyncRead + Unpin + Send> {
+    /// How far we've read through the debian package
+    state: ReadState,
+
+    /// The major and minor fomat version of the debian package
+    format_version: DebianBinaryVersion,
+
+    /// The reader positioned inside the current ar member
+    reader: R,
+
+    /// The bytes left in the current ar member
+    remaining: u64,
+
+    /// The padding byte following the current ar member, if any
+    padding: u64,
+
+    /// The members starting with `_` read so far
+    extra_members: Vec<ArMember>,
+}
+
+// Reads at most `remaining` bytes from the current ar member, failing with
+// UnexpectedEof if the package ends before them
+struct MemberReader<'a, R: AsyncRead + Unpin> {
+    reader: &'a mut R,
+    remaining: &'a mut u64,
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for MemberReader<'_, R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        let max = std::cmp::min(buf.remaining() as u64, *this.remaining) as usize;
+        if max == 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        let mut limited = ReadBuf::new(buf.initialize_unfilled_to(max));
+        match Pin::new(&mut *this.reader).poll_read(cx, &mut limited) {
+            Poll::Ready(Ok(())) => {
+                let read = limited.filled().len();
+                if read == 0 {
+                    return Poll::Ready(Err(std::io::ErrorKind::UnexpectedEof.into()));
+                }
+                *this.remaining -= read as u64;
+                buf.advance(read);
+                Poll::Ready(Ok(()))
+            }
+            other => other,
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin + Send> AsyncDebPkg<R> {
+    /// Parses the ar header and `debian-binary` member of a debian package
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - A type which implements `tokio::io::AsyncRead` and is
+    ///   formatted as an ar archive
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn run() {
+    /// use debpkg::{AsyncDebPkg, Control};
+    /// let file = tokio::fs::File::open("test.deb").await.unwrap();
+    /// let mut pkg = AsyncDebPkg::parse(file).await.unwrap();
+    /// let control_tar = pkg.control().await.unwrap();
+    /// let control = Control::extract_async(control_tar).await.unwrap();
+    /// println!("Package Name: {}", control.name());
+    /// # }
+    /// ```
+    pub async fn parse(mut reader: R) -> Result<AsyncDebPkg<R>> {
+        let mut magic = [0; AR_MAGIC.len()];
+        reader.read_exact(&mut magic).await?;
+        if &magic != AR_MAGIC {
+            return Err(Error::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "invalid ar archive signature",
+            )));
+        }
+
+        let mut pkg = AsyncDebPkg {
+            state: ReadState::Opened,
+            format_version: DebianBinaryVersion { major: 0, minor: 0 },
+            reader,
+            remaining: 0,
+            padding: 0,
+            extra_members: Vec::new(),
+        };
+
+        let identifier = match pkg.next_member().await? {
+            Some(identifier) => identifier,
+            None => return Err(Error::MissingDebianBinary),
+        };
+        if identifier != "debian-binary" {
+            return Err(Error::MissingDebianBinary);
+        }
+        let mut contents = Vec::new();
+        pkg.member().read_to_end(&mut contents).await?;
+        pkg.format_version = parse_debian_binary_contents(&mut &contents[..])?;
+
+        Ok(pkg)
+    }
+
+    /// Returns the format version of the binary debian package
+    pub fn format_version(&self) -> (u32, u32) {
+        (self.format_version.major, self.format_version.minor)
+    }
+
+    /// Returns the control tar
+    pub async fn control(&mut self) -> Result<AsyncTarArchive<'_>> {
+        match self.state {
+            ReadState::Opened => {
+                if self.next_package_member().await?.is_none() {
+                    return Err(Error::MissingControlArchive);
+                }
+                self.state = ReadState::ControlRead;
+                self.member_tar().await
+            }
+            ReadState::ControlRead | ReadState::DataRead => Err(Error::ControlAlreadyRead),
+        }
+    }
+
+    /// Returns the data tar, skipping over the control tar if it has not
+    /// been read
+    pub async fn data(&mut self) -> Result<AsyncTarArchive<'_>> {
+        match self.state {
+            ReadState::Opened | ReadState::ControlRead => {
+                if let ReadState::Opened = self.state {
+                    if self.next_package_member().await?.is_none() {
+                        return Err(Error::MissingControlArchive);
+                    }
+                }
+                if self.next_package_member().await?.is_none() {
+                    return Err(Error::MissingDataArchive);
+                }
+                self.state = ReadState::DataRead;
+                self.member_tar().await
+            }
+            ReadState::DataRead => Err(Error::DataAlreadyRead),
+        }
+    }
+
+    /// Returns the members whose names start with `_`, such as signatures
+    ///
+    /// This reads through the rest of the package, so the control and data
+    /// tars can not be read afterwards.
+    pub async fn extra_members(&mut self) -> Result<&[ArMember]> {
+        self.state = ReadState::DataRead;
+        while let Some(name) = self.next_member().await? {
+            if name.starts_with('_') {
+                let member = self.read_member(name).await?;
+                self.extra_members.push(member);
+            }
+        }
+        Ok(&self.extra_members)
+    }
+
+    // Advances to the next member that is not an extra `_` member, keeping
+    // the contents of any extra members skipped over
+    async fn next_package_member(&mut self) -> Result<Option<String>> {
+        while let Some(name) = self.next_member().await? {
+            if !name.starts_with('_') {
+                return Ok(Some(name));
+            }
+            let member = self.read_member(name).await?;
+            self.extra_members.push(member);
+        }
+        Ok(None)
+    }
+
+    async fn read_member(&mut self, name: String) -> Result<ArMember> {
+        let mut contents = Vec::new();
+        self.member().read_to_end(&mut contents).await?;
+        Ok(ArMember { name, contents })
+    }
+
+    fn member(&mut self) -> MemberReader<'_, R> {
+        MemberReader {
+            reader: &mut self.reader,
+            remaining: &mut self.remaining,
+        }
+    }
+
+    // Skips whatever is left of the current member, then reads the next ar
+    // header and returns its name. Returns None at the end of the
+    // archive.
+    async fn next_member(&mut self) -> Result<Option<String>> {
+        tokio::io::copy(&mut self.member(), &mut tokio::io::sink()).await?;
+        if self.padding != 0 {
+            self.padding = 0;
+            // Some writers omit the padding of the last member
+            if self.reader.read(&mut [0]).await? == 0 {
+                return Ok(None);
+            }
+        }
+
+        let mut header = [0; AR_HEADER_LEN];
+        let mut read = 0;
+        while read < header.len() {
+            match self.reader.read(&mut header[read..]).await? {
+                0 if read == 0 => return Ok(None),
+                0 => {
+                    return Err(Error::Io(std::io::ErrorKind::UnexpectedEof.into()));
+                }
+                n => read += n,
+            }
+        }
+        let MemberHeader {
+            mut name,
+            size,
+            name_len,
+        } = parse_member_header(&header)?;
+        // Members are padded to an even size
+        self.remaining = size;
+        self.padding = size % 2;
+
+        if name_len != 0 {
+            let mut bsd_name = vec![0; name_len as usize];
+            self.member().read_exact(&mut bsd_name).await?;
+            name = parse_bsd_name(&bsd_name);
+        }
+        Ok(Some(name))
+    }
+
+    async fn member_tar(&mut self) -> Result<AsyncTarArchive<'_>> {
+        let mut member = self.member();
+        let mut first_1kb = vec![];
+        (&mut member).take(1024).read_to_end(&mut first_1kb).await?;
+
+        let compression = Compression::detect(&first_1kb)?;
+
+        let entry = Cursor::new(first_1kb).chain(member);
+
+        match compression {
+            Compression::None => {
+                let entry: Box<dyn AsyncRead + Unpin + Send> = Box::new(entry);
+                Ok(tokio_tar::Archive::new(entry))
+            }
+            Compression::Gzip => {
+                #[cfg(feature = "gzip")]
+                {
+                    let gz: Box<dyn AsyncRead + Unpin + Send> =
+                        Box::new(async_compression::tokio::bufread::GzipDecoder::new(
+                            tokio::io::BufReader::new(entry),
+                        ));
+                    Ok(tokio_tar::Archive::new(gz))
+                }
+                #[cfg(not(feature = "gzip"))]
+                {
+                    Err(Error::UnconfiguredFileFormat("gzip".to_string()))
+                }
+            }
+            Compression::Xz => {
+                #[cfg(feature = "xz")]
+                {
+                    let mut decoder = async_compression::tokio::bufread::XzDecoder::new(
+                        tokio::io::BufReader::new(entry),
+                    );
+                    decoder.multiple_members(true);
+                    let xz: Box<dyn AsyncRead + Unpin + Send> = Box::new(decoder);
+                    Ok(tokio_tar::Archive::new(xz))
+                }
+                #[cfg(not(feature = "xz"))]
+                {
+                    Err(Error::UnconfiguredFileFormat("xz".to_string()))
+                }
+            }
+            Compression::Bzip2 => {
+                #[cfg(feature = "bzip2")]
+                {
+                    let bz2: Box<dyn AsyncRead + Unpin + Send> =
+                        Box::new(async_compression::tokio::bufread::BzDecoder::new(
+                            tokio::io::BufReader::new(entry),
+                        ));
+                    Ok(tokio_tar::Archive::new(bz2))
+                }
+                #[cfg(not(feature = "bzip2"))]
+                {
+                    Err(Error::UnconfiguredFileFormat("bzip2".to_string()))
+                }
+            }
+            Compression::Zstd => {
+                #[cfg(feature = "zstd")]
+                {
+                    let zstd: Box<dyn AsyncRead + Unpin + Send> =
+                        Box::new(async_compression::tokio::bufread::ZstdDecoder::new(
+                            tokio::io::BufReader::new(entry),
+                        ));
+                    Ok(tokio_tar::Archive::new(zstd))
+                }
+                #[cfg(not(feature = "zstd"))]
+                {
+                    Err(Error::UnconfiguredFileFormat("zstd".to_string()))
+                }
+            }
+        }
+    }
+}
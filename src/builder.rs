@@ -0,0 +1,410 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+
+use crate::compression::Encoder;
+use crate::{Compression, Control, Error, Result};
+
+use log::warn;
+
+/// The maintainer scripts dpkg knows how to run
+pub(crate) const MAINTAINER_SCRIPTS: &[&str] =
+    &["preinst", "postinst", "prerm", "postrm", "config"];
+
+#[derive(Debug)]
+enum DataEntry {
+    Directory { mode: u32 },
+    File { contents: Vec<u8>, mode: u32 },
+    DiskFile { source: PathBuf, mode: u32 },
+    Symlink { target: PathBuf },
+}
+
+/// Builds a binary debian package
+///
+/// The package is written as a version 2.0 ar archive containing
+/// `debian-binary`, `control.tar` and `data.tar`. Parent directories of any
+/// added entry are created automatically. All entries are owned by root and
+/// use the same modification time, which makes the output reproducible.
+///
+/// # Example
+///
+/// ```no_run
+/// use debpkg::{Control, DebPkgBuilder};
+/// let control = Control::parse(&b"Package: hello\nVersion: 1.0\nArchitecture: all"[..]).unwrap();
+/// let file = std::fs::File::create("hello_1.0_all.deb").unwrap();
+/// DebPkgBuilder::new(control)
+///     .add_file("usr/share/hello/greeting", "hello world\n", 0o644)
+///     .maintainer_script("postinst", "#!/bin/sh\nset -e\n")
+///     .build(file)
+///     .unwrap();
+/// ```
+#[derive(Debug)]
+pub struct DebPkgBuilder {
+    control: Control,
+    control_files: BTreeMap<String, (Vec<u8>, u32)>,
+    data: BTreeMap<PathBuf, DataEntry>,
+    control_compression: Compression,
+    data_compression: Compression,
+    mtime: u64,
+}
+
+impl DebPkgBuilder {
+    /// Creates a builder for a package described by `control`
+    pub fn new(control: Control) -> DebPkgBuilder {
+        DebPkgBuilder {
+            control,
+            control_files: BTreeMap::new(),
+            data: BTreeMap::new(),
+            control_compression: Compression::default(),
+            data_compression: Compression::default(),
+            mtime: 0,
+        }
+    }
+
+    /// Sets the compression of the control tar
+    ///
+    /// Note that dpkg does not accept a bzip2 compressed control tar.
+    pub fn control_compression(&mut self, compression: Compression) -> &mut Self {
+        self.control_compression = compression;
+        self
+    }
+
+    /// Sets the compression of the data tar
+    pub fn data_compression(&mut self, compression: Compression) -> &mut Self {
+        self.data_compression = compression;
+        self
+    }
+
+    /// Sets the modification time, in seconds since the unix epoch, of every
+    /// member and entry in the package. Defaults to 0.
+    pub fn mtime(&mut self, mtime: u64) -> &mut Self {
+        self.mtime = mtime;
+        self
+    }
+
+    /// Adds a maintainer script (`preinst`, `postinst`, `prerm`, `postrm` or
+    /// `config`) to the control tar with mode 0755
+    pub fn maintainer_script<C: Into<Vec<u8>>>(&mut self, name: &str, contents: C) -> &mut Self {
+        if !MAINTAINER_SCRIPTS.contains(&name) {
+            warn!("{name} is not a maintainer script dpkg will run");
+        }
+        self.control_file(name, contents, 0o755)
+    }
+
+    /// Adds an arbitrary file, such as `conffiles` or `triggers`, to the
+    /// control tar. The `control` file itself is always generated from the
+    /// `Control` given to the builder.
+    pub fn control_file<C: Into<Vec<u8>>>(
+        &mut self,
+        name: &str,
+        contents: C,
+        mode: u32,
+    ) -> &mut Self {
+        self.control_files
+            .insert(name.to_owned(), (contents.into(), mode));
+        self
+    }
+
+    /// Adds a regular file with the given contents to the data tar
+    pub fn add_file<P: AsRef<Path>, C: Into<Vec<u8>>>(
+        &mut self,
+        path: P,
+        contents: C,
+        mode: u32,
+    ) -> &mut Self {
+        let entry = DataEntry::File {
+            contents: contents.into(),
+            mode,
+        };
+        self.data.insert(path.as_ref().to_owned(), entry);
+        self
+    }
+
+    /// Adds a directory to the data tar
+    pub fn add_dir<P: AsRef<Path>>(&mut self, path: P, mode: u32) -> &mut Self {
+        self.data
+            .insert(path.as_ref().to_owned(), DataEntry::Directory { mode });
+        self
+    }
+
+    /// Adds a symbolic link pointing at `target` to the data tar
+    pub fn add_symlink<P: AsRef<Path>, T: AsRef<Path>>(&mut self, path: P, target: T) -> &mut Self {
+        let entry = DataEntry::Symlink {
+            target: target.as_ref().to_owned(),
+        };
+        self.data.insert(path.as_ref().to_owned(), entry);
+        self
+    }
+
+    /// Adds every file, directory and symlink under `source` on disk to the
+    /// data tar, rooted at `dest`
+    ///
+    /// Files are only read when the package is built. On unix the file
+    /// permissions are preserved, elsewhere files get mode 0644 and
+    /// directories 0755. FIFOs, sockets and device nodes are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The directory on disk to add
+    ///
+    /// * `dest` - Where `source` should end up in the installed package, e.g.
+    ///   `/` to add a whole staging root
+    pub fn add_dir_all<P: AsRef<Path>, Q: AsRef<Path>>(
+        &mut self,
+        source: P,
+        dest: Q,
+    ) -> Result<&mut Self> {
+        let source = source.as_ref();
+        let dest = dest.as_ref();
+        for entry in std::fs::read_dir(source)? {
+            let entry = entry?;
+            let path = entry.path();
+            let dest = dest.join(entry.file_name());
+            let metadata = std::fs::symlink_metadata(&path)?;
+            let file_type = metadata.file_type();
+            if file_type.is_symlink() {
+                self.add_symlink(&dest, std::fs::read_link(&path)?);
+            } else if file_type.is_dir() {
+                self.add_dir(&dest, mode_of(&metadata, 0o755));
+                self.add_dir_all(&path, &dest)?;
+            } else if file_type.is_file() {
+                let entry = DataEntry::DiskFile {
+                    source: path,
+                    mode: mode_of(&metadata, 0o644),
+                };
+                self.data.insert(dest, entry);
+            } else {
+                // Opening a FIFO would block until something writes to it
+                warn!("skipping {}, which is not a regular file", path.display());
+            }
+        }
+        Ok(self)
+    }
+
+    /// Writes the debian package to `writer`
+    ///
+    /// The control and data tars are compressed into temporary files, so
+    /// the package is never held in memory. Returns `writer` once the
+    /// package has been fully written.
+    pub fn build<W: Write>(&self, writer: W) -> Result<W> {
+        let control_tar =
+            self.control_tar(self.control_compression.encoder(tempfile::tempfile()?)?)?;
+        let data_tar = self.data_tar(self.data_compression.encoder(tempfile::tempfile()?)?)?;
+
+        let mut archive = ar::Builder::new(writer);
+        self.append_member(
+            &mut archive,
+            "debian-binary",
+            std::io::Cursor::new(b"2.0\n"),
+        )?;
+        let name = format!("control.tar{}", self.control_compression.extension());
+        self.append_member(&mut archive, &name, control_tar)?;
+        let name = format!("data.tar{}", self.data_compression.extension());
+        self.append_member(&mut archive, &name, data_tar)?;
+
+        Ok(archive.into_inner()?)
+    }
+
+    fn append_member<W: Write, R: Read + Seek>(
+        &self,
+        archive: &mut ar::Builder<W>,
+        name: &str,
+        mut contents: R,
+    ) -> Result<()> {
+        let size = contents.seek(SeekFrom::End(0))?;
+        contents.rewind()?;
+        let mut header = ar::Header::new(name.as_bytes().to_vec(), size);
+        header.set_mode(0o100644);
+        header.set_mtime(self.mtime);
+        archive.append(&header, contents)?;
+        Ok(())
+    }
+
+    fn header(&self, entry_type: tar::EntryType, mode: u32, size: u64) -> Result<tar::Header> {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(entry_type);
+        header.set_mode(mode);
+        header.set_size(size);
+        header.set_mtime(self.mtime);
+        header.set_uid(0);
+        header.set_gid(0);
+        header.set_username("root")?;
+        header.set_groupname("root")?;
+        Ok(header)
+    }
+
+    // Writes the control tar through `encoder` into a temporary file
+    fn control_tar(&self, encoder: Encoder<File>) -> Result<File> {
+        let mut tar = tar::Builder::new(encoder);
+
+        let mut header = self.header(tar::EntryType::Directory, 0o755, 0)?;
+        append(&mut tar, &mut header, Path::new(""), std::io::empty())?;
+
+        let mut control = Vec::new();
+        self.control.write_to(&mut control)?;
+        let mut files = vec![("control", (control, 0o644))];
+        files.extend(
+            self.control_files
+                .iter()
+                .filter(|(name, _)| name.as_str() != "control")
+                .map(|(name, (contents, mode))| (name.as_str(), (contents.clone(), *mode))),
+        );
+
+        for (name, (contents, mode)) in files {
+            if name.is_empty() || name.contains('/') {
+                return Err(Error::InvalidPath(PathBuf::from(name)));
+            }
+            let size = u64::try_from(contents.len()).unwrap();
+            let mut header = self.header(tar::EntryType::Regular, mode, size)?;
+            append(&mut tar, &mut header, Path::new(name), &contents[..])?;
+        }
+
+        tar.into_inner()?.finish()
+    }
+
+    // Writes the data tar through `encoder` into a temporary file
+    fn data_tar(&self, encoder: Encoder<File>) -> Result<File> {
+        // Normalize every path and fill in any missing parent directories.
+        // The BTreeMap keeps parents ahead of their children.
+        let mut entries: BTreeMap<PathBuf, &DataEntry> = BTreeMap::new();
+        const IMPLICIT_DIR: DataEntry = DataEntry::Directory { mode: 0o755 };
+        for (path, entry) in &self.data {
+            let path = normalize(path)?;
+            let mut parent = path.parent();
+            while let Some(dir) = parent {
+                if dir.as_os_str().is_empty() {
+                    break;
+                }
+                entries.entry(dir.to_owned()).or_insert(&IMPLICIT_DIR);
+                parent = dir.parent();
+            }
+            entries.insert(path, entry);
+        }
+
+        let mut tar = tar::Builder::new(encoder);
+        let mut header = self.header(tar::EntryType::Directory, 0o755, 0)?;
+        append(&mut tar, &mut header, Path::new(""), std::io::empty())?;
+
+        for (path, entry) in entries {
+            match entry {
+                DataEntry::Directory { mode } => {
+                    let mut header = self.header(tar::EntryType::Directory, *mode, 0)?;
+                    append(&mut tar, &mut header, &path, std::io::empty())?;
+                }
+                DataEntry::File { contents, mode } => {
+                    let size = u64::try_from(contents.len()).unwrap();
+                    let mut header = self.header(tar::EntryType::Regular, *mode, size)?;
+                    append(&mut tar, &mut header, &path, &contents[..])?;
+                }
+                DataEntry::DiskFile { source, mode } => {
+                    let file = std::fs::File::open(source)?;
+                    let size = file.metadata()?.len();
+                    let mut header = self.header(tar::EntryType::Regular, *mode, size)?;
+                    append(&mut tar, &mut header, &path, file)?;
+                }
+                DataEntry::Symlink { target } => {
+                    let mut header = self.header(tar::EntryType::Symlink, 0o777, 0)?;
+                    let name = entry_name(&path, &header);
+                    if name.len() <= 100 && header.set_link_name(target).is_ok() {
+                        header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+                        header.set_cksum();
+                        tar.append(&header, std::io::empty())?;
+                    } else {
+                        tar.append_link(&mut header, &path, target)?;
+                    }
+                }
+            }
+        }
+
+        tar.into_inner()?.finish()
+    }
+}
+
+// Names entries the way dpkg-deb does, e.g. `./usr/bin/` for a directory
+fn entry_name(path: &Path, header: &tar::Header) -> String {
+    let mut name = format!("./{}", path.display());
+    if header.entry_type().is_dir() && !name.ends_with('/') {
+        name.push('/');
+    }
+    name
+}
+
+// Appends an entry named after `path` relative to the package root. The tar
+// crate strips the leading `./` dpkg-deb uses, so short names are written
+// directly and only names needing a GNU long name fall back to the tar crate.
+fn append<W: Write, R: Read>(
+    tar: &mut tar::Builder<W>,
+    header: &mut tar::Header,
+    path: &Path,
+    data: R,
+) -> Result<()> {
+    let name = entry_name(path, header);
+    if name.len() <= 100 {
+        header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+        header.set_cksum();
+        tar.append(header, data)?;
+    } else {
+        tar.append_data(header, path, data)?;
+    }
+    Ok(())
+}
+
+// Strips any leading `/` or `./` from a data path and rejects paths which
+// would escape the root
+pub(crate) fn normalize(path: &Path) -> Result<PathBuf> {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::RootDir | Component::CurDir => (),
+            Component::Normal(part) => normalized.push(part),
+            Component::ParentDir | Component::Prefix(_) => {
+                return Err(Error::InvalidPath(path.to_owned()))
+            }
+        }
+    }
+    if normalized.as_os_str().is_empty() {
+        return Err(Error::InvalidPath(path.to_owned()));
+    }
+    Ok(normalized)
+}
+
+#[cfg(unix)]
+fn mode_of(metadata: &std::fs::Metadata, _default: u32) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o7777
+}
+
+#[cfg(not(unix))]
+fn mode_of(_metadata: &std::fs::Metadata, default: u32) -> u32 {
+    default
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+
+    #[test]
+    fn normalize_strips_root_and_curdir() {
+        assert_eq!(
+            normalize(Path::new("/usr/./bin/foo")).unwrap(),
+            Path::new("usr/bin/foo")
+        );
+        assert_eq!(normalize(Path::new("./etc")).unwrap(), Path::new("etc"));
+    }
+
+    #[test]
+    fn normalize_rejects_parent_dir() {
+        assert_matches!(
+            normalize(Path::new("usr/../../etc/passwd")),
+            Err(Error::InvalidPath(_))
+        );
+    }
+
+    #[test]
+    fn normalize_rejects_empty_path() {
+        assert_matches!(normalize(Path::new("/")), Err(Error::InvalidPath(_)));
+    }
+}
@@ -0,0 +1,306 @@
+use std::convert::TryFrom;
+use std::io::Read;
+use std::time::SystemTime;
+
+use crate::date::{parse_rfc2822, system_time};
+use crate::deb822::read_signed_paragraphs;
+use crate::dsc::{binary_list, field_rows, file_checksums, parse_size};
+use crate::{Error, FileChecksum, Paragraph, Result};
+
+fn invalid(msg: String) -> Error {
+    Error::InvalidChangesFile(msg)
+}
+
+/// A file listed in the `Files` field of a `.changes` file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangesFile {
+    /// Lowercase hex MD5 digest of the file
+    pub md5: String,
+
+    /// Size of the file in bytes
+    pub size: u64,
+
+    /// Archive section of the file, e.g. `devel` or `contrib/net`
+    pub section: String,
+
+    /// Priority of the file, e.g. `optional`
+    pub priority: String,
+
+    /// Name of the file, relative to the `.changes` file
+    pub name: String,
+}
+
+/// A parsed Debian upload control (`.changes`) file
+#[derive(Debug, Clone)]
+pub struct Changes {
+    paragraph: Paragraph,
+    date: SystemTime,
+    binary: Vec<String>,
+    files: Vec<ChangesFile>,
+    checksums_sha1: Vec<FileChecksum>,
+    checksums_sha256: Vec<FileChecksum>,
+    checksums_sha512: Vec<FileChecksum>,
+}
+
+impl Changes {
+    /// Parses a `.changes` file, removing its cleartext signature if
+    /// present. The signature is not verified.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let file = std::fs::File::open("hello_2.10-3_amd64.changes").unwrap();
+    /// let changes = debpkg::Changes::parse(file).unwrap();
+    /// println!("{} {} {}", changes.source(), changes.version(), changes.distribution());
+    /// for file in changes.files() {
+    ///     println!("{} {}", file.section, file.name);
+    /// }
+    /// ```
+    pub fn parse<R: Read>(reader: R) -> Result<Changes> {
+        let mut paragraphs = read_signed_paragraphs(reader)?;
+        if paragraphs.len() > 1 {
+            return Err(invalid(
+                ".changes file has more than one paragraph".to_owned(),
+            ));
+        }
+        match paragraphs.pop() {
+            Some(paragraph) => Changes::try_from(paragraph),
+            None => Err(invalid("empty .changes file".to_owned())),
+        }
+    }
+
+    /// Returns the `Format` field, e.g. `1.8`
+    pub fn format(&self) -> &str {
+        self.paragraph.get("Format").unwrap()
+    }
+
+    /// Returns the `Date` field
+    pub fn date(&self) -> SystemTime {
+        self.date
+    }
+
+    /// Returns the source package name, without any version in parentheses
+    pub fn source(&self) -> &str {
+        let source = self.paragraph.get("Source").unwrap();
+        source.split_whitespace().next().unwrap_or(source)
+    }
+
+    /// Returns the binary packages listed in the `Binary` field
+    pub fn binary(&self) -> &[String] {
+        &self.binary
+    }
+
+    /// Returns the architectures listed in the `Architecture` field, which
+    /// include `source` for sourceful uploads
+    pub fn architecture(&self) -> Vec<&str> {
+        self.paragraph
+            .get("Architecture")
+            .unwrap()
+            .split_whitespace()
+            .collect()
+    }
+
+    /// Returns the version of the upload
+    pub fn version(&self) -> &str {
+        self.paragraph.get("Version").unwrap()
+    }
+
+    /// Returns the target suite of the upload, e.g. `unstable`
+    pub fn distribution(&self) -> &str {
+        self.paragraph.get("Distribution").unwrap()
+    }
+
+    /// Returns the `Urgency` field, e.g. `medium`
+    pub fn urgency(&self) -> Option<&str> {
+        self.paragraph.get("Urgency")
+    }
+
+    /// Returns the `Maintainer` field
+    pub fn maintainer(&self) -> Option<&str> {
+        self.paragraph.get("Maintainer")
+    }
+
+    /// Returns the `Changed-By` field, the author of the changelog entry
+    pub fn changed_by(&self) -> Option<&str> {
+        self.paragraph.get("Changed-By")
+    }
+
+    /// Returns the bug numbers listed in the `Closes` field
+    pub fn closes(&self) -> Vec<&str> {
+        self.paragraph
+            .get("Closes")
+            .map(|value| value.split_whitespace().collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the changelog entries from the `Changes` field, without its
+    /// empty first line
+    pub fn changes(&self) -> Option<&str> {
+        self.paragraph.continuation("Changes")
+    }
+
+    /// Returns the files listed in the `Files` field
+    pub fn files(&self) -> &[ChangesFile] {
+        &self.files
+    }
+
+    /// Returns the files listed in the `Checksums-Sha1` field
+    pub fn checksums_sha1(&self) -> &[FileChecksum] {
+        &self.checksums_sha1
+    }
+
+    /// Returns the files listed in the `Checksums-Sha256` field
+    pub fn checksums_sha256(&self) -> &[FileChecksum] {
+        &self.checksums_sha256
+    }
+
+    /// Returns the files listed in the `Checksums-Sha512` field
+    pub fn checksums_sha512(&self) -> &[FileChecksum] {
+        &self.checksums_sha512
+    }
+
+    /// Returns field value based on field name if it exists
+    pub fn get(&self, field_name: &str) -> Option<&str> {
+        self.paragraph.get(field_name)
+    }
+
+    /// Returns all the fields of the `.changes` file
+    pub fn paragraph(&self) -> &Paragraph {
+        &self.paragraph
+    }
+}
+
+impl TryFrom<Paragraph> for Changes {
+    type Error = Error;
+
+    /// Parses the typed fields of a `.changes` paragraph. The `Format`,
+    /// `Date`, `Source`, `Architecture`, `Version`, `Distribution` and
+    /// `Files` fields are required.
+    fn try_from(paragraph: Paragraph) -> Result<Changes> {
+        for name in [
+            "Format",
+            "Date",
+            "Source",
+            "Architecture",
+            "Version",
+            "Distribution",
+            "Files",
+        ] {
+            if paragraph.get(name).is_none() {
+                return Err(invalid(format!("missing {name} field")));
+            }
+        }
+
+        let date = paragraph.get("Date").unwrap();
+        let date = parse_rfc2822(date)
+            .and_then(system_time)
+            .ok_or_else(|| invalid(format!("invalid Date '{date}'")))?;
+
+        let files = field_rows(&paragraph, "Files", 5, invalid)
+            .map(|row| {
+                let row = row?;
+                Ok(ChangesFile {
+                    md5: row[0].to_ascii_lowercase(),
+                    size: parse_size(row[1], "Files", invalid)?,
+                    section: row[2].to_owned(),
+                    priority: row[3].to_owned(),
+                    name: row[4].to_owned(),
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Changes {
+            date,
+            binary: binary_list(&paragraph),
+            files,
+            checksums_sha1: file_checksums(&paragraph, "Checksums-Sha1", invalid)?,
+            checksums_sha256: file_checksums(&paragraph, "Checksums-Sha256", invalid)?,
+            checksums_sha512: file_checksums(&paragraph, "Checksums-Sha512", invalid)?,
+            paragraph,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    const CHANGES: &str = "Format: 1.8
+Date: Sat, 26 Apr 2025 09:33:20 +0000
+Source: hello (2.10-3)
+Binary: hello hello-dbg
+Architecture: source amd64
+Version: 2.10-3
+Distribution: unstable
+Urgency: medium
+Maintainer: Santiago Vila <sanvila@debian.org>
+Changed-By: Santiago Vila <sanvila@debian.org>
+Description:
+ hello      - example package based on GNU hello
+Closes: 1000001 1000002
+Changes:
+ hello (2.10-3) unstable; urgency=medium
+ .
+   * Fix the build.
+Checksums-Sha256:
+ 31e066137a962676e89f69d1b65382de95a7ef7d914b8cb956f41ea72e0f516b 1496 hello_2.10-3.dsc
+Files:
+ 97b7ac2a7f2ca7e2a1b2d5ad6ab5b1c6 1496 devel optional hello_2.10-3.dsc
+ B5C0F9D1E2B9B9D0C2C1F8E8F2D1A1B3 53012 devel optional hello_2.10-3_amd64.deb
+";
+
+    #[test]
+    fn changes_parses() {
+        let changes = Changes::parse(CHANGES.as_bytes()).unwrap();
+        assert_eq!(changes.format(), "1.8");
+        assert_eq!(
+            changes.date(),
+            UNIX_EPOCH + Duration::from_secs(1_745_660_000)
+        );
+        assert_eq!(changes.source(), "hello");
+        assert_eq!(changes.binary(), ["hello", "hello-dbg"]);
+        assert_eq!(changes.architecture(), ["source", "amd64"]);
+        assert_eq!(changes.version(), "2.10-3");
+        assert_eq!(changes.distribution(), "unstable");
+        assert_eq!(changes.urgency(), Some("medium"));
+        assert_eq!(changes.closes(), ["1000001", "1000002"]);
+        assert_eq!(
+            changes.changes(),
+            Some("hello (2.10-3) unstable; urgency=medium\n\n  * Fix the build.")
+        );
+        assert_eq!(changes.files().len(), 2);
+        assert_eq!(changes.files()[1].md5, "b5c0f9d1e2b9b9d0c2c1f8e8f2d1a1b3");
+        assert_eq!(changes.files()[1].section, "devel");
+        assert_eq!(changes.files()[1].priority, "optional");
+        assert_eq!(changes.files()[1].name, "hello_2.10-3_amd64.deb");
+        assert_eq!(changes.checksums_sha256().len(), 1);
+        assert_eq!(changes.checksums_sha256()[0].size, 1496);
+    }
+
+    #[test]
+    fn missing_distribution_fails() {
+        let text = CHANGES.replace("Distribution: unstable\n", "");
+        let err = Changes::parse(text.as_bytes()).unwrap_err();
+        assert_matches!(err, Error::InvalidChangesFile(_));
+    }
+
+    #[test]
+    fn bad_date_fails() {
+        let text = CHANGES.replace("26 Apr", "26 Avr");
+        let err = Changes::parse(text.as_bytes()).unwrap_err();
+        assert_matches!(err, Error::InvalidChangesFile(_));
+
+        let text = CHANGES.replace("Apr 2025", "Apr 9999999999999");
+        let err = Changes::parse(text.as_bytes()).unwrap_err();
+        assert_matches!(err, Error::InvalidChangesFile(_));
+    }
+
+    #[test]
+    fn dsc_style_files_line_fails() {
+        let text = CHANGES.replace(" devel optional hello_2.10-3.dsc", " hello_2.10-3.dsc");
+        let err = Changes::parse(text.as_bytes()).unwrap_err();
+        assert_matches!(err, Error::InvalidChangesFile(_));
+    }
+}
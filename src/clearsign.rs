@@ -0,0 +1,210 @@
+use crate::{Error, Result};
+
+const SIGNED_MESSAGE_HEADER: &str = "-----BEGIN PGP SIGNED MESSAGE-----";
+const SIGNATURE_HEADER: &str = "-----BEGIN PGP SIGNATURE-----";
+const SIGNATURE_FOOTER: &str = "-----END PGP SIGNATURE-----";
+
+fn invalid(msg: &str) -> Error {
+    Error::InvalidSignature(msg.to_owned())
+}
+
+/// An OpenPGP cleartext signed message, such as an APT `InRelease` file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClearSigned {
+    hashes: Vec<String>,
+    body: String,
+    signature: String,
+}
+
+impl ClearSigned {
+    /// Removes the cleartext signature armour from a message, undoing any
+    /// dash escaping of the signed text
+    ///
+    /// The signature is only split off, not verified. Anything other than
+    /// blank lines outside of the armour is rejected, since APT would not
+    /// know whether it was signed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let text = std::fs::read_to_string("InRelease").unwrap();
+    /// let signed = debpkg::ClearSigned::parse(&text).unwrap();
+    /// let release = debpkg::Release::parse(signed.body().as_bytes()).unwrap();
+    /// ```
+    pub fn parse(text: &str) -> Result<ClearSigned> {
+        let mut lines = text.lines().map(|line| line.trim_end_matches('\r'));
+
+        match lines.by_ref().find(|line| !line.trim().is_empty()) {
+            Some(line) if line.trim_end() == SIGNED_MESSAGE_HEADER => (),
+            _ => return Err(invalid("missing cleartext signature header")),
+        }
+
+        let mut hashes = Vec::new();
+        loop {
+            let line = match lines.next() {
+                Some(line) => line,
+                None => return Err(invalid("missing signed text")),
+            };
+            if line.trim().is_empty() {
+                break;
+            }
+            match line.split_once(": ") {
+                Some(("Hash", value)) => {
+                    hashes.extend(value.split(',').map(|hash| hash.trim().to_owned()))
+                }
+                Some(_) => (),
+                None => return Err(invalid("invalid armour header")),
+            }
+        }
+
+        let mut body = String::new();
+        loop {
+            let line = match lines.next() {
+                Some(line) => line,
+                None => return Err(invalid("missing signature")),
+            };
+            if line.trim_end() == SIGNATURE_HEADER {
+                break;
+            }
+            match line.strip_prefix("- ") {
+                Some(line) => body.push_str(line),
+                None if line.starts_with('-') => return Err(invalid("invalid dash escape")),
+                None => body.push_str(line),
+            }
+            body.push('\n');
+        }
+
+        let mut signature = format!("{SIGNATURE_HEADER}\n");
+        loop {
+            let line = match lines.next() {
+                Some(line) => line,
+                None => return Err(invalid("unterminated signature")),
+            };
+            signature.push_str(line);
+            signature.push('\n');
+            if line.trim_end() == SIGNATURE_FOOTER {
+                break;
+            }
+        }
+
+        if lines.any(|line| !line.trim().is_empty()) {
+            return Err(invalid("unexpected text after signature"));
+        }
+
+        Ok(ClearSigned {
+            hashes,
+            body,
+            signature,
+        })
+    }
+
+    /// Returns the hash algorithms listed in the `Hash` armour headers
+    pub fn hashes(&self) -> &[String] {
+        &self.hashes
+    }
+
+    /// Returns the signed text
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    /// Returns the armoured signature block
+    pub fn signature(&self) -> &str {
+        &self.signature
+    }
+
+    /// Verifies a cleartext signed message against a keyring by running
+    /// `gpgv`. Requires the `gpgv` feature and `gpgv` to be installed.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The complete signed message
+    /// * `keyring` - The path of the keyring containing the trusted keys
+    #[cfg(feature = "gpgv")]
+    pub fn verify<P: AsRef<std::path::Path>>(text: &str, keyring: P) -> Result<ClearSigned> {
+        use std::io::Write;
+
+        let signed = ClearSigned::parse(text)?;
+        crate::gpgv::gpgv(keyring.as_ref(), None, |mut stdin| {
+            stdin.write_all(text.as_bytes())?;
+            Ok(())
+        })?;
+        Ok(signed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+
+    const SIGNED: &str = "-----BEGIN PGP SIGNED MESSAGE-----
+Hash: SHA512
+
+Origin: Debian
+Suite: stable
+- -dash escaped
+-----BEGIN PGP SIGNATURE-----
+
+iQIzBAEBCgAdFiEE
+=abcd
+-----END PGP SIGNATURE-----
+";
+
+    #[test]
+    fn clearsigned_parses() {
+        let signed = ClearSigned::parse(SIGNED).unwrap();
+        assert_eq!(signed.hashes(), ["SHA512"]);
+        assert_eq!(
+            signed.body(),
+            "Origin: Debian\nSuite: stable\n-dash escaped\n"
+        );
+        assert!(signed.signature().starts_with(SIGNATURE_HEADER));
+        assert!(signed
+            .signature()
+            .ends_with("-----END PGP SIGNATURE-----\n"));
+    }
+
+    #[test]
+    fn clearsigned_crlf_parses() {
+        let signed = ClearSigned::parse(&SIGNED.replace('\n', "\r\n")).unwrap();
+        assert_eq!(
+            signed.body(),
+            "Origin: Debian\nSuite: stable\n-dash escaped\n"
+        );
+    }
+
+    #[test]
+    fn unsigned_fails() {
+        let err = ClearSigned::parse("Origin: Debian\n").unwrap_err();
+        assert_matches!(err, Error::InvalidSignature(_));
+    }
+
+    #[test]
+    fn missing_signature_fails() {
+        let text = &SIGNED[..SIGNED.find(SIGNATURE_HEADER).unwrap()];
+        let err = ClearSigned::parse(text).unwrap_err();
+        assert_matches!(err, Error::InvalidSignature(_));
+    }
+
+    #[test]
+    fn unterminated_signature_fails() {
+        let text = &SIGNED[..SIGNED.find(SIGNATURE_FOOTER).unwrap()];
+        let err = ClearSigned::parse(text).unwrap_err();
+        assert_matches!(err, Error::InvalidSignature(_));
+    }
+
+    #[test]
+    fn trailing_text_fails() {
+        let text = format!("{SIGNED}\nSuite: unsigned\n");
+        let err = ClearSigned::parse(&text).unwrap_err();
+        assert_matches!(err, Error::InvalidSignature(_));
+    }
+
+    #[test]
+    fn unescaped_dash_fails() {
+        let text = SIGNED.replace("- -dash", "-dash");
+        let err = ClearSigned::parse(&text).unwrap_err();
+        assert_matches!(err, Error::InvalidSignature(_));
+    }
+}
@@ -0,0 +1,198 @@
+use std::io::Write;
+
+use crate::Result;
+
+/// The compression applied to the control or data tar of a debian package
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// A plain, uncompressed tar
+    None,
+
+    /// gzip compression, requires the `gzip` feature
+    Gzip,
+
+    /// xz compression, requires the `xz` feature
+    Xz,
+
+    /// bzip2 compression, requires the `bzip2` feature
+    Bzip2,
+
+    /// zstd compression, requires the `zstd` feature
+    Zstd,
+}
+
+impl Default for Compression {
+    /// Defaults to xz, like `dpkg-deb`, falling back to the first configured
+    /// compression if the `xz` feature is disabled
+    fn default() -> Self {
+        if cfg!(feature = "xz") {
+            Compression::Xz
+        } else if cfg!(feature = "gzip") {
+            Compression::Gzip
+        } else if cfg!(feature = "zstd") {
+            Compression::Zstd
+        } else if cfg!(feature = "bzip2") {
+            Compression::Bzip2
+        } else {
+            Compression::None
+        }
+    }
+}
+
+impl Compression {
+    /// Returns the file extension used for a tar with this compression,
+    /// including the leading dot. Empty for an uncompressed tar.
+    pub fn extension(&self) -> &'static str {
+        match *self {
+            Compression::None => "",
+            Compression::Gzip => ".gz",
+            Compression::Xz => ".xz",
+            Compression::Bzip2 => ".bz2",
+            Compression::Zstd => ".zst",
+        }
+    }
+
+    /// Detects the compression of a tar from its first bytes, which should
+    /// be at least the first 512 byte tar header when uncompressed
+    pub(crate) fn detect(header: &[u8]) -> Result<Compression> {
+        if infer::archive::is_tar(header) {
+            Ok(Compression::None)
+        } else if infer::archive::is_gz(header) {
+            Ok(Compression::Gzip)
+        } else if infer::archive::is_xz(header) {
+            Ok(Compression::Xz)
+        } else if infer::archive::is_bz2(header) {
+            Ok(Compression::Bzip2)
+        } else if infer::archive::is_zst(header) {
+            Ok(Compression::Zstd)
+        } else {
+            Err(crate::Error::UnknownEntryFormat)
+        }
+    }
+
+    /// Compresses `data` in memory
+    pub(crate) fn compress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut encoder = self.encoder(Vec::new())?;
+        encoder.write_all(data)?;
+        encoder.finish()
+    }
+
+    /// Returns a writer which compresses everything written to it into
+    /// `writer`
+    pub(crate) fn encoder<W: Write>(&self, writer: W) -> Result<Encoder<W>> {
+        match *self {
+            Compression::None => Ok(Encoder::None(writer)),
+            Compression::Gzip => {
+                #[cfg(feature = "gzip")]
+                {
+                    Ok(Encoder::Gzip(flate2::write::GzEncoder::new(
+                        writer,
+                        flate2::Compression::best(),
+                    )))
+                }
+                #[cfg(not(feature = "gzip"))]
+                {
+                    Err(crate::Error::UnconfiguredFileFormat("gzip".to_string()))
+                }
+            }
+            Compression::Xz => {
+                #[cfg(feature = "xz")]
+                {
+                    Ok(Encoder::Xz(liblzma::write::XzEncoder::new(writer, 6)))
+                }
+                #[cfg(not(feature = "xz"))]
+                {
+                    Err(crate::Error::UnconfiguredFileFormat("xz".to_string()))
+                }
+            }
+            Compression::Bzip2 => {
+                #[cfg(feature = "bzip2")]
+                {
+                    Ok(Encoder::Bzip2(bzip2::write::BzEncoder::new(
+                        writer,
+                        bzip2::Compression::best(),
+                    )))
+                }
+                #[cfg(not(feature = "bzip2"))]
+                {
+                    Err(crate::Error::UnconfiguredFileFormat("bzip2".to_string()))
+                }
+            }
+            Compression::Zstd => {
+                #[cfg(feature = "zstd")]
+                {
+                    Ok(Encoder::Zstd(zstd::stream::write::Encoder::new(writer, 0)?))
+                }
+                #[cfg(not(feature = "zstd"))]
+                {
+                    Err(crate::Error::UnconfiguredFileFormat("zstd".to_string()))
+                }
+            }
+        }
+    }
+}
+
+// A streaming compressor for one of the configured compressions
+pub(crate) enum Encoder<W: Write> {
+    None(W),
+
+    #[cfg(feature = "gzip")]
+    Gzip(flate2::write::GzEncoder<W>),
+
+    #[cfg(feature = "xz")]
+    Xz(liblzma::write::XzEncoder<W>),
+
+    #[cfg(feature = "bzip2")]
+    Bzip2(bzip2::write::BzEncoder<W>),
+
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::stream::write::Encoder<'static, W>),
+}
+
+impl<W: Write> Encoder<W> {
+    /// Writes any buffered data and the end of the compressed stream,
+    /// returning the inner writer
+    pub(crate) fn finish(self) -> Result<W> {
+        match self {
+            Encoder::None(writer) => Ok(writer),
+            #[cfg(feature = "gzip")]
+            Encoder::Gzip(encoder) => Ok(encoder.finish()?),
+            #[cfg(feature = "xz")]
+            Encoder::Xz(encoder) => Ok(encoder.finish()?),
+            #[cfg(feature = "bzip2")]
+            Encoder::Bzip2(encoder) => Ok(encoder.finish()?),
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(encoder) => Ok(encoder.finish()?),
+        }
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Encoder::None(writer) => writer.write(buf),
+            #[cfg(feature = "gzip")]
+            Encoder::Gzip(encoder) => encoder.write(buf),
+            #[cfg(feature = "xz")]
+            Encoder::Xz(encoder) => encoder.write(buf),
+            #[cfg(feature = "bzip2")]
+            Encoder::Bzip2(encoder) => encoder.write(buf),
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Encoder::None(writer) => writer.flush(),
+            #[cfg(feature = "gzip")]
+            Encoder::Gzip(encoder) => encoder.flush(),
+            #[cfg(feature = "xz")]
+            Encoder::Xz(encoder) => encoder.flush(),
+            #[cfg(feature = "bzip2")]
+            Encoder::Bzip2(encoder) => encoder.flush(),
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
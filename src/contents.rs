@@ -0,0 +1,343 @@
+use std::fmt;
+use std::io::{Read, Seek};
+use std::path::PathBuf;
+
+use crate::date::split_timestamp;
+use crate::{DebPkg, Result, SeekableDebPkg};
+
+// The minimum width of the owner and size columns, as in GNU tar
+const OWNER_SIZE_WIDTH: usize = 19;
+
+/// The type of an entry in the data archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    /// A regular file
+    Regular,
+
+    /// A hard link to an earlier entry
+    HardLink,
+
+    /// A symbolic link
+    Symlink,
+
+    /// A character device
+    CharDevice,
+
+    /// A block device
+    BlockDevice,
+
+    /// A directory
+    Directory,
+
+    /// A named pipe
+    Fifo,
+
+    /// Any other tar entry type
+    Other,
+}
+
+impl FileKind {
+    // The first column of `tar -tv` output
+    fn mode_char(self) -> char {
+        match self {
+            FileKind::Regular => '-',
+            FileKind::HardLink => 'h',
+            FileKind::Symlink => 'l',
+            FileKind::CharDevice => 'c',
+            FileKind::BlockDevice => 'b',
+            FileKind::Directory => 'd',
+            FileKind::Fifo => 'p',
+            FileKind::Other => '?',
+        }
+    }
+}
+
+impl From<tar::EntryType> for FileKind {
+    fn from(entry_type: tar::EntryType) -> FileKind {
+        match entry_type {
+            tar::EntryType::Regular | tar::EntryType::Continuous => FileKind::Regular,
+            tar::EntryType::Link => FileKind::HardLink,
+            tar::EntryType::Symlink => FileKind::Symlink,
+            tar::EntryType::Char => FileKind::CharDevice,
+            tar::EntryType::Block => FileKind::BlockDevice,
+            tar::EntryType::Directory => FileKind::Directory,
+            tar::EntryType::Fifo => FileKind::Fifo,
+            _ => FileKind::Other,
+        }
+    }
+}
+
+/// An entry of the data archive with its metadata, as listed by
+/// `dpkg-deb --contents`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileEntry {
+    /// The path as stored in the archive, usually starting with `./`
+    pub path: PathBuf,
+
+    /// The type of the entry
+    pub kind: FileKind,
+
+    /// The permission bits, including the setuid, setgid and sticky bits
+    pub mode: u32,
+
+    /// The numeric user id of the owner
+    pub uid: u64,
+
+    /// The numeric group id of the owner
+    pub gid: u64,
+
+    /// The user name of the owner, if the archive records one
+    pub user: Option<String>,
+
+    /// The group name of the owner, if the archive records one
+    pub group: Option<String>,
+
+    /// The size of the contents in bytes, zero for everything but regular
+    /// files
+    pub size: u64,
+
+    /// The modification time in seconds since the unix epoch
+    pub mtime: u64,
+
+    /// The target of a symlink, or the earlier entry a hard link refers to
+    pub link_target: Option<PathBuf>,
+
+    /// The major and minor numbers of a character or block device
+    pub device: Option<(u32, u32)>,
+}
+
+impl FileEntry {
+    fn from_entry<R: Read>(entry: &tar::Entry<R>) -> Result<FileEntry> {
+        let header = entry.header();
+        let kind = FileKind::from(header.entry_type());
+        let device = match kind {
+            FileKind::CharDevice | FileKind::BlockDevice => {
+                match (header.device_major()?, header.device_minor()?) {
+                    (Some(major), Some(minor)) => Some((major, minor)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        let name = |name: Option<&str>| name.filter(|name| !name.is_empty()).map(str::to_owned);
+        Ok(FileEntry {
+            path: entry.path()?.into_owned(),
+            kind,
+            mode: header.mode()? & 0o7777,
+            uid: header.uid()?,
+            gid: header.gid()?,
+            user: name(header.username().ok().flatten()),
+            group: name(header.groupname().ok().flatten()),
+            size: entry.size(),
+            mtime: header.mtime()?,
+            link_target: entry.link_name()?.map(|target| target.into_owned()),
+            device,
+        })
+    }
+
+    // The permissions column of `tar -tv` output, e.g. `-rwsr-xr-x`
+    fn mode_string(&self) -> String {
+        let mut mode = String::with_capacity(10);
+        mode.push(self.kind.mode_char());
+        let special = [(0o4000, 's'), (0o2000, 's'), (0o1000, 't')];
+        for (i, (special_bit, special_char)) in special.iter().enumerate() {
+            let bits = self.mode >> (6 - 3 * i);
+            mode.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+            mode.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+            let execute = bits & 0o1 != 0;
+            mode.push(match (self.mode & special_bit != 0, execute) {
+                (true, true) => *special_char,
+                (true, false) => special_char.to_ascii_uppercase(),
+                (false, true) => 'x',
+                (false, false) => '-',
+            });
+        }
+        mode
+    }
+
+    // Writes the entry as one line of `tar -tv` output, widening the owner
+    // and size columns when they do not fit
+    fn write_line<W: fmt::Write>(&self, w: &mut W, width: &mut usize) -> fmt::Result {
+        let user = self.user.clone().unwrap_or_else(|| self.uid.to_string());
+        let group = self.group.clone().unwrap_or_else(|| self.gid.to_string());
+        let size = match self.device {
+            Some((major, minor)) => format!("{major},{minor}"),
+            None => self.size.to_string(),
+        };
+        let used = user.len() + 1 + group.len() + 1 + size.len();
+        *width = (*width).max(used);
+        let ((year, month, day), (hour, minute, _)) = split_timestamp(self.mtime as i64);
+        write!(
+            w,
+            "{} {user}/{group} {size:>size_width$} {year}-{month:02}-{day:02} {hour:02}:{minute:02} {}",
+            self.mode_string(),
+            quote(&self.path.to_string_lossy()),
+            size_width = *width - used + size.len(),
+        )?;
+        match (self.kind, &self.link_target) {
+            (FileKind::Symlink, Some(target)) => {
+                write!(w, " -> {}", quote(&target.to_string_lossy()))
+            }
+            (FileKind::HardLink, Some(target)) => {
+                write!(w, " link to {}", quote(&target.to_string_lossy()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Formats the entry as a line of `dpkg-deb --contents` output, without the
+/// trailing newline. Use [`format_contents`] to format a whole listing with
+/// aligned columns.
+impl fmt::Display for FileEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut width = OWNER_SIZE_WIDTH;
+        self.write_line(f, &mut width)
+    }
+}
+
+// Escapes backslashes and control characters the way GNU tar does by default
+fn quote(name: &str) -> String {
+    let mut quoted = String::with_capacity(name.len());
+    for c in name.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '\t' => quoted.push_str("\\t"),
+            '\n' => quoted.push_str("\\n"),
+            c if c.is_control() => quoted.push_str(&format!("\\{:03o}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted
+}
+
+/// Formats entries exactly like `dpkg-deb --contents`, one line per entry
+///
+/// As with GNU tar, the owner and size column grows to fit the widest entry
+/// seen so far, so earlier lines are not realigned. Times are shown in UTC,
+/// which matches `dpkg-deb` run with `TZ=UTC`.
+///
+/// # Example
+///
+/// ```no_run
+/// use debpkg::DebPkg;
+/// let file = std::fs::File::open("test.deb").unwrap();
+/// let mut pkg = DebPkg::parse(file).unwrap();
+/// print!("{}", debpkg::format_contents(&pkg.contents().unwrap()));
+/// ```
+pub fn format_contents(entries: &[FileEntry]) -> String {
+    let mut listing = String::new();
+    let mut width = OWNER_SIZE_WIDTH;
+    for entry in entries {
+        // Writing to a String can not fail
+        entry.write_line(&mut listing, &mut width).unwrap();
+        listing.push('\n');
+    }
+    listing
+}
+
+fn list_entries<R: Read>(mut archive: tar::Archive<R>) -> Result<Vec<FileEntry>> {
+    let mut entries = Vec::new();
+    for entry in archive.entries()? {
+        entries.push(FileEntry::from_entry(&entry?)?);
+    }
+    Ok(entries)
+}
+
+impl<R: Read> DebPkg<R> {
+    /// Lists every entry of the data archive with its metadata, in archive
+    /// order
+    ///
+    /// The data archive is read, so this must be called before `data`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use debpkg::DebPkg;
+    /// let file = std::fs::File::open("test.deb").unwrap();
+    /// let mut pkg = DebPkg::parse(file).unwrap();
+    /// for entry in pkg.contents().unwrap() {
+    ///     println!("{} {:o} {}", entry.path.display(), entry.mode, entry.size);
+    /// }
+    /// ```
+    pub fn contents(&mut self) -> Result<Vec<FileEntry>> {
+        list_entries(self.data()?)
+    }
+}
+
+impl<R: Read + Seek> SeekableDebPkg<R> {
+    /// Lists every entry of the data archive with its metadata, in archive
+    /// order. See [`DebPkg::contents`].
+    pub fn contents(&mut self) -> Result<Vec<FileEntry>> {
+        list_entries(self.data()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, kind: FileKind, mode: u32, size: u64) -> FileEntry {
+        FileEntry {
+            path: PathBuf::from(path),
+            kind,
+            mode,
+            uid: 0,
+            gid: 0,
+            user: Some("root".to_owned()),
+            group: Some("root".to_owned()),
+            size,
+            mtime: 1_700_000_000,
+            link_target: None,
+            device: None,
+        }
+    }
+
+    #[test]
+    fn entries_format_like_tar() {
+        let mut link = entry("./usr/bin/sh", FileKind::Symlink, 0o777, 0);
+        link.link_target = Some(PathBuf::from("dash"));
+        let mut hard = entry("./usr/bin/su2", FileKind::HardLink, 0o4755, 0);
+        hard.link_target = Some(PathBuf::from("./usr/bin/su"));
+        let mut numeric = entry("./tmp/", FileKind::Directory, 0o1777, 0);
+        numeric.user = None;
+        numeric.uid = 1000;
+        let entries = [
+            entry("./", FileKind::Directory, 0o755, 0),
+            entry("./usr/bin/su", FileKind::Regular, 0o4755, 71_912),
+            link,
+            hard,
+            numeric,
+        ];
+        assert_eq!(
+            format_contents(&entries),
+            "drwxr-xr-x root/root         0 2023-11-14 22:13 ./
+-rwsr-xr-x root/root     71912 2023-11-14 22:13 ./usr/bin/su
+lrwxrwxrwx root/root         0 2023-11-14 22:13 ./usr/bin/sh -> dash
+hrwsr-xr-x root/root         0 2023-11-14 22:13 ./usr/bin/su2 link to ./usr/bin/su
+drwxrwxrwt 1000/root         0 2023-11-14 22:13 ./tmp/
+"
+        );
+    }
+
+    #[test]
+    fn wide_columns_grow() {
+        let mut wide = entry("./big", FileKind::Regular, 0o644, 12_345_678_901);
+        wide.user = Some("a-long-user".to_owned());
+        let mut device = entry("./dev/null", FileKind::CharDevice, 0o666, 0);
+        device.device = Some((1, 3));
+        let entries = [wide, device, entry("./x", FileKind::Regular, 0o2644, 1)];
+        assert_eq!(
+            format_contents(&entries),
+            "-rw-r--r-- a-long-user/root 12345678901 2023-11-14 22:13 ./big
+crw-rw-rw- root/root                1,3 2023-11-14 22:13 ./dev/null
+-rw-r-Sr-- root/root                  1 2023-11-14 22:13 ./x
+"
+        );
+    }
+
+    #[test]
+    fn names_are_quoted() {
+        assert_eq!(quote("a\\b\nc\x7f"), "a\\\\b\\nc\\177");
+    }
+}
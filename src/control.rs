@@ -1,20 +1,25 @@
+use std::convert::TryFrom;
+use std::fmt;
 use std::hash::{Hash, Hasher};
-use std::io::{BufRead, BufReader, Read};
+use std::io::{BufRead, BufReader, Read, Write};
 
-use crate::{Error, Result};
+use crate::{
+    parse_relationships, ControlParseError, ControlParseErrorKind, DebPkg, Dependency, Diagnostic,
+    Error, Result, Version,
+};
 
 use indexmap::{Equivalent, IndexMap};
 use log::warn;
 
 // Tag is used to represent the tag of a field in a debian control file. Tag
 // essentially creates a string which is case insensitive.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Tag(String);
 
 // UncasedStrRef is used to be able to search a hash map of tags without
 // creating a new String.
 #[derive(Debug)]
-struct UncasedStrRef<'a>(&'a str);
+pub(crate) struct UncasedStrRef<'a>(&'a str);
 
 impl<'a> UncasedStrRef<'a> {
     const fn new(s: &'a str) -> Self {
@@ -96,23 +101,499 @@ impl Equivalent<Tag> for UncasedStrRef<'_> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 enum FieldBody {
     Simple(String),
 
-    // Currently binary debian packages don't have any folded fields
-    #[allow(dead_code)]
     Folded(String),
 
     Multiline(String, String),
 }
 
-type Paragraph = IndexMap<Tag, FieldBody>;
-
 const DESCRIPTION: UncasedStrRef = UncasedStrRef::new("Description");
 const PACKAGE: UncasedStrRef = UncasedStrRef::new("Package");
 const VERSION: UncasedStrRef = UncasedStrRef::new("Version");
 
+// How the continuation lines of a field are stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FieldKind {
+    // A single line value, continuation lines are an error unless the
+    // parser folds unknown fields
+    Simple,
+
+    // Continuation lines are joined into a single line with spaces
+    Folded,
+
+    // Continuation lines are kept as separate lines
+    Multiline,
+}
+
+pub(crate) const fn field(
+    name: &'static str,
+    kind: FieldKind,
+) -> (UncasedStrRef<'static>, FieldKind) {
+    (UncasedStrRef::new(name), kind)
+}
+
+// The kinds of the well known fields of control files, APT indices, source
+// package descriptions and uploads. Any field not listed here is Simple.
+pub(crate) const FIELD_KINDS: &[(UncasedStrRef, FieldKind)] = &[
+    (DESCRIPTION, FieldKind::Multiline),
+    field("Changes", FieldKind::Multiline),
+    field("Conffiles", FieldKind::Multiline),
+    field("Files", FieldKind::Multiline),
+    field("Checksums-Sha1", FieldKind::Multiline),
+    field("Checksums-Sha256", FieldKind::Multiline),
+    field("Checksums-Sha512", FieldKind::Multiline),
+    field("Package-List", FieldKind::Multiline),
+    field("MD5Sum", FieldKind::Multiline),
+    field("SHA1", FieldKind::Multiline),
+    field("SHA256", FieldKind::Multiline),
+    field("SHA512", FieldKind::Multiline),
+    field("Binary", FieldKind::Folded),
+    field("Uploaders", FieldKind::Folded),
+    field("Depends", FieldKind::Folded),
+    field("Pre-Depends", FieldKind::Folded),
+    field("Recommends", FieldKind::Folded),
+    field("Suggests", FieldKind::Folded),
+    field("Breaks", FieldKind::Folded),
+    field("Conflicts", FieldKind::Folded),
+    field("Replaces", FieldKind::Folded),
+    field("Provides", FieldKind::Folded),
+    field("Enhances", FieldKind::Folded),
+    field("Built-Using", FieldKind::Folded),
+    field("Static-Built-Using", FieldKind::Folded),
+    field("Build-Depends", FieldKind::Folded),
+    field("Build-Depends-Indep", FieldKind::Folded),
+    field("Build-Depends-Arch", FieldKind::Folded),
+    field("Build-Conflicts", FieldKind::Folded),
+    field("Build-Conflicts-Indep", FieldKind::Folded),
+    field("Build-Conflicts-Arch", FieldKind::Folded),
+    field("Testsuite-Triggers", FieldKind::Folded),
+];
+
+// Returns the kind of a field, Simple if it is not a well known field
+pub(crate) fn field_kind(field_name: &str) -> FieldKind {
+    kind_in(FIELD_KINDS, field_name)
+}
+
+// Looks a field up in a table of field kinds, Simple if it is not listed. A
+// name ending in `*` matches every field starting with the rest of the name,
+// such as the translations `Description-de.UTF-8` of `Description`.
+pub(crate) fn kind_in(table: &[(UncasedStrRef, FieldKind)], field_name: &str) -> FieldKind {
+    let tag = UncasedStrRef::from(field_name);
+    table
+        .iter()
+        .find(|(name, _)| match name.0.strip_suffix('*') {
+            Some(prefix) => field_name
+                .get(..prefix.len())
+                .is_some_and(|start| start.eq_ignore_ascii_case(prefix)),
+            None => *name == tag,
+        })
+        .map_or(FieldKind::Simple, |(_, kind)| *kind)
+}
+
+/// A single paragraph of a deb822 formatted file, such as one stanza of an
+/// APT `Packages` index
+///
+/// Field names are case insensitive and the fields keep the order they were
+/// parsed or added in.
+#[derive(Debug, Default, Clone)]
+pub struct Paragraph {
+    fields: IndexMap<Tag, FieldBody>,
+}
+
+impl Paragraph {
+    /// Creates an empty paragraph
+    pub fn new() -> Paragraph {
+        Paragraph::default()
+    }
+
+    /// Returns field value based on field name if it exists. For multiline
+    /// fields this is only the first line.
+    ///
+    /// # Arguments
+    ///
+    /// * field_name - The field name. This string is case insensitve
+    pub fn get(&self, field_name: &str) -> Option<&str> {
+        match self.fields.get(&UncasedStrRef::from(field_name)) {
+            Some(FieldBody::Simple(value)) | Some(FieldBody::Folded(value)) => Some(value.as_str()),
+            Some(FieldBody::Multiline(value, _)) => Some(value.as_str()),
+            None => None,
+        }
+    }
+
+    /// Returns the continuation lines of a multiline field such as `SHA256`
+    /// joined with newlines. The single space starting each line is removed
+    /// and ` .` lines become empty lines. Returns None if the field is absent
+    /// or is not a multiline field.
+    ///
+    /// # Arguments
+    ///
+    /// * field_name - The field name. This string is case insensitve
+    pub fn continuation(&self, field_name: &str) -> Option<&str> {
+        match self.fields.get(&UncasedStrRef::from(field_name)) {
+            Some(FieldBody::Multiline(_, other)) => Some(other.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Returns an iterator to all the field names in the paragraph
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.fields.keys().map(|i| i.as_ref())
+    }
+
+    /// Returns the number of fields in the paragraph
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Returns true if the paragraph has no fields
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Sets the value of a field, replacing any existing value in place or
+    /// appending the field to the end of the paragraph
+    ///
+    /// See [`Control::set`] for how values containing newlines are stored.
+    pub fn set(&mut self, field_name: &str, value: &str) -> Result<()> {
+        let body = field_body(field_name, self.kind_of(field_name), value)?;
+        match self.fields.get_mut(&UncasedStrRef::from(field_name)) {
+            Some(existing) => *existing = body,
+            None => {
+                self.fields.insert(field_name.into(), body);
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets the value of a field and moves it directly after `after`, or to
+    /// the end of the paragraph if `after` is not present
+    pub fn insert_after(&mut self, after: &str, field_name: &str, value: &str) -> Result<()> {
+        let body = field_body(field_name, self.kind_of(field_name), value)?;
+        let tag = match self
+            .fields
+            .shift_remove_full(&UncasedStrRef::from(field_name))
+        {
+            Some((_, tag, _)) => tag,
+            None => field_name.into(),
+        };
+        match self.fields.get_index_of(&UncasedStrRef::from(after)) {
+            Some(index) => {
+                self.fields.shift_insert(index + 1, tag, body);
+            }
+            None => {
+                self.fields.insert(tag, body);
+            }
+        }
+        Ok(())
+    }
+
+    // Returns how a field's continuation lines are stored. A field already
+    // in the paragraph keeps the kind it was parsed with, which may come
+    // from a file specific table such as the one for copyright files.
+    fn kind_of(&self, field_name: &str) -> FieldKind {
+        match self.fields.get(&UncasedStrRef::from(field_name)) {
+            Some(FieldBody::Multiline(..)) => FieldKind::Multiline,
+            Some(FieldBody::Folded(_)) => FieldKind::Folded,
+            Some(FieldBody::Simple(_)) | None => field_kind(field_name),
+        }
+    }
+
+    /// Removes a field, returning its value if it was present. Multiline
+    /// values are joined with newlines.
+    pub fn remove(&mut self, field_name: &str) -> Option<String> {
+        match self.fields.shift_remove(&UncasedStrRef::from(field_name))? {
+            FieldBody::Simple(value) | FieldBody::Folded(value) => Some(value),
+            FieldBody::Multiline(first, other) if other.is_empty() => Some(first),
+            FieldBody::Multiline(first, other) => Some(format!("{first}\n{other}")),
+        }
+    }
+
+    /// Writes the paragraph in deb822 form
+    pub fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+        write!(writer, "{self}")?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for Paragraph {
+    /// Formats the paragraph in deb822 form. Continuation lines are indented
+    /// by a single space and empty continuation lines are written as ` .`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (tag, body) in &self.fields {
+            let (first, other) = match body {
+                FieldBody::Simple(value) | FieldBody::Folded(value) => (value, None),
+                FieldBody::Multiline(first, other) => (first, Some(other)),
+            };
+            if first.is_empty() {
+                writeln!(f, "{}:", tag.as_ref())?;
+            } else {
+                writeln!(f, "{}: {}", tag.as_ref(), first)?;
+            }
+            for line in other.into_iter().flat_map(|other| other.lines()) {
+                if line.trim().is_empty() {
+                    writeln!(f, " .")?;
+                } else {
+                    writeln!(f, " {line}")?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Parses the fields of a single paragraph one line at a time
+pub(crate) struct ParagraphParser {
+    paragraph: Paragraph,
+
+    curr_name: Option<Tag>,
+
+    // The kinds of the fields the parser knows about
+    field_kinds: &'static [(UncasedStrRef<'static>, FieldKind)],
+
+    // Whether continuation lines of Simple fields are folded into them. If
+    // not they are an error.
+    fold: bool,
+
+    // Whether errors are recorded in `diagnostics` and recovered from
+    lenient: bool,
+
+    // Whether the continuation lines being read belong to a line which was
+    // dropped while recovering
+    skipping: bool,
+
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl ParagraphParser {
+    pub(crate) fn new(
+        field_kinds: &'static [(UncasedStrRef<'static>, FieldKind)],
+        fold: bool,
+    ) -> Self {
+        ParagraphParser {
+            paragraph: Paragraph::new(),
+            curr_name: None,
+            field_kinds,
+            fold,
+            lenient: false,
+            skipping: false,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    pub(crate) fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    // In lenient mode records a parse error and drops the line, along with
+    // its continuation lines for a new field. Otherwise returns the error.
+    fn recover(&mut self, err: Error, new_field: bool) -> Result<()> {
+        match err {
+            Error::InvalidControlFile(diagnostic) if self.lenient => {
+                self.diagnostics.push(diagnostic);
+                if new_field {
+                    self.curr_name = None;
+                    self.skipping = true;
+                }
+                Ok(())
+            }
+            err => Err(err),
+        }
+    }
+
+    // Records a stray blank line inside a single paragraph
+    pub(crate) fn blank_line(&mut self, line_number: usize, line: &str) {
+        self.diagnostics.push(ControlParseError {
+            line: line_number,
+            column: 1,
+            text: line.to_owned(),
+            field: None,
+            kind: ControlParseErrorKind::BlankLine,
+        });
+    }
+
+    // Parses a line which is not a paragraph separator. `line_number` is
+    // only used for errors.
+    pub(crate) fn parse_line(&mut self, line_number: usize, line: &str) -> Result<()> {
+        let error = |column, field: Option<&Tag>, kind| {
+            Error::InvalidControlFile(ControlParseError {
+                line: line_number,
+                column,
+                text: line.to_owned(),
+                field: field.map(|field| field.as_ref().to_owned()),
+                kind,
+            })
+        };
+
+        match line.trim_end().chars().next() {
+            Some('#') => {
+                // Comment line, ignore
+            }
+
+            Some(' ') | Some('\t') if self.skipping => {
+                // contiuation of a line dropped while recovering
+            }
+
+            Some(' ') | Some('\t') => {
+                // contiuation of the current field
+                match self.curr_name {
+                    Some(ref name) => {
+                        let continuation = line.trim();
+                        let data = self.paragraph.fields.get_mut(name).unwrap();
+                        match data {
+                            FieldBody::Simple(value) if self.fold || self.lenient => {
+                                if !self.fold {
+                                    self.diagnostics.push(ControlParseError {
+                                        line: line_number,
+                                        column: 1,
+                                        text: line.to_owned(),
+                                        field: Some(name.as_ref().to_owned()),
+                                        kind: ControlParseErrorKind::ContinuationInSimpleField,
+                                    });
+                                }
+                                let mut value = std::mem::take(value);
+                                if !value.is_empty() {
+                                    value.push(' ');
+                                }
+                                value.push_str(continuation);
+                                *data = FieldBody::Folded(value);
+                            }
+                            FieldBody::Simple(_value) => {
+                                return Err(error(
+                                    1,
+                                    Some(name),
+                                    ControlParseErrorKind::ContinuationInSimpleField,
+                                ))
+                            }
+                            FieldBody::Folded(value) => {
+                                if !value.is_empty() {
+                                    value.push(' ');
+                                }
+                                value.push_str(continuation);
+                            }
+                            FieldBody::Multiline(_first, other) => {
+                                if !other.is_empty() {
+                                    other.push('\n');
+                                }
+                                // Only the single leading space is syntax, the
+                                // rest of the indentation belongs to the value
+                                // and ` .` stands for an empty line
+                                let line = &line.trim_end()[1..];
+                                if line != "." {
+                                    other.push_str(line);
+                                }
+                            }
+                        };
+                    }
+                    None => {
+                        let err = error(1, None, ControlParseErrorKind::ContinuationWithoutField);
+                        return self.recover(err, false);
+                    }
+                };
+            }
+
+            Some(_) => {
+                // new field
+                let (field_name, field_value) = match line.split_once(':') {
+                    Some((field_name, field_value)) => (field_name.trim(), field_value.trim()),
+                    None => {
+                        let column = line.trim_end().chars().count() + 1;
+                        let err = error(column, None, ControlParseErrorKind::MissingColon);
+                        return self.recover(err, true);
+                    }
+                };
+                let field_tag: Tag = field_name.into();
+                if let Some(column) = invalid_name_column(field_name) {
+                    let column = column + line.len() - line.trim_start().len();
+                    let err = error(
+                        column,
+                        Some(&field_tag),
+                        ControlParseErrorKind::IllegalCharacters,
+                    );
+                    return self.recover(err, true);
+                }
+                let kind = kind_in(self.field_kinds, field_name);
+                let data = match kind {
+                    FieldKind::Simple => FieldBody::Simple(field_value.to_owned()),
+                    FieldKind::Folded => FieldBody::Folded(field_value.to_owned()),
+                    FieldKind::Multiline => {
+                        FieldBody::Multiline(field_value.to_owned(), String::default())
+                    }
+                };
+                if self.paragraph.fields.contains_key(&field_tag) {
+                    // The first value is kept when recovering
+                    let err = error(1, Some(&field_tag), ControlParseErrorKind::DuplicateField);
+                    return self.recover(err, true);
+                }
+                self.paragraph.fields.insert(field_tag.clone(), data);
+                self.curr_name = Some(field_tag);
+                self.skipping = false;
+            }
+
+            None => unreachable!(),
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.paragraph.is_empty()
+    }
+
+    pub(crate) fn finish(self) -> Paragraph {
+        self.paragraph
+    }
+
+    pub(crate) fn finish_with_diagnostics(self) -> (Paragraph, Vec<Diagnostic>) {
+        (self.paragraph, self.diagnostics)
+    }
+}
+
+/// How strictly [`Control::parse_with_options`] treats malformed lines
+///
+/// Strict parsing, the default, fails on the first malformed line like
+/// [`Control::parse`]. Lenient parsing records each problem as a
+/// [`Diagnostic`] and recovers:
+///
+/// * A repeated field is dropped, keeping the first value
+/// * A continuation line of a single line field is folded into its value
+/// * A line without a `:` or with an illegal field name is dropped, along
+///   with its continuation lines
+/// * A continuation line before the first field or a blank line is dropped
+///
+/// # Example
+///
+/// ```
+/// let text = b"Package: hello\nVersion: 1.0\nVersion: 2.0\n";
+/// let mut options = debpkg::ParseOptions::new();
+/// options.lenient(true);
+/// let (control, diagnostics) =
+///     debpkg::Control::parse_with_options(&text[..], &options).unwrap();
+/// assert_eq!(control.version(), "1.0");
+/// assert_eq!(diagnostics[0].line, 3);
+/// ```
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ParseOptions {
+    lenient: bool,
+}
+
+impl ParseOptions {
+    /// Creates options for strict parsing
+    pub fn new() -> ParseOptions {
+        ParseOptions::default()
+    }
+
+    /// Sets whether malformed lines are recovered from instead of failing
+    /// the parse. Defaults to false.
+    pub fn lenient(&mut self, lenient: bool) -> &mut Self {
+        self.lenient = lenient;
+        self
+    }
+}
+
 /// Stores the Debian package's control information
 #[derive(Debug)]
 pub struct Control {
@@ -121,12 +602,6 @@ pub struct Control {
 }
 
 impl Control {
-    fn new() -> Control {
-        Control {
-            paragraph: Paragraph::default(),
-        }
-    }
-
     /// Parse the Control file in a Debian Package out of a tar file
     ///
     /// # Arguments
@@ -142,7 +617,23 @@ impl Control {
     /// let mut control_tar = pkg.control().unwrap();
     /// let control = Control::extract(control_tar).unwrap();
     /// ```
-    pub fn extract<R: Read>(mut archive: tar::Archive<R>) -> Result<Control> {
+    pub fn extract<R: Read>(archive: tar::Archive<R>) -> Result<Control> {
+        let (control, _) = Control::extract_with_options(archive, &ParseOptions::new())?;
+        Ok(control)
+    }
+
+    /// Parse the Control file in a Debian Package out of a tar file,
+    /// returning the problems recovered from when `options` is lenient. See
+    /// [`Control::parse_with_options`].
+    ///
+    /// # Arguments
+    ///
+    /// * `archive` - The archive which contains the tar file
+    /// * `options` - How strictly to parse
+    pub fn extract_with_options<R: Read>(
+        mut archive: tar::Archive<R>,
+        options: &ParseOptions,
+    ) -> Result<(Control, Vec<Diagnostic>)> {
         let mut entries = archive.entries()?;
 
         let file = entries.find(|x| match x {
@@ -157,12 +648,39 @@ impl Control {
         });
 
         match file {
-            Some(Ok(file)) => Self::parse(file),
+            Some(Ok(file)) => Self::parse_with_options(file, options),
             Some(Err(e)) => Err(Error::Io(e)),
             None => Err(Error::MissingControlFile),
         }
     }
 
+    /// Parse the Control file in a Debian Package out of an async tar file,
+    /// requires the `async` feature
+    ///
+    /// # Arguments
+    ///
+    /// * `archive` - The archive returned by `AsyncDebPkg::control`
+    #[cfg(feature = "async")]
+    pub async fn extract_async<R: tokio::io::AsyncRead + Unpin>(
+        mut archive: tokio_tar::Archive<R>,
+    ) -> Result<Control> {
+        use tokio::io::AsyncReadExt;
+        use tokio_stream::StreamExt;
+
+        let mut entries = archive.entries()?;
+        while let Some(entry) = entries.next().await {
+            let mut entry = entry?;
+            let path = entry.path()?;
+            if path == std::path::Path::new("./control") || path == std::path::Path::new("control")
+            {
+                let mut contents = Vec::new();
+                entry.read_to_end(&mut contents).await?;
+                return Self::parse(&contents[..]);
+            }
+        }
+        Err(Error::MissingControlFile)
+    }
+
     /// Parse the Control file in a Debian Package
     ///
     /// # Arguments
@@ -179,89 +697,47 @@ impl Control {
     /// let control_tar = pkg.control().unwrap();
     /// ```
     pub fn parse<R: Read>(reader: R) -> Result<Control> {
+        let (control, _) = Control::parse_with_options(reader, &ParseOptions::new())?;
+        Ok(control)
+    }
+
+    /// Parse a control file, returning the problems recovered from when
+    /// `options` is lenient. In strict mode the list is always empty.
+    ///
+    /// A missing `Package` or `Version` field is an error in either mode.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - A type which implements read and contains a control file
+    /// * `options` - How strictly to parse
+    pub fn parse_with_options<R: Read>(
+        reader: R,
+        options: &ParseOptions,
+    ) -> Result<(Control, Vec<Diagnostic>)> {
         let buf_reader = BufReader::new(reader);
         let lines = buf_reader.lines();
 
-        let mut ctrl = Control::new();
-
-        let mut curr_name: Option<Tag> = None;
+        let mut parser = ParagraphParser::new(FIELD_KINDS, false).lenient(options.lenient);
 
-        for line in lines {
+        for (index, line) in lines.enumerate() {
             let line = line?;
 
-            match line.trim_end().chars().next() {
-                Some('#') => {
-                    // Comment line, ignore
-                    continue;
-                }
-
-                Some(' ') | Some('\t') => {
-                    // contiuation of the current field
-                    match curr_name {
-                        Some(ref name) => {
-                            let continuation = line.trim();
-                            let data = ctrl.paragraph.get_mut(name).unwrap();
-                            match data {
-                                FieldBody::Simple(_value) => return Err(Error::InvalidControlFile),
-                                FieldBody::Folded(value) => {
-                                    value.push(' ');
-                                    value.push_str(continuation);
-                                }
-                                FieldBody::Multiline(_first, other) => {
-                                    if !other.is_empty() {
-                                        other.push('\n');
-                                    }
-                                    other.push_str(continuation);
-                                }
-                            };
-                        }
-                        None => return Err(Error::InvalidControlFile),
-                    };
-                }
-
-                Some(_) => {
-                    // new field
-                    let line = line.trim();
-                    let mut split = line.splitn(2, ':');
-                    let field_name = match split.next() {
-                        Some(field_name) => field_name.trim(),
-                        None => return Err(Error::InvalidControlFile),
-                    };
-                    let field_value = match split.next() {
-                        Some(field_name) => field_name.trim(),
-                        None => return Err(Error::InvalidControlFile),
-                    };
-                    let field_tag: Tag = field_name.into();
-                    let data = if field_tag == DESCRIPTION {
-                        FieldBody::Multiline(field_value.to_owned(), String::default())
-                    } else {
-                        FieldBody::Simple(field_value.to_owned())
-                    };
-                    if let Some(_value) = ctrl.paragraph.insert(field_tag, data) {
-                        return Err(Error::InvalidControlFile);
-                    }
-                    let field_tag: Tag = field_name.into();
-                    curr_name = Some(field_tag);
-                }
-
-                None => {
-                    // Paragraph seperation
-                    // TODO: This is technically an error but ignoring for now
+            if line.trim_end().is_empty() {
+                // Paragraph seperation
+                // TODO: This is technically an error but ignoring for now
+                if options.lenient {
+                    parser.blank_line(index + 1, &line);
+                } else {
                     warn!("Unexpected paragraph seperation");
-                    continue;
                 }
+                continue;
             }
-        }
 
-        if !ctrl.paragraph.contains_key(&PACKAGE) {
-            return Err(Error::MissingPackageName);
+            parser.parse_line(index + 1, &line)?;
         }
 
-        if !ctrl.paragraph.contains_key(&VERSION) {
-            return Err(Error::MissingPackageVersion);
-        }
-
-        Ok(ctrl)
+        let (paragraph, diagnostics) = parser.finish_with_diagnostics();
+        Ok((Control::try_from(paragraph)?, diagnostics))
     }
 
     /// Returns the package name from the control file
@@ -274,6 +750,12 @@ impl Control {
         self.get("Version").unwrap()
     }
 
+    /// Returns the package version from the control file parsed as a
+    /// [`Version`]
+    pub fn parsed_version(&self) -> Result<Version> {
+        self.version().parse()
+    }
+
     /// Returns short description if it exists from the control file
     pub fn short_description(&self) -> Option<&str> {
         self.get("Description")
@@ -281,7 +763,7 @@ impl Control {
 
     /// Returns long description if it exists from the control file
     pub fn long_description(&self) -> Option<&str> {
-        let (_, long) = match self.paragraph.get(&DESCRIPTION)? {
+        let (_, long) = match self.paragraph.fields.get(&DESCRIPTION)? {
             FieldBody::Simple(_) | FieldBody::Folded(_) => unreachable!(),
             FieldBody::Multiline(short, long) => (short, long),
         };
@@ -299,16 +781,283 @@ impl Control {
     ///
     /// * field_name - The field name. This string is case insensitve
     pub fn get(&self, field_name: &str) -> Option<&str> {
-        match self.paragraph.get(&UncasedStrRef::from(field_name)) {
-            Some(FieldBody::Simple(value)) | Some(FieldBody::Folded(value)) => Some(value.as_str()),
-            Some(FieldBody::Multiline(value, _)) => Some(value.as_str()),
-            None => None,
-        }
+        self.paragraph.get(field_name)
     }
 
     /// Returns an iterator to all the field names in the control file
     pub fn tags(&self) -> impl Iterator<Item = &str> {
-        self.paragraph.keys().map(|i| i.as_ref())
+        self.paragraph.tags()
+    }
+
+    /// Returns the paragraph holding all of the control file's fields
+    pub fn paragraph(&self) -> &Paragraph {
+        &self.paragraph
+    }
+
+    /// Consumes the control file, returning the paragraph holding all of its
+    /// fields
+    pub fn into_paragraph(self) -> Paragraph {
+        self.paragraph
+    }
+
+    /// Sets the value of a field, replacing any existing value in place or
+    /// appending the field to the end of the control file
+    ///
+    /// Newlines in the value are handled the way the parser handles
+    /// continuation lines. For a multiline field such as `Description` the
+    /// first line becomes the field's value and the remaining lines its
+    /// continuation lines. A field such as `Depends` is folded into a single
+    /// line. Any other field must fit on one line.
+    ///
+    /// # Arguments
+    ///
+    /// * field_name - The field name. This string is case insensitve
+    ///
+    /// * value - The new field value
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut ctrl = debpkg::Control::parse(&b"Package: name\nVersion: 1.0"[..]).unwrap();
+    /// ctrl.set("Version", "1.0+repack1").unwrap();
+    /// assert_eq!(ctrl.version(), "1.0+repack1");
+    /// ```
+    pub fn set(&mut self, field_name: &str, value: &str) -> Result<()> {
+        self.paragraph.set(field_name, value)
+    }
+
+    /// Sets the value of a field and moves it directly after `after`
+    ///
+    /// If `after` is not in the control file the field is appended to the
+    /// end instead.
+    ///
+    /// # Arguments
+    ///
+    /// * after - The field name to insert after. This string is case
+    ///   insensitve
+    ///
+    /// * field_name - The field name. This string is case insensitve
+    ///
+    /// * value - The new field value
+    pub fn insert_after(&mut self, after: &str, field_name: &str, value: &str) -> Result<()> {
+        self.paragraph.insert_after(after, field_name, value)
+    }
+
+    /// Removes a field, returning its value if it was present
+    ///
+    /// The `Package` and `Version` fields are required and can not be
+    /// removed. Trying to remove them returns `None` and leaves the control
+    /// file unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * field_name - The field name. This string is case insensitve
+    pub fn remove(&mut self, field_name: &str) -> Option<String> {
+        let key = UncasedStrRef::from(field_name);
+        if key == PACKAGE || key == VERSION {
+            return None;
+        }
+        self.paragraph.remove(field_name)
+    }
+
+    /// Writes the control file in deb822 form
+    ///
+    /// This produces the same output as the `Display` implementation.
+    ///
+    /// # Arguments
+    ///
+    /// * writer - Where to write the control file
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let ctrl = debpkg::Control::parse(&b"Package: name\nVersion: 1.0"[..]).unwrap();
+    /// let mut out = Vec::new();
+    /// ctrl.write_to(&mut out).unwrap();
+    /// assert_eq!(out, b"Package: name\nVersion: 1.0\n");
+    /// ```
+    pub fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+        write!(writer, "{self}")?;
+        Ok(())
+    }
+
+    fn relationships(&self, field_name: &str) -> Result<Vec<Dependency>> {
+        match self.get(field_name) {
+            Some(value) => parse_relationships(value),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Returns the parsed `Depends` field, or an empty list if it is absent
+    pub fn depends(&self) -> Result<Vec<Dependency>> {
+        self.relationships("Depends")
+    }
+
+    /// Returns the parsed `Pre-Depends` field, or an empty list if it is absent
+    pub fn pre_depends(&self) -> Result<Vec<Dependency>> {
+        self.relationships("Pre-Depends")
+    }
+
+    /// Returns the parsed `Recommends` field, or an empty list if it is absent
+    pub fn recommends(&self) -> Result<Vec<Dependency>> {
+        self.relationships("Recommends")
+    }
+
+    /// Returns the parsed `Suggests` field, or an empty list if it is absent
+    pub fn suggests(&self) -> Result<Vec<Dependency>> {
+        self.relationships("Suggests")
+    }
+
+    /// Returns the parsed `Breaks` field, or an empty list if it is absent
+    pub fn breaks(&self) -> Result<Vec<Dependency>> {
+        self.relationships("Breaks")
+    }
+
+    /// Returns the parsed `Conflicts` field, or an empty list if it is absent
+    pub fn conflicts(&self) -> Result<Vec<Dependency>> {
+        self.relationships("Conflicts")
+    }
+
+    /// Returns the parsed `Replaces` field, or an empty list if it is absent
+    pub fn replaces(&self) -> Result<Vec<Dependency>> {
+        self.relationships("Replaces")
+    }
+
+    /// Returns the parsed `Provides` field, or an empty list if it is absent
+    pub fn provides(&self) -> Result<Vec<Dependency>> {
+        self.relationships("Provides")
+    }
+
+    /// Returns the parsed `Enhances` field, or an empty list if it is absent
+    pub fn enhances(&self) -> Result<Vec<Dependency>> {
+        self.relationships("Enhances")
+    }
+}
+
+impl fmt::Display for Control {
+    /// Formats the control file in deb822 form. Fields keep the order they
+    /// were parsed or added in, continuation lines are indented by a single
+    /// space and empty continuation lines are written as ` .`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.paragraph, f)
+    }
+}
+
+impl TryFrom<Paragraph> for Control {
+    type Error = Error;
+
+    /// Turns a paragraph, such as one read by a [`crate::Deb822Reader`], into
+    /// a control file. Fails if the paragraph has no `Package` or `Version`
+    /// field.
+    fn try_from(paragraph: Paragraph) -> Result<Control> {
+        if !paragraph.fields.contains_key(&PACKAGE) {
+            return Err(Error::MissingPackageName);
+        }
+
+        if !paragraph.fields.contains_key(&VERSION) {
+            return Err(Error::MissingPackageVersion);
+        }
+
+        Ok(Control { paragraph })
+    }
+}
+
+// Returns the 1 based column of the first character which is not allowed in
+// a field name, or 1 if the name is empty or starts with `#` or `-`
+fn invalid_name_column(field_name: &str) -> Option<usize> {
+    if field_name.is_empty() || field_name.starts_with(['#', '-']) {
+        return Some(1);
+    }
+    field_name
+        .chars()
+        .position(|c| !c.is_ascii_graphic() || c == ':')
+        .map(|position| position + 1)
+}
+
+// Builds the body for a field being set, checking the field name is valid
+// and storing continuation lines the way the parser would for a field of
+// `kind`
+fn field_body(field_name: &str, kind: FieldKind, value: &str) -> Result<FieldBody> {
+    let error = |text: &str, column, kind| {
+        Error::InvalidControlFile(ControlParseError {
+            line: 0,
+            column,
+            text: text.to_owned(),
+            field: Some(field_name.to_owned()),
+            kind,
+        })
+    };
+    if let Some(column) = invalid_name_column(field_name) {
+        return Err(error(
+            field_name,
+            column,
+            ControlParseErrorKind::IllegalCharacters,
+        ));
+    }
+    if let Some(position) = value.find('\r') {
+        return Err(error(
+            value,
+            value[..position].chars().count() + 1,
+            ControlParseErrorKind::IllegalCharacters,
+        ));
+    }
+
+    let (first, other) = match value.split_once('\n') {
+        Some((first, other)) => (first.trim(), other.trim_end_matches('\n')),
+        None => (value.trim(), ""),
+    };
+
+    match kind {
+        FieldKind::Multiline => {}
+        FieldKind::Folded => {
+            let folded = std::iter::once(first)
+                .chain(other.lines().map(str::trim))
+                .filter(|line| !line.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ");
+            return Ok(FieldBody::Folded(folded));
+        }
+        FieldKind::Simple if other.is_empty() => return Ok(FieldBody::Simple(first.to_owned())),
+        FieldKind::Simple => {
+            let position = value.find('\n').unwrap();
+            return Err(error(
+                value,
+                value[..position].chars().count() + 1,
+                ControlParseErrorKind::ContinuationInSimpleField,
+            ));
+        }
+    }
+
+    // Blank lines are stored empty, the same as a parsed ` .`
+    let other = other
+        .split('\n')
+        .map(|line| if line.trim().is_empty() { "" } else { line })
+        .collect::<Vec<_>>()
+        .join("\n");
+    Ok(FieldBody::Multiline(first.to_owned(), other))
+}
+
+impl<R: Read> DebPkg<R> {
+    /// Parses the control file of the package, returning the problems
+    /// recovered from when `options` is lenient. Like
+    /// [`DebPkg::control`](crate::DebPkg::control), this must be called
+    /// before the data tar is read.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use debpkg::{DebPkg, ParseOptions};
+    /// let file = std::fs::File::open("test.deb").unwrap();
+    /// let mut pkg = DebPkg::parse(file).unwrap();
+    /// let mut options = ParseOptions::new();
+    /// options.lenient(true);
+    /// let (control, diagnostics) = pkg.parse_control(&options).unwrap();
+    /// for diagnostic in diagnostics {
+    ///     println!("{}: {diagnostic}", control.name());
+    /// }
+    /// ```
+    pub fn parse_control(&mut self, options: &ParseOptions) -> Result<(Control, Vec<Diagnostic>)> {
+        Control::extract_with_options(self.control()?, options)
     }
 }
 
@@ -341,6 +1090,24 @@ mod tests {
         assert!(ctrl.version() == "1.8.2");
     }
 
+    #[test]
+    fn parsed_version_parse() {
+        let ctrl = Control::parse(&b"package: name\nversion: 1:1.8.2-1"[..]).unwrap();
+        let version = ctrl.parsed_version().unwrap();
+        assert!(version.epoch() == 1);
+        assert!(version.upstream_version() == "1.8.2");
+        assert!(version.debian_revision() == Some("1"));
+    }
+
+    #[test]
+    fn invalid_parsed_version_fails() {
+        let ctrl = Control::parse(&b"package: name\nversion: 1.8.2-"[..]).unwrap();
+        assert_matches!(
+            ctrl.parsed_version().unwrap_err(),
+            Error::InvalidPackageVersion(_)
+        );
+    }
+
     #[test]
     fn proper_description_parse() {
         let ctrl =
@@ -357,7 +1124,8 @@ mod tests {
     #[test]
     fn control_starting_with_continuation_fails() {
         let err = Control::parse(&b" continue\npackage: name\nversion: 1.8.2"[..]).unwrap_err();
-        assert_matches!(err, Error::InvalidControlFile);
+        assert_matches!(err, Error::InvalidControlFile(ref err)
+            if err.kind == ControlParseErrorKind::ContinuationWithoutField && err.line == 1);
     }
 
     #[test]
@@ -376,19 +1144,233 @@ mod tests {
     #[test]
     fn control_non_continuation_line_fails() {
         let err = Control::parse(&b"package: name\nthis is wrong"[..]).unwrap_err();
-        assert_matches!(err, Error::InvalidControlFile);
+        assert_matches!(err, Error::InvalidControlFile(ref err)
+            if err.kind == ControlParseErrorKind::MissingColon
+                && err.line == 2
+                && err.column == 14
+                && err.text == "this is wrong");
     }
 
     #[test]
     fn duplicate_fields_fails_parsing() {
         let err =
             Control::parse(&b"package: name\nversion: 1.8.2\npackage: name2"[..]).unwrap_err();
-        assert_matches!(err, Error::InvalidControlFile);
+        assert_matches!(err, Error::InvalidControlFile(ref err)
+            if err.kind == ControlParseErrorKind::DuplicateField
+                && err.line == 3
+                && err.field.as_deref() == Some("package"));
+    }
+
+    #[test]
+    fn depends_parses() {
+        let ctrl = Control::parse(
+            &b"package: name\nversion: 1.8.2\nDepends: libc6 (>= 2.17), foo | bar"[..],
+        )
+        .unwrap();
+        let depends = ctrl.depends().unwrap();
+        assert_eq!(depends.len(), 2);
+        assert_eq!(depends[0].alternatives[0].name, "libc6");
+        assert_eq!(depends[1].alternatives.len(), 2);
+        assert!(ctrl.recommends().unwrap().is_empty());
+    }
+
+    #[test]
+    fn invalid_depends_fails() {
+        let ctrl =
+            Control::parse(&b"package: name\nversion: 1.8.2\nPre-Depends: foo (>= "[..]).unwrap();
+        assert_matches!(
+            ctrl.pre_depends().unwrap_err(),
+            Error::InvalidRelationship(_)
+        );
+    }
+
+    #[test]
+    fn set_replaces_in_place() {
+        let mut ctrl = Control::parse(&b"package: name\nversion: 1.8.2\nTest: a"[..]).unwrap();
+        ctrl.set("VERSION", "1.8.3").unwrap();
+        assert!(ctrl.version() == "1.8.3");
+        assert!(ctrl.tags().collect::<Vec<_>>() == ["package", "version", "Test"]);
+    }
+
+    #[test]
+    fn set_appends_new_field() {
+        let mut ctrl = Control::parse(&b"package: name\nversion: 1.8.2"[..]).unwrap();
+        ctrl.set("Maintainer", "A <a@example.com>").unwrap();
+        assert!(ctrl.tags().last() == Some("Maintainer"));
+        assert!(ctrl.get("maintainer") == Some("A <a@example.com>"));
+    }
+
+    #[test]
+    fn set_invalid_field_name_fails() {
+        let mut ctrl = Control::parse(&b"package: name\nversion: 1.8.2"[..]).unwrap();
+        for name in ["", "a b", "a:b", "#comment", "-dash"] {
+            assert_matches!(ctrl.set(name, "value"), Err(Error::InvalidControlFile(ref err))
+                if err.kind == ControlParseErrorKind::IllegalCharacters);
+        }
+    }
+
+    #[test]
+    fn set_multiline_description() {
+        let mut ctrl = Control::parse(&b"package: name\nversion: 1.8.2"[..]).unwrap();
+        ctrl.set("Description", "short\nvery\n\nlong").unwrap();
+        assert!(ctrl.short_description() == Some("short"));
+        assert!(ctrl.long_description() == Some("very\n\nlong"));
+        ctrl.set("Description", "only short").unwrap();
+        assert!(ctrl.long_description().is_none());
+    }
+
+    #[test]
+    fn insert_after_moves_field() {
+        let mut ctrl =
+            Control::parse(&b"package: name\nversion: 1.8.2\nA: 1\nB: 2\nC: 3"[..]).unwrap();
+        ctrl.insert_after("package", "C", "4").unwrap();
+        assert!(ctrl.tags().collect::<Vec<_>>() == ["package", "C", "version", "A", "B"]);
+        assert!(ctrl.get("C") == Some("4"));
+        ctrl.insert_after("missing", "D", "5").unwrap();
+        assert!(ctrl.tags().last() == Some("D"));
+    }
+
+    #[test]
+    fn remove_field() {
+        let mut ctrl =
+            Control::parse(&b"package: name\nversion: 1.8.2\nDescription: a\n b\nTest: c"[..])
+                .unwrap();
+        assert!(ctrl.remove("test") == Some("c".to_owned()));
+        assert!(ctrl.remove("description") == Some("a\nb".to_owned()));
+        assert!(ctrl.remove("test").is_none());
+        assert!(ctrl.tags().count() == 2);
+    }
+
+    #[test]
+    fn remove_required_field_is_refused() {
+        let mut ctrl = Control::parse(&b"package: name\nversion: 1.8.2"[..]).unwrap();
+        assert!(ctrl.remove("Package").is_none());
+        assert!(ctrl.remove("version").is_none());
+        assert!(ctrl.name() == "name");
+    }
+
+    #[test]
+    fn display_round_trips() {
+        let text = "Package: name\nVersion: 1.8.2\nDepends: a, b\nDescription: short\n line one\n .\n line two\n   indented\n";
+        let ctrl = Control::parse(text.as_bytes()).unwrap();
+        assert!(ctrl.to_string() == text);
+    }
+
+    #[test]
+    fn set_blank_description_line_round_trips() {
+        let mut ctrl = Control::parse(&b"package: name\nversion: 1.8.2"[..]).unwrap();
+        ctrl.set("Description", "short\na\n\nb").unwrap();
+        assert!(ctrl.long_description() == Some("a\n\nb"));
+        let parsed = Control::parse(ctrl.to_string().as_bytes()).unwrap();
+        assert!(parsed.long_description() == ctrl.long_description());
+    }
+
+    #[test]
+    fn set_follows_field_kind() {
+        let mut ctrl = Control::parse(&b"package: name\nversion: 1.8.2"[..]).unwrap();
+        ctrl.set("Depends", "libc6,\n foo").unwrap();
+        assert!(ctrl.get("Depends") == Some("libc6, foo"));
+        assert!(ctrl.depends().unwrap().len() == 2);
+        let text = ctrl.to_string();
+        assert!(text.contains("Depends: libc6, foo\n"));
+        let parsed = Control::parse(text.as_bytes()).unwrap();
+        assert!(parsed.get("Depends") == ctrl.get("Depends"));
+
+        let err = ctrl.set("Version", "1.0\n2.0").unwrap_err();
+        assert_matches!(
+            err,
+            Error::InvalidControlFile(ControlParseError {
+                kind: ControlParseErrorKind::ContinuationInSimpleField,
+                ..
+            })
+        );
+        assert!(ctrl.version() == "1.8.2");
+    }
+
+    #[test]
+    fn display_encodes_empty_lines() {
+        let mut ctrl = Control::parse(&b"Package: name\nVersion: 1.8.2"[..]).unwrap();
+        ctrl.set("Description", "short\nfirst\n\nsecond").unwrap();
+        ctrl.set("Files", "\nabc 1 a.deb").unwrap();
+        assert!(
+            ctrl.to_string()
+                == "Package: name\nVersion: 1.8.2\nDescription: short\n first\n .\n second\nFiles:\n abc 1 a.deb\n"
+        );
+    }
+
+    #[test]
+    fn known_multiline_and_folded_fields_parse() {
+        let text =
+            b"Package: name\nVersion: 1.0\nDepends: a,\n b\nFiles:\n abc 1 a.deb\n def 2 b.deb\n";
+        let ctrl = Control::parse(&text[..]).unwrap();
+        assert_eq!(ctrl.get("Depends"), Some("a, b"));
+        assert_eq!(ctrl.depends().unwrap().len(), 2);
+        assert_eq!(
+            ctrl.paragraph().continuation("Files"),
+            Some("abc 1 a.deb\ndef 2 b.deb")
+        );
     }
 
     #[test]
     fn continuation_in_package_should_fail() {
         let err = Control::parse(&b"package: name\n is invalid\nversion: 1.8.2"[..]).unwrap_err();
-        assert_matches!(err, Error::InvalidControlFile);
+        assert_matches!(err, Error::InvalidControlFile(ref err)
+            if err.kind == ControlParseErrorKind::ContinuationInSimpleField
+                && err.line == 2
+                && err.field.as_deref() == Some("package"));
+    }
+
+    #[test]
+    fn illegal_field_name_fails() {
+        let err = Control::parse(&b"package: name\nversion: 1.8.2\nBad Name: x"[..]).unwrap_err();
+        assert_matches!(err, Error::InvalidControlFile(ref err)
+            if err.kind == ControlParseErrorKind::IllegalCharacters
+                && err.line == 3
+                && err.column == 4);
+    }
+
+    #[test]
+    fn lenient_parse_recovers() {
+        let text = b"Package: name\n continued\nVersion: 1.8.2\n\nbroken line\n more broken\nVersion: 2\n trailing\nBad Name: x\nDescription: short\n long\n";
+        let mut options = ParseOptions::new();
+        options.lenient(true);
+        let (ctrl, diagnostics) = Control::parse_with_options(&text[..], &options).unwrap();
+        assert_eq!(ctrl.name(), "name continued");
+        assert_eq!(ctrl.version(), "1.8.2");
+        assert_eq!(ctrl.long_description(), Some("long"));
+        assert_eq!(ctrl.tags().count(), 3);
+        let kinds: Vec<_> = diagnostics.iter().map(|d| (d.line, d.kind)).collect();
+        assert_eq!(
+            kinds,
+            [
+                (2, ControlParseErrorKind::ContinuationInSimpleField),
+                (4, ControlParseErrorKind::BlankLine),
+                (5, ControlParseErrorKind::MissingColon),
+                (7, ControlParseErrorKind::DuplicateField),
+                (9, ControlParseErrorKind::IllegalCharacters),
+            ]
+        );
+
+        let err = Control::parse_with_options(&text[..], &ParseOptions::new()).unwrap_err();
+        assert_matches!(err, Error::InvalidControlFile(ref err) if err.line == 2);
+    }
+
+    #[test]
+    fn lenient_parse_still_needs_package() {
+        let mut options = ParseOptions::new();
+        options.lenient(true);
+        let err = Control::parse_with_options(&b" x\nVersion: 1"[..], &options).unwrap_err();
+        assert_matches!(err, Error::MissingPackageName);
+    }
+
+    #[test]
+    fn parse_error_is_the_source() {
+        use std::error::Error as _;
+
+        let err = Control::parse(&b"package: name\n is invalid"[..]).unwrap_err();
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            "continuation line in a single line field in field package at line 2, column 1: \" is invalid\""
+        );
     }
 }
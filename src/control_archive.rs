@@ -0,0 +1,641 @@
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use crate::control::{field, FieldKind, UncasedStrRef};
+use crate::{Control, Deb822Reader, Error, Paragraph, Result};
+
+use indexmap::IndexMap;
+
+fn invalid(member: &str, line_number: usize, msg: &str) -> Error {
+    Error::InvalidControlMember(format!("{member} line {line_number}: {msg}"))
+}
+
+// Returns the non-empty, non-comment lines of a control member along with
+// their line numbers
+fn content_lines<R: Read>(reader: R) -> Result<Vec<(usize, String)>> {
+    let mut lines = Vec::new();
+    for (index, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line?;
+        let trimmed = line.trim_end();
+        if trimmed.trim_start().is_empty() || trimmed.trim_start().starts_with('#') {
+            continue;
+        }
+        lines.push((index + 1, trimmed.to_owned()));
+    }
+    Ok(lines)
+}
+
+// Strips the leading `/` or `./` so paths from md5sums, conffiles and data.tar
+// can be compared
+pub(crate) fn relative_path(path: &str) -> PathBuf {
+    PathBuf::from(path.trim_start_matches("./").trim_start_matches('/'))
+}
+
+/// The digests listed in a package's `md5sums` control file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Md5Sums {
+    digests: IndexMap<PathBuf, String>,
+}
+
+impl Md5Sums {
+    /// Parses an `md5sums` file
+    ///
+    /// Paths are stored relative to the root of the package, e.g.
+    /// `usr/bin/xz`.
+    pub fn parse<R: Read>(reader: R) -> Result<Md5Sums> {
+        let mut digests = IndexMap::new();
+        for (line_number, line) in content_lines(reader)? {
+            let (digest, path) = match line.split_once(char::is_whitespace) {
+                Some((digest, path)) => (digest, path.trim_start()),
+                None => return Err(invalid("md5sums", line_number, "missing path")),
+            };
+            if digest.len() != 32 || !digest.bytes().all(|c| c.is_ascii_hexdigit()) {
+                return Err(invalid("md5sums", line_number, "invalid MD5 digest"));
+            }
+            if path.is_empty() {
+                return Err(invalid("md5sums", line_number, "missing path"));
+            }
+            digests.insert(relative_path(path), digest.to_ascii_lowercase());
+        }
+        Ok(Md5Sums { digests })
+    }
+
+    /// Returns the lowercase hex digest of a file, if it is listed
+    ///
+    /// # Arguments
+    ///
+    /// * path - The path of the file, with or without a leading `/` or `./`
+    pub fn get<P: AsRef<Path>>(&self, path: P) -> Option<&str> {
+        let path = relative_path(&path.as_ref().to_string_lossy());
+        self.digests.get(&path).map(String::as_str)
+    }
+
+    /// Returns an iterator over every listed path and its digest
+    pub fn iter(&self) -> impl Iterator<Item = (&Path, &str)> {
+        self.digests
+            .iter()
+            .map(|(path, digest)| (path.as_path(), digest.as_str()))
+    }
+
+    /// Returns the number of listed files
+    pub fn len(&self) -> usize {
+        self.digests.len()
+    }
+
+    /// Returns true if no files are listed
+    pub fn is_empty(&self) -> bool {
+        self.digests.is_empty()
+    }
+}
+
+/// An entry in a package's `conffiles` control file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conffile {
+    /// The absolute path of the configuration file
+    pub path: PathBuf,
+
+    /// Whether the `remove-on-upgrade` flag was set
+    pub remove_on_upgrade: bool,
+}
+
+/// Parses a `conffiles` control file
+pub fn parse_conffiles<R: Read>(reader: R) -> Result<Vec<Conffile>> {
+    let mut conffiles = Vec::new();
+    for (line_number, line) in content_lines(reader)? {
+        let line = line.trim();
+        let (remove_on_upgrade, path) = match line.split_once(char::is_whitespace) {
+            Some(("remove-on-upgrade", path)) => (true, path.trim()),
+            Some(_) => return Err(invalid("conffiles", line_number, "unknown flag")),
+            None => (false, line),
+        };
+        if !path.starts_with('/') {
+            return Err(invalid("conffiles", line_number, "path is not absolute"));
+        }
+        conffiles.push(Conffile {
+            path: PathBuf::from(path),
+            remove_on_upgrade,
+        });
+    }
+    Ok(conffiles)
+}
+
+/// An entry in a package's `shlibs` control file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShlibsEntry {
+    /// The optional package type, e.g. `udeb`
+    pub package_type: Option<String>,
+
+    /// The library name, e.g. `liblzma`
+    pub library: String,
+
+    /// The soname version, e.g. `5`
+    pub version: String,
+
+    /// The dependencies to add for binaries linking against the library
+    pub dependencies: String,
+}
+
+/// Parses a `shlibs` control file
+pub fn parse_shlibs<R: Read>(reader: R) -> Result<Vec<ShlibsEntry>> {
+    let mut entries = Vec::new();
+    for (line_number, line) in content_lines(reader)? {
+        let mut rest = line.trim();
+        let mut package_type = None;
+        if let Some((first, remainder)) = rest.split_once(char::is_whitespace) {
+            if let Some(package) = first.strip_suffix(':') {
+                package_type = Some(package.to_owned());
+                rest = remainder.trim_start();
+            }
+        }
+
+        let mut parts = rest.splitn(3, char::is_whitespace);
+        let library = parts.next().unwrap_or_default();
+        let version = parts.next().unwrap_or_default();
+        let dependencies = parts.next().unwrap_or_default().trim();
+        if library.is_empty() || version.is_empty() {
+            return Err(invalid("shlibs", line_number, "missing library or version"));
+        }
+
+        entries.push(ShlibsEntry {
+            package_type,
+            library: library.to_owned(),
+            version: version.to_owned(),
+            dependencies: dependencies.to_owned(),
+        });
+    }
+    Ok(entries)
+}
+
+/// A symbol exported by a shared library, from a `symbols` control file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    /// The symbol name including its version and any tags, e.g.
+    /// `lzma_code@Base` or `(optional)foo@Base`
+    pub name: String,
+
+    /// The minimal package version providing the symbol
+    pub minimal_version: String,
+
+    /// The index of the alternative dependency template to use. 0 is the
+    /// main template.
+    pub dependency_id: u32,
+}
+
+/// A shared library's section of a `symbols` control file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolsLibrary {
+    /// The soname, e.g. `liblzma.so.5`
+    pub soname: String,
+
+    /// The main dependency template, e.g. `liblzma5 #MINVER#`
+    pub dependency_template: String,
+
+    /// Alternative dependency templates listed on `|` lines
+    pub alternative_templates: Vec<String>,
+
+    /// Meta information fields listed on `*` lines, such as
+    /// `Build-Depends-Package`
+    pub fields: Vec<(String, String)>,
+
+    /// The exported symbols
+    pub symbols: Vec<Symbol>,
+}
+
+/// Parses a `symbols` control file
+pub fn parse_symbols<R: Read>(reader: R) -> Result<Vec<SymbolsLibrary>> {
+    let mut libraries: Vec<SymbolsLibrary> = Vec::new();
+    for (line_number, line) in content_lines(reader)? {
+        if let Some(rest) = line.strip_prefix(|c| c == ' ' || c == '\t') {
+            let library = match libraries.last_mut() {
+                Some(library) => library,
+                None => return Err(invalid("symbols", line_number, "symbol before library")),
+            };
+            library
+                .symbols
+                .push(parse_symbol(rest.trim(), line_number)?);
+        } else if let Some(rest) = line.strip_prefix('|') {
+            match libraries.last_mut() {
+                Some(library) => library.alternative_templates.push(rest.trim().to_owned()),
+                None => return Err(invalid("symbols", line_number, "template before library")),
+            }
+        } else if let Some(rest) = line.strip_prefix('*') {
+            let library = match libraries.last_mut() {
+                Some(library) => library,
+                None => return Err(invalid("symbols", line_number, "field before library")),
+            };
+            match rest.split_once(':') {
+                Some((name, value)) => library
+                    .fields
+                    .push((name.trim().to_owned(), value.trim().to_owned())),
+                None => return Err(invalid("symbols", line_number, "missing ':' in field")),
+            }
+        } else {
+            let (soname, template) = match line.split_once(char::is_whitespace) {
+                Some((soname, template)) => (soname, template.trim()),
+                None => return Err(invalid("symbols", line_number, "missing dependency")),
+            };
+            libraries.push(SymbolsLibrary {
+                soname: soname.to_owned(),
+                dependency_template: template.to_owned(),
+                alternative_templates: Vec::new(),
+                fields: Vec::new(),
+                symbols: Vec::new(),
+            });
+        }
+    }
+    Ok(libraries)
+}
+
+fn parse_symbol(line: &str, line_number: usize) -> Result<Symbol> {
+    // Tags and quoted names may contain spaces, so find where the name ends
+    // before splitting the rest on whitespace
+    let mut name_end = 0;
+    let bytes = line.as_bytes();
+    if bytes.first() == Some(&b'(') {
+        name_end = match line.find(')') {
+            Some(index) => index + 1,
+            None => return Err(invalid("symbols", line_number, "unclosed symbol tag")),
+        };
+    }
+    if bytes.get(name_end) == Some(&b'"') {
+        name_end = match line[name_end + 1..].find('"') {
+            Some(index) => name_end + index + 2,
+            None => return Err(invalid("symbols", line_number, "unclosed quote")),
+        };
+    } else {
+        name_end += line[name_end..]
+            .find(char::is_whitespace)
+            .unwrap_or(line.len() - name_end);
+    }
+
+    let name = &line[..name_end];
+    let mut rest = line[name_end..].split_whitespace();
+    let minimal_version = match rest.next() {
+        Some(version) => version,
+        None => return Err(invalid("symbols", line_number, "missing minimal version")),
+    };
+    let dependency_id = match rest.next() {
+        Some(id) => id
+            .parse()
+            .map_err(|_| invalid("symbols", line_number, "invalid dependency id"))?,
+        None => 0,
+    };
+
+    Ok(Symbol {
+        name: name.to_owned(),
+        minimal_version: minimal_version.to_owned(),
+        dependency_id,
+    })
+}
+
+/// The kind of a trigger directive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriggerDirective {
+    /// `interest`
+    Interest,
+
+    /// `interest-await`
+    InterestAwait,
+
+    /// `interest-noawait`
+    InterestNoawait,
+
+    /// `activate`
+    Activate,
+
+    /// `activate-await`
+    ActivateAwait,
+
+    /// `activate-noawait`
+    ActivateNoawait,
+}
+
+/// An entry in a package's `triggers` control file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trigger {
+    /// Whether the package is interested in or activates the trigger
+    pub directive: TriggerDirective,
+
+    /// The trigger name, which may be a file path
+    pub name: String,
+}
+
+/// Parses a `triggers` control file
+pub fn parse_triggers<R: Read>(reader: R) -> Result<Vec<Trigger>> {
+    let mut triggers = Vec::new();
+    for (line_number, line) in content_lines(reader)? {
+        let mut parts = line.split_whitespace();
+        let directive = match parts.next() {
+            Some("interest") => TriggerDirective::Interest,
+            Some("interest-await") => TriggerDirective::InterestAwait,
+            Some("interest-noawait") => TriggerDirective::InterestNoawait,
+            Some("activate") => TriggerDirective::Activate,
+            Some("activate-await") => TriggerDirective::ActivateAwait,
+            Some("activate-noawait") => TriggerDirective::ActivateNoawait,
+            _ => return Err(invalid("triggers", line_number, "unknown directive")),
+        };
+        let name = match (parts.next(), parts.next()) {
+            (Some(name), None) => name,
+            _ => {
+                return Err(invalid(
+                    "triggers",
+                    line_number,
+                    "expected one trigger name",
+                ))
+            }
+        };
+        triggers.push(Trigger {
+            directive,
+            name: name.to_owned(),
+        });
+    }
+    Ok(triggers)
+}
+
+// The fields of debconf templates which keep their line structure, including
+// their translations such as `Description-de.UTF-8`. Any other field is
+// folded into a single line.
+const TEMPLATE_FIELD_KINDS: &[(UncasedStrRef, FieldKind)] = &[
+    field("Description*", FieldKind::Multiline),
+    field("Choices*", FieldKind::Multiline),
+];
+
+/// Every member of a debian package's control archive
+///
+/// The `control` file is parsed up front. The well known `md5sums`,
+/// `conffiles`, `shlibs`, `symbols`, `triggers` and `templates` files are
+/// parsed by their accessors, so a malformed optional member does not stop
+/// the rest of the archive from being read. Every file, including
+/// maintainer scripts and unknown members, is also kept as raw bytes.
+#[derive(Debug)]
+pub struct ControlArchive {
+    control: Control,
+    members: IndexMap<String, Vec<u8>>,
+}
+
+impl ControlArchive {
+    /// Reads every member of the control archive in a single pass. Only a
+    /// missing or malformed `control` file is an error.
+    ///
+    /// # Arguments
+    ///
+    /// * `archive` - The control tar returned by `DebPkg::control`
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use debpkg::{DebPkg, ControlArchive};
+    /// let file = std::fs::File::open("test.deb").unwrap();
+    /// let mut pkg = DebPkg::parse(file).unwrap();
+    /// let control_tar = pkg.control().unwrap();
+    /// let archive = ControlArchive::extract(control_tar).unwrap();
+    /// println!("{}", archive.control().name());
+    /// if let Some(md5sums) = archive.md5sums() {
+    ///     for (path, digest) in md5sums.unwrap().iter() {
+    ///         println!("{digest}  {}", path.display());
+    ///     }
+    /// }
+    /// ```
+    pub fn extract<R: Read>(mut archive: tar::Archive<R>) -> Result<ControlArchive> {
+        let mut members = IndexMap::new();
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            if entry.header().entry_type() != tar::EntryType::Regular {
+                continue;
+            }
+            let name = relative_path(&entry.path()?.to_string_lossy())
+                .to_string_lossy()
+                .into_owned();
+            let mut contents = Vec::new();
+            entry.read_to_end(&mut contents)?;
+            members.insert(name, contents);
+        }
+
+        let control = match members.get("control") {
+            Some(contents) => Control::parse(&contents[..])?,
+            None => return Err(Error::MissingControlFile),
+        };
+
+        Ok(ControlArchive { control, members })
+    }
+
+    /// Returns the parsed `control` file
+    pub fn control(&self) -> &Control {
+        &self.control
+    }
+
+    /// Consumes the archive, returning the parsed `control` file
+    pub fn into_control(self) -> Control {
+        self.control
+    }
+
+    /// Parses the `md5sums` file if the package has one
+    pub fn md5sums(&self) -> Option<Result<Md5Sums>> {
+        self.member("md5sums").map(Md5Sums::parse)
+    }
+
+    /// Parses the `conffiles` file if the package has one
+    pub fn conffiles(&self) -> Option<Result<Vec<Conffile>>> {
+        self.member("conffiles").map(parse_conffiles)
+    }
+
+    /// Parses the `shlibs` file if the package has one
+    pub fn shlibs(&self) -> Option<Result<Vec<ShlibsEntry>>> {
+        self.member("shlibs").map(parse_shlibs)
+    }
+
+    /// Parses the `symbols` file if the package has one
+    pub fn symbols(&self) -> Option<Result<Vec<SymbolsLibrary>>> {
+        self.member("symbols").map(parse_symbols)
+    }
+
+    /// Parses the `triggers` file if the package has one
+    pub fn triggers(&self) -> Option<Result<Vec<Trigger>>> {
+        self.member("triggers").map(parse_triggers)
+    }
+
+    /// Parses the debconf templates if the package has a `templates` file
+    pub fn templates(&self) -> Option<Result<Vec<Paragraph>>> {
+        self.member("templates").map(|contents| {
+            Deb822Reader::with_field_kinds(contents, TEMPLATE_FIELD_KINDS).collect()
+        })
+    }
+
+    /// Returns the raw contents of a member, such as `postinst`
+    ///
+    /// # Arguments
+    ///
+    /// * name - The member name without any leading `./`
+    pub fn member(&self, name: &str) -> Option<&[u8]> {
+        self.members.get(name).map(|contents| &contents[..])
+    }
+
+    /// Returns the names of every regular file in the control archive, in
+    /// archive order
+    pub fn member_names(&self) -> impl Iterator<Item = &str> {
+        self.members.keys().map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+
+    #[test]
+    fn md5sums_parse() {
+        let md5sums = Md5Sums::parse(
+            &b"0123456789abcdef0123456789ABCDEF  usr/bin/xz\nfedcba9876543210fedcba9876543210  usr/share/doc/xz utils/README\n"[..],
+        )
+        .unwrap();
+        assert_eq!(md5sums.len(), 2);
+        assert_eq!(
+            md5sums.get("/usr/bin/xz"),
+            Some("0123456789abcdef0123456789abcdef")
+        );
+        assert!(md5sums.get("usr/share/doc/xz utils/README").is_some());
+    }
+
+    #[test]
+    fn md5sums_bad_digest_fails() {
+        let err = Md5Sums::parse(&b"0123  usr/bin/xz\n"[..]).unwrap_err();
+        assert_matches!(err, Error::InvalidControlMember(_));
+    }
+
+    #[test]
+    fn md5sums_missing_path_fails() {
+        let err = Md5Sums::parse(&b"0123456789abcdef0123456789abcdef\n"[..]).unwrap_err();
+        assert_matches!(err, Error::InvalidControlMember(_));
+    }
+
+    #[test]
+    fn conffiles_parse() {
+        let conffiles =
+            parse_conffiles(&b"/etc/foo.conf\n\nremove-on-upgrade /etc/old.conf\n"[..]).unwrap();
+        assert_eq!(conffiles.len(), 2);
+        assert_eq!(conffiles[0].path, Path::new("/etc/foo.conf"));
+        assert!(!conffiles[0].remove_on_upgrade);
+        assert!(conffiles[1].remove_on_upgrade);
+    }
+
+    #[test]
+    fn conffiles_relative_path_fails() {
+        let err = parse_conffiles(&b"etc/foo.conf\n"[..]).unwrap_err();
+        assert_matches!(err, Error::InvalidControlMember(_));
+    }
+
+    #[test]
+    fn shlibs_parse() {
+        let shlibs =
+            parse_shlibs(&b"liblzma 5 liblzma5 (>= 5.2.2)\nudeb: liblzma 5 liblzma5-udeb\n"[..])
+                .unwrap();
+        assert_eq!(shlibs.len(), 2);
+        assert_eq!(shlibs[0].library, "liblzma");
+        assert_eq!(shlibs[0].dependencies, "liblzma5 (>= 5.2.2)");
+        assert_eq!(shlibs[1].package_type.as_deref(), Some("udeb"));
+        assert_eq!(shlibs[1].version, "5");
+    }
+
+    #[test]
+    fn symbols_parse() {
+        let text = b"liblzma.so.5 liblzma5 #MINVER#
+| liblzma5-extra
+* Build-Depends-Package: liblzma-dev
+ lzma_code@XZ_5.0 5.1.1alpha+20120614
+ (c++)\"foo(int)@Base\" 5.2 1
+ (optional=templinst)bar@Base 5.0
+";
+        let libraries = parse_symbols(&text[..]).unwrap();
+        assert_eq!(libraries.len(), 1);
+        let library = &libraries[0];
+        assert_eq!(library.soname, "liblzma.so.5");
+        assert_eq!(library.dependency_template, "liblzma5 #MINVER#");
+        assert_eq!(library.alternative_templates, ["liblzma5-extra"]);
+        assert_eq!(
+            library.fields,
+            [("Build-Depends-Package".to_owned(), "liblzma-dev".to_owned())]
+        );
+        assert_eq!(library.symbols.len(), 3);
+        assert_eq!(library.symbols[0].name, "lzma_code@XZ_5.0");
+        assert_eq!(library.symbols[1].name, "(c++)\"foo(int)@Base\"");
+        assert_eq!(library.symbols[1].dependency_id, 1);
+        assert_eq!(library.symbols[2].name, "(optional=templinst)bar@Base");
+        assert_eq!(library.symbols[2].minimal_version, "5.0");
+    }
+
+    #[test]
+    fn symbols_without_library_fails() {
+        let err = parse_symbols(&b" foo@Base 1.0\n"[..]).unwrap_err();
+        assert_matches!(err, Error::InvalidControlMember(_));
+    }
+
+    #[test]
+    fn triggers_parse() {
+        let triggers = parse_triggers(
+            &b"# comment\ninterest-noawait /usr/share/icons\nactivate ldconfig\n"[..],
+        )
+        .unwrap();
+        assert_eq!(triggers.len(), 2);
+        assert_eq!(triggers[0].directive, TriggerDirective::InterestNoawait);
+        assert_eq!(triggers[0].name, "/usr/share/icons");
+        assert_eq!(triggers[1].directive, TriggerDirective::Activate);
+    }
+
+    #[test]
+    fn triggers_unknown_directive_fails() {
+        let err = parse_triggers(&b"poke foo\n"[..]).unwrap_err();
+        assert_matches!(err, Error::InvalidControlMember(_));
+    }
+
+    #[test]
+    fn malformed_optional_member_is_not_fatal() {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (name, contents) in [
+            ("./control", &b"Package: name\nVersion: 1.0\n"[..]),
+            ("./triggers", &b"poke foo\n"[..]),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            builder.append_data(&mut header, name, contents).unwrap();
+        }
+        let tar = builder.into_inner().unwrap();
+
+        let archive = ControlArchive::extract(tar::Archive::new(&tar[..])).unwrap();
+        assert_eq!(archive.control().name(), "name");
+        assert_matches!(
+            archive.triggers(),
+            Some(Err(Error::InvalidControlMember(_)))
+        );
+        assert!(archive.symbols().is_none());
+        assert_eq!(archive.member("triggers"), Some(&b"poke foo\n"[..]));
+    }
+
+    #[test]
+    fn templates_keep_translations_multiline() {
+        let mut members = IndexMap::new();
+        members.insert(
+            "templates".to_owned(),
+            b"Template: name/mode\nType: select\nChoices: fast, safe\nChoices-de.UTF-8: schnell, sicher\nDescription: Mode\n Pick a mode.\n .\n Fast is unsafe.\nDescription-de.UTF-8: Modus\n Modus waehlen.\n .\n Schnell ist unsicher.\n"
+                .to_vec(),
+        );
+        let archive = ControlArchive {
+            control: Control::parse(&b"Package: name\nVersion: 1.0\n"[..]).unwrap(),
+            members,
+        };
+        let templates = archive.templates().unwrap().unwrap();
+        assert_eq!(templates.len(), 1);
+        let template = &templates[0];
+        assert_eq!(
+            template.continuation("Description"),
+            Some("Pick a mode.\n\nFast is unsafe.")
+        );
+        assert_eq!(template.get("Description-de.UTF-8"), Some("Modus"));
+        assert_eq!(
+            template.continuation("description-DE.utf-8"),
+            Some("Modus waehlen.\n\nSchnell ist unsicher.")
+        );
+        assert_eq!(template.get("Choices-de.UTF-8"), Some("schnell, sicher"));
+        assert_eq!(template.continuation("Choices-de.UTF-8"), Some(""));
+    }
+}
@@ -0,0 +1,480 @@
+use std::io::{Read, Seek};
+use std::path::{Component, PathBuf};
+
+use crate::control::{field, FieldKind, UncasedStrRef};
+use crate::{Control, Deb822Reader, DebPkg, Paragraph, Result, SeekableDebPkg};
+
+// The fields of a machine-readable copyright file which hold formatted text.
+// Any other field is folded into a single line.
+const COPYRIGHT_FIELD_KINDS: &[(UncasedStrRef, FieldKind)] = &[
+    field("Files", FieldKind::Multiline),
+    field("Copyright", FieldKind::Multiline),
+    field("License", FieldKind::Multiline),
+    field("Comment", FieldKind::Multiline),
+    field("Disclaimer", FieldKind::Multiline),
+    field("Source", FieldKind::Multiline),
+    field("Upstream-Contact", FieldKind::Multiline),
+];
+
+// Returns the value of a formatted text field with its first line and its
+// continuation lines joined by newlines
+fn formatted_text(paragraph: &Paragraph, name: &str) -> Option<String> {
+    let first = paragraph.get(name)?;
+    let rest = paragraph.continuation(name).unwrap_or_default();
+    Some(match (first.is_empty(), rest.is_empty()) {
+        (_, true) => first.to_owned(),
+        (true, false) => rest.to_owned(),
+        (false, false) => format!("{first}\n{rest}"),
+    })
+}
+
+/// A license named in a `License` field of a machine-readable copyright
+/// file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct License {
+    /// The short name of the license, such as `GPL-2+`, or an expression
+    /// such as `GPL-2+ or Artistic`. Empty if the field has no first line.
+    pub name: String,
+
+    /// The full text of the license, if the field has continuation lines
+    pub text: Option<String>,
+}
+
+impl License {
+    fn from_paragraph(paragraph: &Paragraph) -> Option<License> {
+        let name = paragraph.get("License")?.to_owned();
+        let text = paragraph
+            .continuation("License")
+            .filter(|text| !text.is_empty())
+            .map(str::to_owned);
+        Some(License { name, text })
+    }
+}
+
+/// A `Files` paragraph of a machine-readable copyright file, giving the
+/// copyright and license of the files matching its patterns
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CopyrightFiles {
+    /// The glob patterns of the `Files` field, relative to the root of the
+    /// source package
+    pub patterns: Vec<String>,
+
+    /// The copyright holders, one per line
+    pub copyright: Option<String>,
+
+    /// The license of the files
+    pub license: License,
+
+    /// The `Comment` field
+    pub comment: Option<String>,
+}
+
+impl CopyrightFiles {
+    /// Returns true if any of the patterns matches `path`
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - A path relative to the root of the source package. A
+    ///   leading `./` is ignored.
+    pub fn matches(&self, path: &str) -> bool {
+        let path = path.trim_start_matches("./");
+        self.patterns
+            .iter()
+            .any(|pattern| glob_match(pattern.trim_start_matches("./"), path))
+    }
+}
+
+// Matches a path against a DEP-5 pattern, where `*` matches any characters
+// including `/` and `?` matches any single character. Either may be escaped
+// with a backslash, as may a backslash itself.
+fn glob_match(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let path: Vec<char> = path.chars().collect();
+    let (mut p, mut s) = (0, 0);
+    // Where to resume after the last `*` if the rest fails to match
+    let mut backtrack = None;
+
+    while s < path.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p + 1, s));
+                p += 1;
+                continue;
+            }
+            Some('?') => {
+                p += 1;
+                s += 1;
+                continue;
+            }
+            Some('\\') if pattern.get(p + 1) == Some(&path[s]) => {
+                p += 2;
+                s += 1;
+                continue;
+            }
+            Some(&c) if c != '\\' && c == path[s] => {
+                p += 1;
+                s += 1;
+                continue;
+            }
+            _ => (),
+        }
+        match backtrack {
+            Some((star_p, star_s)) => {
+                p = star_p;
+                s = star_s + 1;
+                backtrack = Some((star_p, star_s + 1));
+            }
+            None => return false,
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// A parsed `debian/copyright` file, as installed to
+/// `/usr/share/doc/<package>/copyright`
+///
+/// Files in the [machine-readable
+/// format](https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/)
+/// (DEP-5) are split into their header, `Files` and standalone `License`
+/// paragraphs. Any other copyright file is kept as plain text, so
+/// [`text`](Copyright::text) is always available.
+#[derive(Debug, Clone)]
+pub struct Copyright {
+    text: String,
+    header: Option<Paragraph>,
+    license: Option<License>,
+    files: Vec<CopyrightFiles>,
+    licenses: Vec<License>,
+}
+
+impl Copyright {
+    /// Parses a copyright file
+    ///
+    /// A file is treated as machine-readable if its first paragraph has a
+    /// `Format` field and every other paragraph is a valid `Files` or
+    /// `License` paragraph. Otherwise it is kept as plain text and no error
+    /// is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - A type which implements read and contains a copyright
+    ///   file
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let file = std::fs::File::open("/usr/share/doc/xz-utils/copyright").unwrap();
+    /// let copyright = debpkg::Copyright::parse(file).unwrap();
+    /// if let Some(license) = copyright.license_for("src/xz/main.c") {
+    ///     println!("{}", license.name);
+    /// }
+    /// ```
+    pub fn parse<R: Read>(mut reader: R) -> Result<Copyright> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let text = String::from_utf8_lossy(&bytes).into_owned();
+
+        let mut copyright = Copyright {
+            text,
+            header: None,
+            license: None,
+            files: Vec::new(),
+            licenses: Vec::new(),
+        };
+        if let Some((header, files, licenses)) = parse_machine_readable(&copyright.text) {
+            copyright.license = License::from_paragraph(&header);
+            copyright.header = Some(header);
+            copyright.files = files;
+            copyright.licenses = licenses;
+        }
+        Ok(copyright)
+    }
+
+    /// Reads `usr/share/doc/<package>/copyright` out of a data archive.
+    /// Returns None if the package does not contain it, for example because
+    /// its documentation directory is a symlink to another package's.
+    ///
+    /// # Arguments
+    ///
+    /// * `archive` - The data archive of the package
+    /// * `package` - The name of the package
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use debpkg::{Control, Copyright, DebPkg};
+    /// let file = std::fs::File::open("test.deb").unwrap();
+    /// let mut pkg = DebPkg::parse(file).unwrap();
+    /// let control = Control::extract(pkg.control().unwrap()).unwrap();
+    /// let copyright = Copyright::extract(pkg.data().unwrap(), control.name()).unwrap();
+    /// ```
+    pub fn extract<R: Read>(
+        mut archive: tar::Archive<R>,
+        package: &str,
+    ) -> Result<Option<Copyright>> {
+        let wanted: PathBuf = ["usr", "share", "doc", package, "copyright"]
+            .iter()
+            .collect();
+        for entry in archive.entries()? {
+            let entry = entry?;
+            let path: PathBuf = entry
+                .path()?
+                .components()
+                .filter(|component| matches!(component, Component::Normal(_)))
+                .collect();
+            if path == wanted && entry.header().entry_type().is_file() {
+                return Copyright::parse(entry).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns true if the file is in the machine-readable format
+    pub fn is_machine_readable(&self) -> bool {
+        self.header.is_some()
+    }
+
+    /// Returns the whole file as text
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the header paragraph of a machine-readable file
+    pub fn header(&self) -> Option<&Paragraph> {
+        self.header.as_ref()
+    }
+
+    /// Returns the `Upstream-Name` field of a machine-readable file
+    pub fn upstream_name(&self) -> Option<&str> {
+        self.header.as_ref()?.get("Upstream-Name")
+    }
+
+    /// Returns the `License` field of the header, which usually summarizes
+    /// the licenses of the whole package
+    pub fn license(&self) -> Option<&License> {
+        self.license.as_ref()
+    }
+
+    /// Returns the `Files` paragraphs in file order
+    pub fn files(&self) -> &[CopyrightFiles] {
+        &self.files
+    }
+
+    /// Returns the standalone `License` paragraphs, which hold the text of
+    /// licenses named in `Files` paragraphs
+    pub fn licenses(&self) -> &[License] {
+        &self.licenses
+    }
+
+    /// Returns the `Files` paragraph which applies to `path`. As the format
+    /// requires, the last matching paragraph wins.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - A path relative to the root of the source package
+    pub fn files_for(&self, path: &str) -> Option<&CopyrightFiles> {
+        self.files.iter().rev().find(|files| files.matches(path))
+    }
+
+    /// Returns the license which applies to `path`, or None if no `Files`
+    /// paragraph matches or the file is not machine-readable. Use
+    /// [`license_text`](Copyright::license_text) to find the text of the
+    /// license if it is given elsewhere in the file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - A path relative to the root of the source package
+    pub fn license_for(&self, path: &str) -> Option<&License> {
+        self.files_for(path).map(|files| &files.license)
+    }
+
+    /// Returns the full text of a license by its short name, which is case
+    /// insensitive. Standalone `License` paragraphs are searched first, then
+    /// the `Files` paragraphs and the header.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The short name of the license, such as `GPL-2+`
+    pub fn license_text(&self, name: &str) -> Option<&str> {
+        self.licenses
+            .iter()
+            .chain(self.files.iter().map(|files| &files.license))
+            .chain(self.license.as_ref())
+            .filter(|license| license.name.eq_ignore_ascii_case(name))
+            .find_map(|license| license.text.as_deref())
+    }
+}
+
+// Splits a machine-readable copyright file into its header, `Files` and
+// standalone `License` paragraphs. Returns None if the file has no `Format`
+// header or does not follow the format, so it is kept as plain text.
+fn parse_machine_readable(text: &str) -> Option<(Paragraph, Vec<CopyrightFiles>, Vec<License>)> {
+    let mut paragraphs = Deb822Reader::with_field_kinds(text.as_bytes(), COPYRIGHT_FIELD_KINDS);
+    let header = paragraphs.next()?.ok()?;
+    header.get("Format")?;
+
+    let mut files = Vec::new();
+    let mut licenses = Vec::new();
+    for paragraph in paragraphs {
+        let paragraph = paragraph.ok()?;
+        let license = License::from_paragraph(&paragraph);
+        match (formatted_text(&paragraph, "Files"), license) {
+            (Some(patterns), Some(license)) => files.push(CopyrightFiles {
+                patterns: patterns.split_whitespace().map(str::to_owned).collect(),
+                copyright: formatted_text(&paragraph, "Copyright"),
+                license,
+                comment: formatted_text(&paragraph, "Comment"),
+            }),
+            (None, Some(license)) => licenses.push(license),
+            (_, None) => return None,
+        }
+    }
+    Some((header, files, licenses))
+}
+
+impl<R: Read> DebPkg<R> {
+    /// Reads the copyright file of the package. See [`Copyright::extract`].
+    ///
+    /// The control and data archives are both read, so this must be called
+    /// before `control` or `data`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use debpkg::DebPkg;
+    /// let file = std::fs::File::open("test.deb").unwrap();
+    /// let mut pkg = DebPkg::parse(file).unwrap();
+    /// if let Some(copyright) = pkg.copyright().unwrap() {
+    ///     for files in copyright.files() {
+    ///         println!("{}: {}", files.patterns.join(" "), files.license.name);
+    ///     }
+    /// }
+    /// ```
+    pub fn copyright(&mut self) -> Result<Option<Copyright>> {
+        let control = Control::extract(self.control()?)?;
+        Copyright::extract(self.data()?, control.name())
+    }
+}
+
+impl<R: Read + Seek> SeekableDebPkg<R> {
+    /// Reads the copyright file of the package. See [`Copyright::extract`].
+    pub fn copyright(&mut self) -> Result<Option<Copyright>> {
+        let control = Control::extract(self.control()?)?;
+        Copyright::extract(self.data()?, control.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEP5: &str = "Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/
+Upstream-Name: hello
+Source: https://example.org/hello
+
+Files: *
+Copyright: 2001 Alice
+ 2002 Bob
+License: GPL-2+
+
+Files: debian/*
+ src/gen_?.c
+Copyright: 2020 Carol
+License: MIT
+ Permission is hereby granted.
+ .
+ The software is provided as is.
+Comment: Generated
+
+License: GPL-2+
+ This program is free software.
+";
+
+    #[test]
+    fn machine_readable_file_is_parsed() {
+        let copyright = Copyright::parse(DEP5.as_bytes()).unwrap();
+        assert!(copyright.is_machine_readable());
+        assert_eq!(copyright.upstream_name(), Some("hello"));
+        assert_eq!(copyright.files().len(), 2);
+
+        let all = &copyright.files()[0];
+        assert_eq!(all.patterns, ["*"]);
+        assert_eq!(all.copyright.as_deref(), Some("2001 Alice\n2002 Bob"));
+        assert_eq!(all.license.text, None);
+
+        let debian = &copyright.files()[1];
+        assert_eq!(debian.patterns, ["debian/*", "src/gen_?.c"]);
+        assert_eq!(
+            debian.license.text.as_deref(),
+            Some("Permission is hereby granted.\n\nThe software is provided as is.")
+        );
+        assert_eq!(debian.comment.as_deref(), Some("Generated"));
+        assert_eq!(copyright.licenses().len(), 1);
+    }
+
+    #[test]
+    fn last_matching_paragraph_wins() {
+        let copyright = Copyright::parse(DEP5.as_bytes()).unwrap();
+        let license = |path| copyright.license_for(path).map(|l| l.name.as_str());
+        assert_eq!(license("src/main.c"), Some("GPL-2+"));
+        assert_eq!(license("./debian/rules"), Some("MIT"));
+        assert_eq!(license("src/gen_a.c"), Some("MIT"));
+        assert_eq!(license("src/gen_ab.c"), Some("GPL-2+"));
+        assert_eq!(
+            copyright.license_text("gpl-2+"),
+            Some("This program is free software.")
+        );
+        assert_eq!(copyright.license_text("BSD-3-clause"), None);
+    }
+
+    #[test]
+    fn globs_match() {
+        assert!(glob_match("*", "a/b/c"));
+        assert!(glob_match("src/*.c", "src/x/y.c"));
+        assert!(glob_match("a*b*c", "aXbYbZc"));
+        assert!(!glob_match("a*b*c", "aXbYbZ"));
+        assert!(glob_match("?.h", "a.h"));
+        assert!(!glob_match("?.h", "ab.h"));
+        assert!(glob_match("\\*.h", "*.h"));
+        assert!(!glob_match("\\*.h", "a.h"));
+        assert!(glob_match("a\\\\b", "a\\b"));
+    }
+
+    #[test]
+    fn plain_text_falls_back() {
+        let text = "This package was debianized by Alice.\n\nIt is licensed under the GPL.\n";
+        let copyright = Copyright::parse(text.as_bytes()).unwrap();
+        assert!(!copyright.is_machine_readable());
+        assert_eq!(copyright.text(), text);
+        assert!(copyright.files().is_empty());
+        assert_eq!(copyright.license_for("src/main.c"), None);
+    }
+
+    #[test]
+    fn files_need_a_license() {
+        let text = "Format: dep5\n\nFiles: *\nCopyright: 2001 Alice\n";
+        let copyright = Copyright::parse(text.as_bytes()).unwrap();
+        assert!(!copyright.is_machine_readable());
+        assert!(copyright.header().is_none());
+        assert_eq!(copyright.text(), text);
+    }
+
+    #[test]
+    fn malformed_machine_readable_file_falls_back() {
+        let text = "Format: dep5\n\nFiles: *\nLicense: MIT\nLicense: GPL-2+\n";
+        let copyright = Copyright::parse(text.as_bytes()).unwrap();
+        assert!(!copyright.is_machine_readable());
+        assert_eq!(copyright.text(), text);
+        assert_eq!(copyright.license_for("src/main.c"), None);
+    }
+
+    #[test]
+    fn license_text_keeps_indentation() {
+        let text = "Format: dep5\n\nFiles: *\nLicense: MIT\n Terms:\n .\n   1. Keep this notice.\n";
+        let copyright = Copyright::parse(text.as_bytes()).unwrap();
+        assert_eq!(
+            copyright.files()[0].license.text.as_deref(),
+            Some("Terms:\n\n  1. Keep this notice.")
+        );
+    }
+}
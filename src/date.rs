@@ -0,0 +1,211 @@
+// Minimal UTC date handling for the date fields of Release files and file
+// listings, so the crate does not need a full date library
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Days since 1970-01-01 to a (year, month, day) civil date. This is Howard
+// Hinnant's `civil_from_days` algorithm.
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+// A (year, month, day) civil date to days since 1970-01-01, the inverse of
+// `civil_from_days`
+pub(crate) fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let month = i64::from(month);
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+// Splits seconds since the unix epoch into a civil date and the time of day
+pub(crate) fn split_timestamp(secs: i64) -> ((i64, u32, u32), (u32, u32, u32)) {
+    let days = secs.div_euclid(86_400);
+    let rem = secs.rem_euclid(86_400);
+    let time = (
+        (rem / 3600) as u32,
+        (rem % 3600 / 60) as u32,
+        (rem % 60) as u32,
+    );
+    (civil_from_days(days), time)
+}
+
+const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+// Formats seconds since the unix epoch the way APT expects in Release files,
+// e.g. `Thu, 01 Jan 1970 00:00:00 UTC`
+pub(crate) fn format_rfc2822(secs: i64) -> String {
+    let ((year, month, day), (hour, minute, second)) = split_timestamp(secs);
+    let weekday = WEEKDAYS[secs.div_euclid(86_400).rem_euclid(7) as usize];
+    format!(
+        "{weekday}, {day:02} {} {year} {hour:02}:{minute:02}:{second:02} UTC",
+        MONTHS[month as usize - 1]
+    )
+}
+
+// Formats seconds since the unix epoch as an ISO 8601 UTC timestamp, e.g.
+// `1970-01-01T00:00:00Z`, as used in SBOMs
+#[cfg(feature = "sbom")]
+pub(crate) fn format_iso8601(secs: i64) -> String {
+    let ((year, month, day), (hour, minute, second)) = split_timestamp(secs);
+    format!("{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}Z")
+}
+
+// Parses an RFC 2822 date as used in the Date and Valid-Until fields of
+// Release files, e.g. `Sat, 26 Apr 2025 09:33:20 UTC`, into seconds since the
+// unix epoch. The weekday is optional and not checked.
+pub(crate) fn parse_rfc2822(date: &str) -> Option<i64> {
+    let mut parts = date.split_whitespace().peekable();
+    if parts.peek()?.ends_with(',') {
+        parts.next();
+    }
+
+    let day: u32 = parts.next()?.parse().ok()?;
+    let month_name = parts.next()?;
+    let month = MONTHS
+        .iter()
+        .position(|name| name.eq_ignore_ascii_case(month_name))? as u32
+        + 1;
+    // Four digit years keep the arithmetic below from overflowing
+    let year = parts.next()?;
+    if year.len() != 4 || !year.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let year: i64 = year.parse().ok()?;
+    if !(1..=31).contains(&day) {
+        return None;
+    }
+
+    let mut time = parts.next()?.split(':');
+    let hour: i64 = time.next()?.parse().ok()?;
+    let minute: i64 = time.next()?.parse().ok()?;
+    let second: i64 = time.next().unwrap_or("0").parse().ok()?;
+    if time.next().is_some() || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    let offset = match parts.next() {
+        None | Some("UTC") | Some("GMT") | Some("Z") => 0,
+        Some(zone) => {
+            let (sign, digits) = match zone.split_at(1) {
+                ("+", digits) => (1, digits),
+                ("-", digits) => (-1, digits),
+                _ => return None,
+            };
+            if digits.len() != 4 || !digits.bytes().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            let hours: i64 = digits[..2].parse().ok()?;
+            let minutes: i64 = digits[2..].parse().ok()?;
+            sign * (hours * 3600 + minutes * 60)
+        }
+    };
+    if parts.next().is_some() {
+        return None;
+    }
+
+    days_from_civil(year, month, day)
+        .checked_mul(86_400)?
+        .checked_add(hour * 3600 + minute * 60 + second)?
+        .checked_sub(offset)
+}
+
+// Converts seconds since the unix epoch, possibly negative, to a SystemTime.
+// Returns None if the platform can not represent the time.
+pub(crate) fn system_time(secs: i64) -> Option<SystemTime> {
+    if secs >= 0 {
+        UNIX_EPOCH.checked_add(Duration::from_secs(secs as u64))
+    } else {
+        UNIX_EPOCH.checked_sub(Duration::from_secs(secs.unsigned_abs()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn epoch_formats() {
+        assert_eq!(format_rfc2822(0), "Thu, 01 Jan 1970 00:00:00 UTC");
+    }
+
+    #[test]
+    fn leap_day_formats() {
+        assert_eq!(format_rfc2822(951_782_400), "Tue, 29 Feb 2000 00:00:00 UTC");
+    }
+
+    #[test]
+    fn recent_date_formats() {
+        assert_eq!(
+            format_rfc2822(1_745_660_000),
+            "Sat, 26 Apr 2025 09:33:20 UTC"
+        );
+    }
+
+    #[test]
+    fn dates_parse() {
+        assert_eq!(parse_rfc2822("Thu, 01 Jan 1970 00:00:00 UTC"), Some(0));
+        assert_eq!(
+            parse_rfc2822("Sat, 26 Apr 2025 09:33:20 UTC"),
+            Some(1_745_660_000)
+        );
+        assert_eq!(
+            parse_rfc2822("29 Feb 2000 00:00:00 +0000"),
+            Some(951_782_400)
+        );
+        assert_eq!(
+            parse_rfc2822("Sat, 26 Apr 2025 11:33:20 +0200"),
+            Some(1_745_660_000)
+        );
+        assert_eq!(parse_rfc2822("Wed, 31 Dec 1969 23:59:59 UTC"), Some(-1));
+    }
+
+    #[test]
+    fn bad_dates_fail() {
+        assert_eq!(parse_rfc2822(""), None);
+        assert_eq!(parse_rfc2822("Sat, 26 Foo 2025 09:33:20 UTC"), None);
+        assert_eq!(parse_rfc2822("Sat, 26 Apr 2025 25:33:20 UTC"), None);
+        assert_eq!(parse_rfc2822("Sat, 26 Apr 2025 09:33:20 CEST"), None);
+        assert_eq!(parse_rfc2822("Sat, 26 Apr 2025 09:33:20 UTC extra"), None);
+        assert_eq!(
+            parse_rfc2822("Sat, 26 Apr 9999999999999 09:33:20 UTC"),
+            None
+        );
+        assert_eq!(parse_rfc2822("Sat, 26 Apr -2025 09:33:20 UTC"), None);
+    }
+
+    #[test]
+    fn extreme_times_do_not_panic() {
+        assert_eq!(system_time(0), Some(UNIX_EPOCH));
+        let _ = system_time(i64::MAX);
+        let _ = system_time(i64::MIN);
+    }
+
+    #[test]
+    fn days_round_trip() {
+        for days in [-800_000, -1, 0, 1, 11_016, 20_000, 2_000_000] {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+
+    #[test]
+    fn before_epoch_formats() {
+        assert_eq!(format_rfc2822(-1), "Wed, 31 Dec 1969 23:59:59 UTC");
+    }
+}
@@ -0,0 +1,208 @@
+use std::io::{BufRead, BufReader, Lines, Read};
+
+use crate::control::{FieldKind, ParagraphParser, UncasedStrRef, FIELD_KINDS};
+use crate::{ClearSigned, Paragraph, Result};
+
+/// Reads the paragraphs of a multi-paragraph deb822 file one at a time
+///
+/// This is the format of APT `Packages` and `Sources` indices and of
+/// `/var/lib/dpkg/status`. Only the paragraph currently being parsed is held
+/// in memory.
+///
+/// Continuation lines of well known multiline fields, such as `Description`
+/// or `Checksums-Sha256`, are kept as separate lines. Continuation lines of
+/// any other field are folded into a single line.
+///
+/// If a paragraph fails to parse the error is returned and the reader skips
+/// to the next paragraph.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::convert::TryFrom;
+/// let file = std::fs::File::open("/var/lib/dpkg/status").unwrap();
+/// for paragraph in debpkg::Deb822Reader::new(file) {
+///     let control = debpkg::Control::try_from(paragraph.unwrap()).unwrap();
+///     println!("{} {}", control.name(), control.version());
+/// }
+/// ```
+pub struct Deb822Reader<R: Read> {
+    lines: Lines<BufReader<R>>,
+
+    // The kinds of the fields the paragraphs are expected to hold
+    field_kinds: &'static [(UncasedStrRef<'static>, FieldKind)],
+
+    // The number of lines read so far, for errors
+    line_number: usize,
+}
+
+impl<R: Read> Deb822Reader<R> {
+    /// Creates a reader over the paragraphs in `reader`
+    pub fn new(reader: R) -> Deb822Reader<R> {
+        Deb822Reader::with_field_kinds(reader, FIELD_KINDS)
+    }
+
+    // Creates a reader for a file format whose fields are stored differently
+    // from those of APT indices, such as `debian/copyright`
+    pub(crate) fn with_field_kinds(
+        reader: R,
+        field_kinds: &'static [(UncasedStrRef<'static>, FieldKind)],
+    ) -> Deb822Reader<R> {
+        Deb822Reader {
+            lines: BufReader::new(reader).lines(),
+            field_kinds,
+            line_number: 0,
+        }
+    }
+}
+
+impl<R: Read> Iterator for Deb822Reader<R> {
+    type Item = Result<Paragraph>;
+
+    fn next(&mut self) -> Option<Result<Paragraph>> {
+        let mut parser = ParagraphParser::new(self.field_kinds, true);
+        let mut error = None;
+
+        for line in self.lines.by_ref() {
+            self.line_number += 1;
+            let line = match line {
+                Ok(line) => line,
+                Err(err) => return Some(Err(err.into())),
+            };
+
+            if line.trim_end().is_empty() {
+                if parser.is_empty() && error.is_none() {
+                    // Extra separator lines between paragraphs
+                    continue;
+                }
+                break;
+            }
+
+            // Keep consuming the rest of a broken paragraph so the next call
+            // starts at the following one
+            if error.is_none() {
+                if let Err(err) = parser.parse_line(self.line_number, &line) {
+                    error = Some(err);
+                }
+            }
+        }
+
+        match error {
+            Some(err) => Some(Err(err)),
+            None if parser.is_empty() => None,
+            None => Some(Ok(parser.finish())),
+        }
+    }
+}
+
+// Reads every paragraph of a file that may be wrapped in a cleartext
+// signature, such as an `InRelease`, `.dsc` or `.changes` file. The signature
+// is only removed, not verified.
+pub(crate) fn read_signed_paragraphs<R: Read>(mut reader: R) -> Result<Vec<Paragraph>> {
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+
+    let signed;
+    let body = if text
+        .trim_start()
+        .starts_with("-----BEGIN PGP SIGNED MESSAGE-----")
+    {
+        signed = ClearSigned::parse(&text)?;
+        signed.body()
+    } else {
+        &text
+    };
+    Deb822Reader::new(body.as_bytes()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Control, Error};
+    use assert_matches::assert_matches;
+    use std::convert::TryFrom;
+
+    const PACKAGES: &[u8] = b"Package: a
+Version: 1.0
+Depends: b,
+ c
+Description: first
+ long
+ .
+ more
+
+Package: b
+Version: 2.0
+Filename: pool/main/b/b_2.0_all.deb
+SHA256: 0123
+
+
+Package: c
+Version: 3.0
+";
+
+    #[test]
+    fn paragraphs_are_split() {
+        let paragraphs: Vec<Paragraph> =
+            Deb822Reader::new(PACKAGES).collect::<Result<_>>().unwrap();
+        assert_eq!(paragraphs.len(), 3);
+        assert_eq!(
+            paragraphs[1].get("filename"),
+            Some("pool/main/b/b_2.0_all.deb")
+        );
+        assert_eq!(paragraphs[1].get("SHA256"), Some("0123"));
+        assert_eq!(paragraphs[2].get("Package"), Some("c"));
+    }
+
+    #[test]
+    fn folded_and_multiline_fields() {
+        let paragraph = Deb822Reader::new(PACKAGES).next().unwrap().unwrap();
+        assert_eq!(paragraph.get("Depends"), Some("b, c"));
+        let control = Control::try_from(paragraph).unwrap();
+        assert_eq!(control.short_description(), Some("first"));
+        assert_eq!(control.long_description(), Some("long\n\nmore"));
+        assert_eq!(control.depends().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn checksum_lists_are_multiline() {
+        let text = b"Package: src\nChecksums-Sha256:\n abc 12 src_1.dsc\n def 34 src_1.tar.xz\n";
+        let mut paragraph = Deb822Reader::new(&text[..]).next().unwrap().unwrap();
+        assert_eq!(paragraph.get("Checksums-Sha256"), Some(""));
+        assert_eq!(
+            paragraph.remove("checksums-sha256"),
+            Some("\nabc 12 src_1.dsc\ndef 34 src_1.tar.xz".to_owned())
+        );
+    }
+
+    #[test]
+    fn empty_input_has_no_paragraphs() {
+        assert!(Deb822Reader::new(&b""[..]).next().is_none());
+        assert!(Deb822Reader::new(&b"\n\n# comment\n\n"[..])
+            .next()
+            .is_none());
+    }
+
+    #[test]
+    fn broken_paragraph_is_skipped() {
+        let text = b"Package: a\nbroken\nVersion: 1\n\nPackage: b\n";
+        let mut reader = Deb822Reader::new(&text[..]);
+        assert_matches!(reader.next(), Some(Err(Error::InvalidControlFile(ref err)))
+            if err.line == 2);
+        let paragraph = reader.next().unwrap().unwrap();
+        assert_eq!(paragraph.get("Package"), Some("b"));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn paragraph_without_version_is_not_control() {
+        let paragraph = Deb822Reader::new(&b"Package: a\n"[..])
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_matches!(
+            Control::try_from(paragraph),
+            Err(Error::MissingPackageVersion)
+        );
+    }
+}
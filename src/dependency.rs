@@ -0,0 +1,540 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{Error, Result, Version};
+
+use log::warn;
+
+/// The relation operator of a version constraint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionRelation {
+    /// `<<`
+    StrictlyEarlier,
+
+    /// `<=`
+    EarlierOrEqual,
+
+    /// `=`
+    Exactly,
+
+    /// `>=`
+    LaterOrEqual,
+
+    /// `>>`
+    StrictlyLater,
+}
+
+impl VersionRelation {
+    /// Returns the operator as written in a control file
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            VersionRelation::StrictlyEarlier => "<<",
+            VersionRelation::EarlierOrEqual => "<=",
+            VersionRelation::Exactly => "=",
+            VersionRelation::LaterOrEqual => ">=",
+            VersionRelation::StrictlyLater => ">>",
+        }
+    }
+}
+
+impl fmt::Display for VersionRelation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for VersionRelation {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<VersionRelation> {
+        match s {
+            "<<" => Ok(VersionRelation::StrictlyEarlier),
+            "<=" => Ok(VersionRelation::EarlierOrEqual),
+            "=" => Ok(VersionRelation::Exactly),
+            ">=" => Ok(VersionRelation::LaterOrEqual),
+            ">>" => Ok(VersionRelation::StrictlyLater),
+            // dpkg still accepts the obsolete forms and treats them as
+            // inclusive
+            "<" => {
+                warn!("Obsolete version relation '<', treating as '<='");
+                Ok(VersionRelation::EarlierOrEqual)
+            }
+            ">" => {
+                warn!("Obsolete version relation '>', treating as '>='");
+                Ok(VersionRelation::LaterOrEqual)
+            }
+            _ => Err(Error::InvalidRelationship(format!(
+                "unknown version relation '{s}'"
+            ))),
+        }
+    }
+}
+
+/// A version constraint such as `(>= 1.2)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionConstraint {
+    /// The relation operator
+    pub relation: VersionRelation,
+
+    /// The version the relation is against, exactly as written
+    pub version: String,
+}
+
+impl VersionConstraint {
+    /// Checks whether `version` satisfies this constraint
+    ///
+    /// Fails if the version in the constraint is not a valid Debian version.
+    pub fn satisfied_by(&self, version: &Version) -> Result<bool> {
+        let constraint: Version = self.version.parse()?;
+        let ordering = version.cmp(&constraint);
+        Ok(match self.relation {
+            VersionRelation::StrictlyEarlier => ordering.is_lt(),
+            VersionRelation::EarlierOrEqual => ordering.is_le(),
+            VersionRelation::Exactly => ordering.is_eq(),
+            VersionRelation::LaterOrEqual => ordering.is_ge(),
+            VersionRelation::StrictlyLater => ordering.is_gt(),
+        })
+    }
+}
+
+impl fmt::Display for VersionConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.relation, self.version)
+    }
+}
+
+/// A single, possibly negated, term of an architecture or build profile
+/// restriction such as `amd64` or `!nocheck`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Restriction {
+    /// Whether the term was prefixed with `!`
+    pub negated: bool,
+
+    /// The architecture or build profile name
+    pub name: String,
+}
+
+impl fmt::Display for Restriction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.negated {
+            f.write_str("!")?;
+        }
+        f.write_str(&self.name)
+    }
+}
+
+/// A reference to a single package within a relationship field
+///
+/// For example `libc6:any (>= 2.17) [amd64 i386] <!nocheck>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Relationship {
+    /// The package name
+    pub name: String,
+
+    /// The multiarch qualifier following `:`, e.g. `any` or `native`
+    pub arch_qualifier: Option<String>,
+
+    /// The optional version constraint
+    pub version: Option<VersionConstraint>,
+
+    /// The architecture restriction list. Empty if there is none.
+    pub architectures: Vec<Restriction>,
+
+    /// The build profile restriction formula. Each inner list is one `<...>`
+    /// group whose terms must all hold; the relationship applies if any group
+    /// holds. Empty if there is none.
+    pub profiles: Vec<Vec<Restriction>>,
+}
+
+impl fmt::Display for Relationship {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.name)?;
+        if let Some(ref qualifier) = self.arch_qualifier {
+            write!(f, ":{qualifier}")?;
+        }
+        if let Some(ref version) = self.version {
+            write!(f, " ({version})")?;
+        }
+        if !self.architectures.is_empty() {
+            f.write_str(" [")?;
+            write_terms(f, &self.architectures)?;
+            f.write_str("]")?;
+        }
+        for group in &self.profiles {
+            f.write_str(" <")?;
+            write_terms(f, group)?;
+            f.write_str(">")?;
+        }
+        Ok(())
+    }
+}
+
+fn write_terms(f: &mut fmt::Formatter, terms: &[Restriction]) -> fmt::Result {
+    for (i, term) in terms.iter().enumerate() {
+        if i != 0 {
+            f.write_str(" ")?;
+        }
+        write!(f, "{term}")?;
+    }
+    Ok(())
+}
+
+impl FromStr for Relationship {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Relationship> {
+        RelationshipParser::new(s).parse()
+    }
+}
+
+/// One comma separated entry of a relationship field. The entry is satisfied
+/// if any of its alternatives (separated by `|`) is satisfied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dependency {
+    /// The alternatives in the order they were listed
+    pub alternatives: Vec<Relationship>,
+}
+
+impl fmt::Display for Dependency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, alternative) in self.alternatives.iter().enumerate() {
+            if i != 0 {
+                f.write_str(" | ")?;
+            }
+            write!(f, "{alternative}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Dependency {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Dependency> {
+        let alternatives = s
+            .split('|')
+            .map(|alternative| alternative.parse())
+            .collect::<Result<Vec<Relationship>>>()?;
+        Ok(Dependency { alternatives })
+    }
+}
+
+/// Parses the value of a relationship field such as `Depends` or
+/// `Build-Depends`
+///
+/// Empty entries, such as those left by a trailing comma, are skipped.
+///
+/// # Arguments
+///
+/// * `value` - The field value
+///
+/// # Example
+///
+/// ```
+/// let deps = debpkg::parse_relationships("libc6 (>= 2.17), foo | bar").unwrap();
+/// assert_eq!(deps.len(), 2);
+/// assert_eq!(deps[1].alternatives[1].name, "bar");
+/// ```
+pub fn parse_relationships(value: &str) -> Result<Vec<Dependency>> {
+    value
+        .split(',')
+        .filter(|entry| !entry.trim().is_empty())
+        .map(|entry| entry.parse())
+        .collect()
+}
+
+struct RelationshipParser<'a> {
+    input: &'a str,
+    rest: &'a str,
+}
+
+impl<'a> RelationshipParser<'a> {
+    fn new(input: &'a str) -> Self {
+        RelationshipParser {
+            input: input.trim(),
+            rest: input.trim(),
+        }
+    }
+
+    fn error(&self, msg: &str) -> Error {
+        Error::InvalidRelationship(format!("{msg} in '{}'", self.input))
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.rest = self.rest.trim_start();
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        match self.rest.strip_prefix(c) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn take_while<F: Fn(char) -> bool>(&mut self, pred: F) -> &'a str {
+        let end = self.rest.find(|c| !pred(c)).unwrap_or(self.rest.len());
+        let (taken, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        taken
+    }
+
+    fn parse(mut self) -> Result<Relationship> {
+        let name = self.take_while(is_package_name_char);
+        if name.is_empty() {
+            return Err(self.error("missing package name"));
+        }
+
+        let arch_qualifier = if self.eat(':') {
+            let qualifier = self.take_while(is_arch_char);
+            if qualifier.is_empty() {
+                return Err(self.error("missing architecture qualifier after ':'"));
+            }
+            Some(qualifier.to_owned())
+        } else {
+            None
+        };
+
+        self.skip_whitespace();
+        let version = if self.eat('(') {
+            Some(self.parse_version_constraint()?)
+        } else {
+            None
+        };
+
+        self.skip_whitespace();
+        let architectures = if self.eat('[') {
+            self.parse_terms(']')?
+        } else {
+            Vec::new()
+        };
+
+        self.skip_whitespace();
+        let mut profiles = Vec::new();
+        while self.eat('<') {
+            profiles.push(self.parse_terms('>')?);
+            self.skip_whitespace();
+        }
+
+        if !self.rest.is_empty() {
+            return Err(self.error(&format!("unexpected '{}'", self.rest)));
+        }
+
+        Ok(Relationship {
+            name: name.to_owned(),
+            arch_qualifier,
+            version,
+            architectures,
+            profiles,
+        })
+    }
+
+    fn parse_version_constraint(&mut self) -> Result<VersionConstraint> {
+        self.skip_whitespace();
+        let relation = self.take_while(|c| matches!(c, '<' | '=' | '>'));
+        if relation.is_empty() {
+            return Err(self.error("missing version relation"));
+        }
+        let relation = relation.parse()?;
+
+        self.skip_whitespace();
+        let version = self.take_while(|c| !c.is_whitespace() && c != ')');
+        if version.is_empty() {
+            return Err(self.error("missing version"));
+        }
+
+        self.skip_whitespace();
+        if !self.eat(')') {
+            return Err(self.error("missing closing ')'"));
+        }
+
+        Ok(VersionConstraint {
+            relation,
+            version: version.to_owned(),
+        })
+    }
+
+    fn parse_terms(&mut self, close: char) -> Result<Vec<Restriction>> {
+        let mut terms = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.eat(close) {
+                break;
+            }
+            let negated = self.eat('!');
+            let name = self.take_while(is_arch_char);
+            if name.is_empty() {
+                return Err(self.error(&format!("missing closing '{close}'")));
+            }
+            terms.push(Restriction {
+                negated,
+                name: name.to_owned(),
+            });
+        }
+
+        if terms.is_empty() {
+            return Err(self.error(&format!("empty restriction list before '{close}'")));
+        }
+
+        Ok(terms)
+    }
+}
+
+fn is_package_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.')
+}
+
+fn is_arch_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+
+    #[test]
+    fn simple_name_parses() {
+        let rel: Relationship = "libc6".parse().unwrap();
+        assert_eq!(rel.name, "libc6");
+        assert!(rel.arch_qualifier.is_none());
+        assert!(rel.version.is_none());
+        assert!(rel.architectures.is_empty());
+        assert!(rel.profiles.is_empty());
+    }
+
+    #[test]
+    fn version_constraint_parses() {
+        let rel: Relationship = "libc6 (>= 2.17)".parse().unwrap();
+        let version = rel.version.unwrap();
+        assert_eq!(version.relation, VersionRelation::LaterOrEqual);
+        assert_eq!(version.version, "2.17");
+    }
+
+    #[test]
+    fn version_constraint_without_spaces_parses() {
+        let rel: Relationship = "foo(<<1:2.0-1~bpo1)".parse().unwrap();
+        let version = rel.version.unwrap();
+        assert_eq!(version.relation, VersionRelation::StrictlyEarlier);
+        assert_eq!(version.version, "1:2.0-1~bpo1");
+    }
+
+    #[test]
+    fn obsolete_relation_is_inclusive() {
+        let rel: Relationship = "foo (< 1.0)".parse().unwrap();
+        assert_eq!(
+            rel.version.unwrap().relation,
+            VersionRelation::EarlierOrEqual
+        );
+    }
+
+    #[test]
+    fn version_constraint_is_satisfied() {
+        let rel: Relationship = "lzip (<< 1.8~rc2)".parse().unwrap();
+        let constraint = rel.version.unwrap();
+        assert!(constraint.satisfied_by(&"1.7".parse().unwrap()).unwrap());
+        assert!(!constraint.satisfied_by(&"1.8".parse().unwrap()).unwrap());
+        assert!(!constraint
+            .satisfied_by(&"1.8~rc2".parse().unwrap())
+            .unwrap());
+    }
+
+    #[test]
+    fn full_relationship_parses() {
+        let rel: Relationship = "python3:any (>= 3.9~) [amd64 i386] <!nocheck> <stage1 cross>"
+            .parse()
+            .unwrap();
+        assert_eq!(rel.name, "python3");
+        assert_eq!(rel.arch_qualifier.as_deref(), Some("any"));
+        assert_eq!(rel.architectures.len(), 2);
+        assert_eq!(rel.architectures[1].name, "i386");
+        assert_eq!(rel.profiles.len(), 2);
+        assert!(rel.profiles[0][0].negated);
+        assert_eq!(rel.profiles[0][0].name, "nocheck");
+        assert_eq!(rel.profiles[1].len(), 2);
+    }
+
+    #[test]
+    fn negated_architectures_parse() {
+        let rel: Relationship = "foo [!hurd-i386 !kfreebsd-amd64]".parse().unwrap();
+        assert!(rel.architectures.iter().all(|a| a.negated));
+        assert_eq!(rel.architectures[0].name, "hurd-i386");
+    }
+
+    #[test]
+    fn alternatives_and_entries_parse() {
+        let deps = parse_relationships("a, b | c (= 1), d").unwrap();
+        assert_eq!(deps.len(), 3);
+        assert_eq!(deps[1].alternatives.len(), 2);
+        assert_eq!(deps[1].alternatives[1].name, "c");
+    }
+
+    #[test]
+    fn trailing_comma_is_ignored() {
+        let deps = parse_relationships("a,\n b,\n").unwrap();
+        assert_eq!(deps.len(), 2);
+    }
+
+    #[test]
+    fn empty_value_parses_to_nothing() {
+        assert!(parse_relationships("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn display_round_trips() {
+        let value = "a:any (>= 1.0) [amd64] <!nocheck>, b | c (<< 2)";
+        let deps = parse_relationships(value).unwrap();
+        let printed: Vec<String> = deps.iter().map(|d| d.to_string()).collect();
+        assert_eq!(printed.join(", "), value);
+    }
+
+    #[test]
+    fn unclosed_version_fails() {
+        let err = parse_relationships("foo (>= 1.0").unwrap_err();
+        assert_matches!(err, Error::InvalidRelationship(_));
+    }
+
+    #[test]
+    fn missing_relation_fails() {
+        let err = parse_relationships("foo (1.0)").unwrap_err();
+        assert_matches!(err, Error::InvalidRelationship(_));
+    }
+
+    #[test]
+    fn unknown_relation_fails() {
+        let err = parse_relationships("foo (=> 1.0)").unwrap_err();
+        assert_matches!(err, Error::InvalidRelationship(_));
+    }
+
+    #[test]
+    fn unclosed_architectures_fails() {
+        let err = parse_relationships("foo [amd64").unwrap_err();
+        assert_matches!(err, Error::InvalidRelationship(_));
+    }
+
+    #[test]
+    fn empty_profile_fails() {
+        let err = parse_relationships("foo <>").unwrap_err();
+        assert_matches!(err, Error::InvalidRelationship(_));
+    }
+
+    #[test]
+    fn empty_alternative_fails() {
+        let err = parse_relationships("foo | , bar").unwrap_err();
+        assert_matches!(err, Error::InvalidRelationship(_));
+    }
+
+    #[test]
+    fn garbage_after_relationship_fails() {
+        let err = parse_relationships("foo bar").unwrap_err();
+        assert_matches!(err, Error::InvalidRelationship(_));
+    }
+
+    #[test]
+    fn missing_arch_qualifier_fails() {
+        let err = parse_relationships("foo: (>= 1)").unwrap_err();
+        assert_matches!(err, Error::InvalidRelationship(_));
+    }
+}
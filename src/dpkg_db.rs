@@ -0,0 +1,586 @@
+use std::fmt;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::{parse_conffiles, Conffile, Deb822Reader, Error, Md5Sums, Paragraph, Result};
+
+fn invalid(msg: String) -> Error {
+    Error::InvalidDpkgDatabase(msg)
+}
+
+/// The action selected for a package, the first word of its `Status` field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Want {
+    /// `unknown`
+    Unknown,
+
+    /// `install`
+    Install,
+
+    /// `hold`
+    Hold,
+
+    /// `deinstall`
+    Deinstall,
+
+    /// `purge`
+    Purge,
+}
+
+/// Whether a package needs to be reinstalled, the second word of its
+/// `Status` field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flag {
+    /// `ok`
+    Ok,
+
+    /// `reinstreq`, the package is broken and must be reinstalled
+    ReinstReq,
+}
+
+/// How far a package has been installed, the third word of its `Status`
+/// field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackageState {
+    /// `not-installed`
+    NotInstalled,
+
+    /// `config-files`, only the configuration files are left
+    ConfigFiles,
+
+    /// `half-installed`
+    HalfInstalled,
+
+    /// `unpacked`
+    Unpacked,
+
+    /// `half-configured`
+    HalfConfigured,
+
+    /// `triggers-awaited`
+    TriggersAwaited,
+
+    /// `triggers-pending`
+    TriggersPending,
+
+    /// `installed`
+    Installed,
+}
+
+const WANTS: [(Want, &str); 5] = [
+    (Want::Unknown, "unknown"),
+    (Want::Install, "install"),
+    (Want::Hold, "hold"),
+    (Want::Deinstall, "deinstall"),
+    (Want::Purge, "purge"),
+];
+
+const FLAGS: [(Flag, &str); 2] = [(Flag::Ok, "ok"), (Flag::ReinstReq, "reinstreq")];
+
+const STATES: [(PackageState, &str); 8] = [
+    (PackageState::NotInstalled, "not-installed"),
+    (PackageState::ConfigFiles, "config-files"),
+    (PackageState::HalfInstalled, "half-installed"),
+    (PackageState::Unpacked, "unpacked"),
+    (PackageState::HalfConfigured, "half-configured"),
+    (PackageState::TriggersAwaited, "triggers-awaited"),
+    (PackageState::TriggersPending, "triggers-pending"),
+    (PackageState::Installed, "installed"),
+];
+
+fn lookup<T: Copy + PartialEq>(table: &[(T, &'static str)], word: &str) -> Option<T> {
+    table
+        .iter()
+        .find(|(_, name)| *name == word)
+        .map(|(value, _)| *value)
+}
+
+fn name_of<T: Copy + PartialEq>(table: &[(T, &'static str)], value: T) -> &'static str {
+    table.iter().find(|(v, _)| *v == value).unwrap().1
+}
+
+/// The `Status` field of an installed package, e.g. `install ok installed`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PackageStatus {
+    /// The selected action
+    pub want: Want,
+
+    /// Whether the package needs to be reinstalled
+    pub flag: Flag,
+
+    /// The installation state
+    pub state: PackageState,
+}
+
+impl fmt::Display for PackageStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            name_of(&WANTS, self.want),
+            name_of(&FLAGS, self.flag),
+            name_of(&STATES, self.state)
+        )
+    }
+}
+
+impl FromStr for PackageStatus {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<PackageStatus> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        let status = match words[..] {
+            [want, flag, state] => match (
+                lookup(&WANTS, want),
+                lookup(&FLAGS, flag),
+                lookup(&STATES, state),
+            ) {
+                (Some(want), Some(flag), Some(state)) => Some(PackageStatus { want, flag, state }),
+                _ => None,
+            },
+            _ => None,
+        };
+        status.ok_or_else(|| invalid(format!("invalid Status '{s}'")))
+    }
+}
+
+/// A configuration file listed in the `Conffiles` field of the dpkg status
+/// file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstalledConffile {
+    /// The absolute path of the configuration file
+    pub path: PathBuf,
+
+    /// The MD5 digest of the file as shipped by the package
+    pub md5: String,
+
+    /// Whether the file is no longer shipped by the package
+    pub obsolete: bool,
+
+    /// Whether the file will be removed on the next upgrade
+    pub remove_on_upgrade: bool,
+}
+
+/// An entry of the dpkg `diversions` file, created by `dpkg-divert`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diversion {
+    /// The absolute path packages install to
+    pub from: PathBuf,
+
+    /// The absolute path the file is installed to instead
+    pub to: PathBuf,
+
+    /// The package allowed to install to `from`, or None for a local
+    /// diversion which applies to every package
+    pub package: Option<String>,
+}
+
+/// A package paragraph of the dpkg status file
+#[derive(Debug, Clone)]
+pub struct InstalledPackage {
+    paragraph: Paragraph,
+    status: PackageStatus,
+    conffiles: Vec<InstalledConffile>,
+}
+
+impl InstalledPackage {
+    pub(crate) fn from_paragraph(paragraph: Paragraph) -> Result<InstalledPackage> {
+        let name = paragraph
+            .get("Package")
+            .ok_or_else(|| invalid("status entry has no Package field".to_owned()))?;
+        let status = paragraph
+            .get("Status")
+            .ok_or_else(|| invalid(format!("{name} has no Status field")))?
+            .parse()?;
+
+        let mut conffiles = Vec::new();
+        for line in paragraph
+            .continuation("Conffiles")
+            .unwrap_or_default()
+            .lines()
+        {
+            let mut words = line.split_whitespace();
+            let (path, md5) = match (words.next(), words.next()) {
+                (Some(path), Some(md5)) => (path, md5),
+                (None, _) => continue,
+                _ => return Err(invalid(format!("invalid {name} Conffiles line: {line}"))),
+            };
+            let mut conffile = InstalledConffile {
+                path: PathBuf::from(path),
+                md5: md5.to_owned(),
+                obsolete: false,
+                remove_on_upgrade: false,
+            };
+            for flag in words {
+                match flag {
+                    "obsolete" => conffile.obsolete = true,
+                    "remove-on-upgrade" => conffile.remove_on_upgrade = true,
+                    _ => return Err(invalid(format!("invalid {name} Conffiles line: {line}"))),
+                }
+            }
+            conffiles.push(conffile);
+        }
+
+        Ok(InstalledPackage {
+            paragraph,
+            status,
+            conffiles,
+        })
+    }
+
+    /// Returns the package name
+    pub fn name(&self) -> &str {
+        self.paragraph.get("Package").unwrap()
+    }
+
+    /// Returns the installed version, which is absent for packages that
+    /// are not installed
+    pub fn version(&self) -> Option<&str> {
+        self.paragraph.get("Version")
+    }
+
+    /// Returns the `Architecture` field
+    pub fn architecture(&self) -> Option<&str> {
+        self.paragraph.get("Architecture")
+    }
+
+    /// Returns the parsed `Status` field
+    pub fn status(&self) -> PackageStatus {
+        self.status
+    }
+
+    /// Returns true if the package is fully installed
+    pub fn is_installed(&self) -> bool {
+        self.status.state == PackageState::Installed
+    }
+
+    /// Returns the configuration files listed in the `Conffiles` field
+    pub fn conffiles(&self) -> &[InstalledConffile] {
+        &self.conffiles
+    }
+
+    /// Returns field value based on field name if it exists
+    pub fn get(&self, field_name: &str) -> Option<&str> {
+        self.paragraph.get(field_name)
+    }
+
+    /// Returns all the fields of the status entry
+    pub fn paragraph(&self) -> &Paragraph {
+        &self.paragraph
+    }
+}
+
+/// The dpkg database of an installed system, read from
+/// `<root>/var/lib/dpkg`
+///
+/// The status file is read when the database is opened. The per-package
+/// files under `info/` are read on demand.
+#[derive(Debug, Clone)]
+pub struct DpkgDatabase {
+    admin_dir: PathBuf,
+    packages: Vec<InstalledPackage>,
+}
+
+impl DpkgDatabase {
+    /// Opens the dpkg database of the system rooted at `root`, such as an
+    /// unpacked container image or a chroot. Use `/` for the running system.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The root directory of the system
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let db = debpkg::DpkgDatabase::open("/srv/chroot/bookworm").unwrap();
+    /// let bash = db.package("bash").unwrap();
+    /// println!("{} {}", bash.name(), bash.status());
+    /// for owner in db.owners("/bin/bash").unwrap() {
+    ///     println!("{}", owner.name());
+    /// }
+    /// ```
+    pub fn open<P: AsRef<Path>>(root: P) -> Result<DpkgDatabase> {
+        let admin_dir = root.as_ref().join("var/lib/dpkg");
+        let status = std::fs::File::open(admin_dir.join("status"))?;
+        let packages = Deb822Reader::new(status)
+            .map(|paragraph| InstalledPackage::from_paragraph(paragraph?))
+            .collect::<Result<_>>()?;
+        Ok(DpkgDatabase {
+            admin_dir,
+            packages,
+        })
+    }
+
+    /// Returns every package in the status file, including those which are
+    /// not installed
+    pub fn packages(&self) -> &[InstalledPackage] {
+        &self.packages
+    }
+
+    /// Returns the first package with the given name. The name may be
+    /// qualified with an architecture, e.g. `libc6:amd64`.
+    pub fn package(&self, name: &str) -> Option<&InstalledPackage> {
+        let (name, arch) = match name.split_once(':') {
+            Some((name, arch)) => (name, Some(arch)),
+            None => (name, None),
+        };
+        self.packages.iter().find(|package| {
+            package.name() == name && (arch.is_none() || package.architecture() == arch)
+        })
+    }
+
+    // Returns the path of a file under info/. Packages which can be
+    // installed for several architectures qualify the name with theirs.
+    fn info_path(&self, package: &InstalledPackage, extension: &str) -> PathBuf {
+        let info = self.admin_dir.join("info");
+        if let Some(arch) = package.architecture() {
+            let qualified = info.join(format!("{}:{arch}.{extension}", package.name()));
+            if qualified.exists() {
+                return qualified;
+            }
+        }
+        info.join(format!("{}.{extension}", package.name()))
+    }
+
+    fn open_info(
+        &self,
+        package: &InstalledPackage,
+        extension: &str,
+    ) -> Result<Option<std::fs::File>> {
+        match std::fs::File::open(self.info_path(package, extension)) {
+            Ok(file) => Ok(Some(file)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Returns the paths installed by a package, read from its `.list`
+    /// file. The paths are absolute and include directories. Returns an
+    /// empty list if the package has no `.list` file.
+    pub fn files(&self, package: &InstalledPackage) -> Result<Vec<PathBuf>> {
+        let file = match self.open_info(package, "list")? {
+            Some(file) => file,
+            None => return Ok(Vec::new()),
+        };
+        let mut files = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+            // The root directory is listed as `/.`
+            files.push(PathBuf::from(if line == "/." { "/" } else { &line }));
+        }
+        Ok(files)
+    }
+
+    /// Returns the digests from a package's `.md5sums` file if it has one
+    pub fn md5sums(&self, package: &InstalledPackage) -> Result<Option<Md5Sums>> {
+        match self.open_info(package, "md5sums")? {
+            Some(file) => Ok(Some(Md5Sums::parse(file)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the entries of a package's `.conffiles` file. Returns an empty
+    /// list if it has none.
+    pub fn conffiles(&self, package: &InstalledPackage) -> Result<Vec<Conffile>> {
+        match self.open_info(package, "conffiles")? {
+            Some(file) => parse_conffiles(file),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Returns the entries of the `diversions` file. Returns an empty list if
+    /// there is no such file.
+    pub fn diversions(&self) -> Result<Vec<Diversion>> {
+        let text = match std::fs::read_to_string(self.admin_dir.join("diversions")) {
+            Ok(text) => text,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+        // Each diversion is three lines: from, to and the package, which is
+        // `:` for local diversions
+        let lines: Vec<&str> = text.lines().collect();
+        let diversions = lines.chunks_exact(3);
+        if !diversions.remainder().is_empty() {
+            return Err(invalid("truncated diversions file".to_owned()));
+        }
+        Ok(diversions
+            .map(|diversion| Diversion {
+                from: PathBuf::from(diversion[0]),
+                to: PathBuf::from(diversion[1]),
+                package: match diversion[2] {
+                    ":" => None,
+                    package => Some(package.to_owned()),
+                },
+            })
+            .collect())
+    }
+
+    /// Returns the packages whose `.list` file contains `path`, like
+    /// `dpkg -S`. Directories are often owned by several packages.
+    ///
+    /// Every `.list` file is read on each call.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to look up, relative to the root or absolute
+    pub fn owners<P: AsRef<Path>>(&self, path: P) -> Result<Vec<&InstalledPackage>> {
+        let path = Path::new("/").join(path);
+        let mut owners = Vec::new();
+        for package in &self.packages {
+            if self.files(package)?.contains(&path) {
+                owners.push(package);
+            }
+        }
+        Ok(owners)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+
+    const STATUS: &str = "Package: base-files
+Status: install ok installed
+Priority: required
+Architecture: amd64
+Version: 12.4+deb12u10
+Conffiles:
+ /etc/debian_version 2f6d3b2f4f0e7a7ec4a3bd0de1c58f3c
+ /etc/dpkg/origins/debian c47b6815f67ad1aeccb0d4529bd0b990 obsolete
+
+Package: libc6
+Status: install ok installed
+Architecture: amd64
+Multi-Arch: same
+Version: 2.36-9
+
+Package: removed
+Status: deinstall ok config-files
+Architecture: all
+Version: 1.0
+";
+
+    fn root() -> tempfile::TempDir {
+        let root = tempfile::TempDir::new().unwrap();
+        let info = root.path().join("var/lib/dpkg/info");
+        std::fs::create_dir_all(&info).unwrap();
+        std::fs::write(root.path().join("var/lib/dpkg/status"), STATUS).unwrap();
+        std::fs::write(
+            info.join("base-files.list"),
+            "/.\n/etc\n/etc/debian_version\n/usr\n",
+        )
+        .unwrap();
+        std::fs::write(
+            info.join("base-files.md5sums"),
+            "0123456789abcdef0123456789abcdef  usr/share/doc/base-files/README\n",
+        )
+        .unwrap();
+        std::fs::write(info.join("base-files.conffiles"), "/etc/debian_version\n").unwrap();
+        std::fs::write(
+            info.join("libc6:amd64.list"),
+            "/.\n/usr\n/usr/lib/libc.so.6\n",
+        )
+        .unwrap();
+        root
+    }
+
+    #[test]
+    fn status_parses() {
+        let root = root();
+        let db = DpkgDatabase::open(root.path()).unwrap();
+        assert_eq!(db.packages().len(), 3);
+
+        let base_files = db.package("base-files").unwrap();
+        assert_eq!(base_files.version(), Some("12.4+deb12u10"));
+        assert_eq!(
+            base_files.status(),
+            PackageStatus {
+                want: Want::Install,
+                flag: Flag::Ok,
+                state: PackageState::Installed
+            }
+        );
+        assert_eq!(base_files.conffiles().len(), 2);
+        assert!(base_files.conffiles()[1].obsolete);
+        assert_eq!(
+            base_files.conffiles()[0].path,
+            Path::new("/etc/debian_version")
+        );
+
+        let removed = db.package("removed").unwrap();
+        assert!(!removed.is_installed());
+        assert_eq!(removed.status().to_string(), "deinstall ok config-files");
+        assert!(db.package("libc6:amd64").is_some());
+        assert!(db.package("libc6:i386").is_none());
+    }
+
+    #[test]
+    fn info_files_are_read() {
+        let root = root();
+        let db = DpkgDatabase::open(root.path()).unwrap();
+        let base_files = db.package("base-files").unwrap();
+        assert_eq!(db.files(base_files).unwrap().len(), 4);
+        assert_eq!(db.files(base_files).unwrap()[0], Path::new("/"));
+        assert_eq!(db.md5sums(base_files).unwrap().unwrap().len(), 1);
+        assert_eq!(db.conffiles(base_files).unwrap().len(), 1);
+
+        let libc6 = db.package("libc6").unwrap();
+        assert_eq!(db.files(libc6).unwrap().len(), 3);
+        assert!(db.md5sums(libc6).unwrap().is_none());
+
+        let removed = db.package("removed").unwrap();
+        assert!(db.files(removed).unwrap().is_empty());
+    }
+
+    #[test]
+    fn owners_are_found() {
+        let root = root();
+        let db = DpkgDatabase::open(root.path()).unwrap();
+        let names = |path| -> Vec<String> {
+            db.owners(path)
+                .unwrap()
+                .iter()
+                .map(|package| package.name().to_owned())
+                .collect()
+        };
+        assert_eq!(names("/etc/debian_version"), ["base-files"]);
+        assert_eq!(names("usr/lib/libc.so.6"), ["libc6"]);
+        assert_eq!(names("/usr"), ["base-files", "libc6"]);
+        assert!(names("/usr/bin/missing").is_empty());
+    }
+
+    #[test]
+    fn diversions_parse() {
+        let root = root();
+        let db = DpkgDatabase::open(root.path()).unwrap();
+        assert!(db.diversions().unwrap().is_empty());
+
+        std::fs::write(
+            root.path().join("var/lib/dpkg/diversions"),
+            "/bin/sh\n/bin/sh.distrib\ndash\n/usr/bin/foo\n/usr/bin/foo.real\n:\n",
+        )
+        .unwrap();
+        let diversions = db.diversions().unwrap();
+        assert_eq!(diversions.len(), 2);
+        assert_eq!(diversions[0].to, Path::new("/bin/sh.distrib"));
+        assert_eq!(diversions[0].package.as_deref(), Some("dash"));
+        assert_eq!(diversions[1].package, None);
+    }
+
+    #[test]
+    fn bad_status_fails() {
+        let root = root();
+        std::fs::write(
+            root.path().join("var/lib/dpkg/status"),
+            "Package: a\nStatus: install ok\n",
+        )
+        .unwrap();
+        let err = DpkgDatabase::open(root.path()).unwrap_err();
+        assert_matches!(err, Error::InvalidDpkgDatabase(_));
+    }
+}
@@ -0,0 +1,339 @@
+use std::convert::TryFrom;
+use std::io::Read;
+
+use crate::deb822::read_signed_paragraphs;
+use crate::{parse_relationships, Dependency, Error, Paragraph, Result};
+
+fn invalid(msg: String) -> Error {
+    Error::InvalidDscFile(msg)
+}
+
+/// A file listed in the `Files` or one of the `Checksums-*` fields of a
+/// `.dsc` or `.changes` file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileChecksum {
+    /// Lowercase hex digest of the file
+    pub hash: String,
+
+    /// Size of the file in bytes
+    pub size: u64,
+
+    /// Name of the file, relative to the `.dsc` or `.changes` file
+    pub name: String,
+}
+
+// Splits the continuation lines of a multiline field into their whitespace
+// separated columns, requiring exactly `columns` of them
+pub(crate) fn field_rows<'a>(
+    paragraph: &'a Paragraph,
+    name: &'a str,
+    columns: usize,
+    invalid: fn(String) -> Error,
+) -> impl Iterator<Item = Result<Vec<&'a str>>> + 'a {
+    paragraph
+        .continuation(name)
+        .unwrap_or_default()
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(move |line| {
+            let row: Vec<&str> = line.split_whitespace().collect();
+            if row.len() == columns {
+                Ok(row)
+            } else {
+                Err(invalid(format!("invalid {name} line: {line}")))
+            }
+        })
+}
+
+pub(crate) fn parse_size(size: &str, name: &str, invalid: fn(String) -> Error) -> Result<u64> {
+    size.parse()
+        .map_err(|_| invalid(format!("invalid size in {name}: {size}")))
+}
+
+// Parses a list of `hash size name` lines
+pub(crate) fn file_checksums(
+    paragraph: &Paragraph,
+    name: &str,
+    invalid: fn(String) -> Error,
+) -> Result<Vec<FileChecksum>> {
+    field_rows(paragraph, name, 3, invalid)
+        .map(|row| {
+            let row = row?;
+            Ok(FileChecksum {
+                hash: row[0].to_ascii_lowercase(),
+                size: parse_size(row[1], name, invalid)?,
+                name: row[2].to_owned(),
+            })
+        })
+        .collect()
+}
+
+// Splits the `Binary` field, which is comma separated in `.dsc` files and
+// space separated in `.changes` files
+pub(crate) fn binary_list(paragraph: &Paragraph) -> Vec<String> {
+    paragraph
+        .get("Binary")
+        .map(|value| {
+            value
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|name| !name.is_empty())
+                .map(str::to_owned)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// A parsed Debian source control (`.dsc`) file
+#[derive(Debug, Clone)]
+pub struct Dsc {
+    paragraph: Paragraph,
+    binary: Vec<String>,
+    files: Vec<FileChecksum>,
+    checksums_sha1: Vec<FileChecksum>,
+    checksums_sha256: Vec<FileChecksum>,
+    checksums_sha512: Vec<FileChecksum>,
+}
+
+impl Dsc {
+    /// Parses a `.dsc` file, removing its cleartext signature if present.
+    /// The signature is not verified.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let file = std::fs::File::open("hello_2.10-3.dsc").unwrap();
+    /// let dsc = debpkg::Dsc::parse(file).unwrap();
+    /// println!("{} {}", dsc.source(), dsc.version());
+    /// for file in dsc.checksums_sha256() {
+    ///     println!("{} {}", file.hash, file.name);
+    /// }
+    /// ```
+    pub fn parse<R: Read>(reader: R) -> Result<Dsc> {
+        let mut paragraphs = read_signed_paragraphs(reader)?;
+        if paragraphs.len() > 1 {
+            return Err(invalid(".dsc file has more than one paragraph".to_owned()));
+        }
+        match paragraphs.pop() {
+            Some(paragraph) => Dsc::try_from(paragraph),
+            None => Err(invalid("empty .dsc file".to_owned())),
+        }
+    }
+
+    /// Returns the `Format` field, e.g. `3.0 (quilt)`
+    pub fn format(&self) -> &str {
+        self.paragraph.get("Format").unwrap()
+    }
+
+    /// Returns the source package name
+    pub fn source(&self) -> &str {
+        self.paragraph.get("Source").unwrap()
+    }
+
+    /// Returns the source package version
+    pub fn version(&self) -> &str {
+        self.paragraph.get("Version").unwrap()
+    }
+
+    /// Returns the binary packages listed in the `Binary` field
+    pub fn binary(&self) -> &[String] {
+        &self.binary
+    }
+
+    /// Returns the architectures listed in the `Architecture` field
+    pub fn architecture(&self) -> Vec<&str> {
+        self.paragraph
+            .get("Architecture")
+            .map(|value| value.split_whitespace().collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the `Maintainer` field
+    pub fn maintainer(&self) -> Option<&str> {
+        self.paragraph.get("Maintainer")
+    }
+
+    /// Returns the comma separated entries of the `Uploaders` field
+    pub fn uploaders(&self) -> Vec<&str> {
+        self.paragraph
+            .get("Uploaders")
+            .map(|value| {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|uploader| !uploader.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the `Homepage` field
+    pub fn homepage(&self) -> Option<&str> {
+        self.paragraph.get("Homepage")
+    }
+
+    /// Returns the `Standards-Version` field
+    pub fn standards_version(&self) -> Option<&str> {
+        self.paragraph.get("Standards-Version")
+    }
+
+    fn relationships(&self, field_name: &str) -> Result<Vec<Dependency>> {
+        match self.paragraph.get(field_name) {
+            Some(value) => parse_relationships(value),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Parses the `Build-Depends` field
+    pub fn build_depends(&self) -> Result<Vec<Dependency>> {
+        self.relationships("Build-Depends")
+    }
+
+    /// Parses the `Build-Depends-Indep` field
+    pub fn build_depends_indep(&self) -> Result<Vec<Dependency>> {
+        self.relationships("Build-Depends-Indep")
+    }
+
+    /// Parses the `Build-Depends-Arch` field
+    pub fn build_depends_arch(&self) -> Result<Vec<Dependency>> {
+        self.relationships("Build-Depends-Arch")
+    }
+
+    /// Parses the `Build-Conflicts` field
+    pub fn build_conflicts(&self) -> Result<Vec<Dependency>> {
+        self.relationships("Build-Conflicts")
+    }
+
+    /// Parses the `Build-Conflicts-Indep` field
+    pub fn build_conflicts_indep(&self) -> Result<Vec<Dependency>> {
+        self.relationships("Build-Conflicts-Indep")
+    }
+
+    /// Parses the `Build-Conflicts-Arch` field
+    pub fn build_conflicts_arch(&self) -> Result<Vec<Dependency>> {
+        self.relationships("Build-Conflicts-Arch")
+    }
+
+    /// Returns the files listed in the `Files` field with their MD5 sums
+    pub fn files(&self) -> &[FileChecksum] {
+        &self.files
+    }
+
+    /// Returns the files listed in the `Checksums-Sha1` field
+    pub fn checksums_sha1(&self) -> &[FileChecksum] {
+        &self.checksums_sha1
+    }
+
+    /// Returns the files listed in the `Checksums-Sha256` field
+    pub fn checksums_sha256(&self) -> &[FileChecksum] {
+        &self.checksums_sha256
+    }
+
+    /// Returns the files listed in the `Checksums-Sha512` field
+    pub fn checksums_sha512(&self) -> &[FileChecksum] {
+        &self.checksums_sha512
+    }
+
+    /// Returns field value based on field name if it exists
+    pub fn get(&self, field_name: &str) -> Option<&str> {
+        self.paragraph.get(field_name)
+    }
+
+    /// Returns all the fields of the `.dsc` file
+    pub fn paragraph(&self) -> &Paragraph {
+        &self.paragraph
+    }
+}
+
+impl TryFrom<Paragraph> for Dsc {
+    type Error = Error;
+
+    /// Parses the typed fields of a `.dsc` paragraph. The `Format`, `Source`,
+    /// `Version` and `Files` fields are required.
+    fn try_from(paragraph: Paragraph) -> Result<Dsc> {
+        for name in ["Format", "Source", "Version", "Files"] {
+            if paragraph.get(name).is_none() {
+                return Err(invalid(format!("missing {name} field")));
+            }
+        }
+
+        Ok(Dsc {
+            binary: binary_list(&paragraph),
+            files: file_checksums(&paragraph, "Files", invalid)?,
+            checksums_sha1: file_checksums(&paragraph, "Checksums-Sha1", invalid)?,
+            checksums_sha256: file_checksums(&paragraph, "Checksums-Sha256", invalid)?,
+            checksums_sha512: file_checksums(&paragraph, "Checksums-Sha512", invalid)?,
+            paragraph,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+
+    const DSC: &str = "Format: 3.0 (quilt)
+Source: hello
+Binary: hello,
+ hello-dbg
+Architecture: any
+Version: 2.10-3
+Maintainer: Santiago Vila <sanvila@debian.org>
+Uploaders: A <a@example.org>,
+ B <b@example.org>
+Standards-Version: 4.6.2
+Build-Depends: debhelper-compat (= 13),
+ help2man
+Files:
+ 97b7ac2a7f2ca7e2a1b2d5ad6ab5b1c6 725946 hello_2.10.orig.tar.gz
+ b5c0f9d1e2b9b9d0c2c1f8e8f2d1a1b3 12688 hello_2.10-3.debian.tar.xz
+Checksums-Sha256:
+ 31e066137a962676e89f69d1b65382de95a7ef7d914b8cb956f41ea72e0f516b 725946 hello_2.10.orig.tar.gz
+ A6A1D3A8F4E7C5B2D9C0E1F2A3B4C5D6E7F8A9B0C1D2E3F4A5B6C7D8E9F0A1B2 12688 hello_2.10-3.debian.tar.xz
+";
+
+    #[test]
+    fn dsc_parses() {
+        let dsc = Dsc::parse(DSC.as_bytes()).unwrap();
+        assert_eq!(dsc.format(), "3.0 (quilt)");
+        assert_eq!(dsc.source(), "hello");
+        assert_eq!(dsc.version(), "2.10-3");
+        assert_eq!(dsc.binary(), ["hello", "hello-dbg"]);
+        assert_eq!(dsc.architecture(), ["any"]);
+        assert_eq!(dsc.uploaders(), ["A <a@example.org>", "B <b@example.org>"]);
+        assert_eq!(dsc.build_depends().unwrap().len(), 2);
+        assert_eq!(dsc.files().len(), 2);
+        assert_eq!(dsc.files()[1].size, 12688);
+        assert_eq!(dsc.files()[1].name, "hello_2.10-3.debian.tar.xz");
+        assert_eq!(dsc.checksums_sha1().len(), 0);
+        assert_eq!(dsc.checksums_sha256().len(), 2);
+        assert!(dsc.checksums_sha256()[1].hash.starts_with("a6a1d3"));
+    }
+
+    #[test]
+    fn signed_dsc_parses() {
+        let text = format!(
+            "-----BEGIN PGP SIGNED MESSAGE-----\nHash: SHA512\n\n{DSC}\
+             -----BEGIN PGP SIGNATURE-----\n\nabcd\n-----END PGP SIGNATURE-----\n"
+        );
+        let dsc = Dsc::parse(text.as_bytes()).unwrap();
+        assert_eq!(dsc.source(), "hello");
+    }
+
+    #[test]
+    fn missing_files_fails() {
+        let text = &DSC[..DSC.find("Files:").unwrap()];
+        let err = Dsc::parse(text.as_bytes()).unwrap_err();
+        assert_matches!(err, Error::InvalidDscFile(_));
+    }
+
+    #[test]
+    fn bad_files_line_fails() {
+        let text = DSC.replace(
+            " 725946 hello_2.10.orig.tar.gz\n b5c0",
+            " hello_2.10.orig.tar.gz\n b5c0",
+        );
+        let err = Dsc::parse(text.as_bytes()).unwrap_err();
+        assert_matches!(err, Error::InvalidDscFile(_));
+    }
+}
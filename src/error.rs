@@ -2,6 +2,87 @@ use std::error::Error as StdError;
 use std::fmt;
 use std::io::Error as IoError;
 
+/// What is wrong with a line of a control file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlParseErrorKind {
+    /// A field appears more than once in the same paragraph
+    DuplicateField,
+
+    /// A continuation line appears before the first field
+    ContinuationWithoutField,
+
+    /// A line which is not a continuation has no `:` after the field name
+    MissingColon,
+
+    /// A field name is empty or contains characters which are not allowed,
+    /// or a value contains a carriage return
+    IllegalCharacters,
+
+    /// A continuation line follows a field which must fit on one line,
+    /// such as `Package`
+    ContinuationInSimpleField,
+
+    /// A blank line inside a file which holds a single paragraph. This is
+    /// only reported as a [`Diagnostic`], strict parsing skips such lines.
+    BlankLine,
+}
+
+impl fmt::Display for ControlParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let msg = match *self {
+            ControlParseErrorKind::DuplicateField => "duplicate field",
+            ControlParseErrorKind::ContinuationWithoutField => "continuation line without a field",
+            ControlParseErrorKind::MissingColon => "missing ':' after field name",
+            ControlParseErrorKind::IllegalCharacters => "illegal character",
+            ControlParseErrorKind::ContinuationInSimpleField => {
+                "continuation line in a single line field"
+            }
+            ControlParseErrorKind::BlankLine => "blank line",
+        };
+        f.write_str(msg)
+    }
+}
+
+/// The details of an [`Error::InvalidControlFile`], returned by its
+/// `source()`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControlParseError {
+    /// The line number, starting at 1. Zero if the error comes from setting
+    /// a field rather than from parsing a file.
+    pub line: usize,
+
+    /// The column of the offending character, starting at 1
+    pub column: usize,
+
+    /// The offending line, or the value being set
+    pub text: String,
+
+    /// The field the line belongs to, if known
+    pub field: Option<String>,
+
+    /// What is wrong with the line
+    pub kind: ControlParseErrorKind,
+}
+
+impl fmt::Display for ControlParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if let Some(ref field) = self.field {
+            write!(f, " in field {field}")?;
+        }
+        if self.line != 0 {
+            write!(f, " at line {}, column {}", self.line, self.column)?;
+        }
+        write!(f, ": {:?}", self.text)
+    }
+}
+
+impl StdError for ControlParseError {}
+
+/// A problem found and recovered from when parsing leniently, see
+/// [`ParseOptions`](crate::ParseOptions)
+pub type Diagnostic = ControlParseError;
+
 #[derive(Debug)]
 /// Errors from parsing Debian packages
 pub enum Error {
@@ -14,6 +95,9 @@ pub enum Error {
     /// The conrtol archive does not contain a control file
     MissingControlFile,
 
+    /// The control archive does not contain an md5sums file
+    MissingMd5sums,
+
     /// The control file does not contain a package name
     MissingPackageName,
 
@@ -21,7 +105,16 @@ pub enum Error {
     MissingPackageVersion,
 
     /// The control file is not formatted correctly
-    InvalidControlFile,
+    InvalidControlFile(ControlParseError),
+
+    /// A relationship field such as Depends could not be parsed
+    InvalidRelationship(String),
+
+    /// A package version does not follow Debian Policy
+    InvalidPackageVersion(String),
+
+    /// A control archive member such as md5sums could not be parsed
+    InvalidControlMember(String),
 
     /// The ar archive does not contain a control archive
     MissingControlArchive,
@@ -35,6 +128,28 @@ pub enum Error {
     /// The data archive was already read and thus can not be read again
     DataAlreadyRead,
 
+    /// A signature is missing, malformed or could not be verified
+    InvalidSignature(String),
+
+    /// An APT Release file could not be parsed
+    InvalidReleaseFile(String),
+
+    /// A Debian upload `.changes` file could not be parsed
+    InvalidChangesFile(String),
+
+    /// A Debian source control `.dsc` file could not be parsed
+    InvalidDscFile(String),
+
+    /// A source package `debian/control` file could not be parsed
+    InvalidSourceControl(String),
+
+    /// A `debian/*.substvars` file could not be parsed
+    InvalidSubstvars(String),
+
+    /// The dpkg status file or one of the files under `info/` could not be
+    /// parsed
+    InvalidDpkgDatabase(String),
+
     /// The entry in the deb package was an unknown file format
     UnknownEntryFormat,
 
@@ -42,6 +157,18 @@ pub enum Error {
     /// was not configured in features
     UnconfiguredFileFormat(String),
 
+    /// A path inside the package is absolute, empty or escapes the package
+    /// root, or a repository pool is outside the repository root
+    InvalidPath(std::path::PathBuf),
+
+    /// Installing a package would overwrite a file owned by another package.
+    /// Holds the path and the name of the owning package.
+    FileConflict(std::path::PathBuf, String),
+
+    /// Extracting an archive went over a limit set in
+    /// [`ExtractOptions`](crate::ExtractOptions)
+    LimitExceeded(String),
+
     /// These was an IoError during the parsing
     Io(IoError),
 }
@@ -52,21 +179,43 @@ impl fmt::Display for Error {
             Error::InvalidVersion => write!(f, "Contents of debian_binary is not 2.x"),
             Error::MissingDebianBinary => write!(f, "Missing debian_binary file"),
             Error::MissingControlFile => write!(f, "control archive is missing control file"),
+            Error::MissingMd5sums => write!(f, "control archive is missing md5sums file"),
             Error::MissingPackageName => write!(f, "control file did not contain a package name"),
             Error::MissingPackageVersion => {
                 write!(f, "control file did not contain a package version")
             }
-            Error::InvalidControlFile => write!(f, "control file missed formatted"),
+            Error::InvalidControlFile(ref err) => write!(f, "invalid control file: {err}"),
+            Error::InvalidRelationship(ref msg) => write!(f, "invalid relationship: {msg}"),
+            Error::InvalidPackageVersion(ref msg) => write!(f, "invalid package version: {msg}"),
+            Error::InvalidControlMember(ref msg) => write!(f, "invalid control member: {msg}"),
             Error::MissingControlArchive => write!(f, "control archive is missing"),
             Error::MissingDataArchive => write!(f, "data archive is missing"),
             Error::ControlAlreadyRead => write!(f, "control archive has been past"),
             Error::DataAlreadyRead => write!(f, "data archive has been past"),
+            Error::InvalidSignature(ref msg) => write!(f, "invalid signature: {msg}"),
+            Error::InvalidReleaseFile(ref msg) => write!(f, "invalid Release file: {msg}"),
+            Error::InvalidChangesFile(ref msg) => write!(f, "invalid .changes file: {msg}"),
+            Error::InvalidDscFile(ref msg) => write!(f, "invalid .dsc file: {msg}"),
+            Error::InvalidSourceControl(ref msg) => {
+                write!(f, "invalid debian/control file: {msg}")
+            }
+            Error::InvalidSubstvars(ref msg) => write!(f, "invalid substvars file: {msg}"),
+            Error::InvalidDpkgDatabase(ref msg) => write!(f, "invalid dpkg database: {msg}"),
             Error::UnknownEntryFormat => {
                 write!(f, "entry in debian package has unknown file format")
             }
             Error::UnconfiguredFileFormat(ref format) => {
                 write!(f, "entry in debian package requires feature {format}")
             }
+            Error::InvalidPath(ref path) => {
+                write!(f, "invalid path: {}", path.display())
+            }
+            Error::FileConflict(ref path, ref package) => write!(
+                f,
+                "trying to overwrite '{}', which is also in package {package}",
+                path.display()
+            ),
+            Error::LimitExceeded(ref msg) => write!(f, "archive has {msg}"),
             Error::Io(ref err) => write!(f, "{err}"),
         }
     }
@@ -75,6 +224,7 @@ impl fmt::Display for Error {
 impl StdError for Error {
     fn source(&self) -> Option<&(dyn StdError + 'static)> {
         match *self {
+            Error::InvalidControlFile(ref err) => Some(err),
             Error::Io(ref err) => Some(err),
             _ => None,
         }
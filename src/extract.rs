@@ -0,0 +1,475 @@
+use std::ffi::OsString;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+use crate::builder::normalize;
+use crate::{Error, Result};
+
+// The most symlinks followed while resolving a single path, as on Linux
+const MAX_SYMLINKS: u32 = 40;
+
+/// Limits and policies for unpacking a data archive with
+/// [`DebPkg::extract_to`](crate::DebPkg::extract_to)
+///
+/// By default there are no size or entry limits, device nodes and FIFOs are
+/// skipped and setuid, setgid and sticky bits are kept.
+///
+/// # Example
+///
+/// ```
+/// let mut options = debpkg::ExtractOptions::new();
+/// options
+///     .max_size(512 * 1024 * 1024)
+///     .max_entries(100_000)
+///     .strip_setuid(true);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ExtractOptions {
+    max_size: Option<u64>,
+    max_entries: Option<u64>,
+    skip_devices: bool,
+    strip_setuid: bool,
+}
+
+impl Default for ExtractOptions {
+    fn default() -> ExtractOptions {
+        ExtractOptions {
+            max_size: None,
+            max_entries: None,
+            skip_devices: true,
+            strip_setuid: false,
+        }
+    }
+}
+
+impl ExtractOptions {
+    /// Creates the default options
+    pub fn new() -> ExtractOptions {
+        ExtractOptions::default()
+    }
+
+    /// Sets the most bytes of file contents that may be unpacked in total
+    pub fn max_size(&mut self, max_size: u64) -> &mut Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Sets the most entries, including directories and links, that may be
+    /// unpacked
+    pub fn max_entries(&mut self, max_entries: u64) -> &mut Self {
+        self.max_entries = Some(max_entries);
+        self
+    }
+
+    /// Sets whether character devices, block devices and FIFOs are skipped.
+    /// Defaults to true. The tar crate can not create these, so when they
+    /// are not skipped they are written as empty regular files.
+    pub fn skip_devices(&mut self, skip_devices: bool) -> &mut Self {
+        self.skip_devices = skip_devices;
+        self
+    }
+
+    /// Sets whether the setuid, setgid and sticky bits are removed from the
+    /// unpacked files. Defaults to false.
+    pub fn strip_setuid(&mut self, strip_setuid: bool) -> &mut Self {
+        self.strip_setuid = strip_setuid;
+        self
+    }
+}
+
+// Unpacks the entries of a data archive below a root directory, one entry at
+// a time so callers can decide where each entry goes
+//
+// Entry paths are validated with `normalize`, so they can not be absolute or
+// contain `..`. Existing symlinks in the root are followed as if the root
+// were a chroot, so nothing is ever written outside of it, and symlink
+// entries may not point above the root.
+pub(crate) struct Extractor<'a> {
+    root: PathBuf,
+    options: &'a ExtractOptions,
+    entries: u64,
+    size: u64,
+}
+
+impl<'a> Extractor<'a> {
+    pub(crate) fn new(root: &Path, options: &'a ExtractOptions) -> Result<Extractor<'a>> {
+        std::fs::create_dir_all(root)?;
+        Ok(Extractor {
+            root: root.canonicalize()?,
+            options,
+            entries: 0,
+            size: 0,
+        })
+    }
+
+    // Applies the options which are implemented by the tar crate
+    pub(crate) fn configure<R: Read>(&self, archive: &mut tar::Archive<R>) {
+        archive.set_preserve_permissions(!self.options.strip_setuid);
+        archive.set_preserve_mtime(true);
+        archive.set_unpack_xattrs(false);
+    }
+
+    // Checks an entry against the limits and returns its path relative to
+    // the root, or None if the entry should be skipped
+    pub(crate) fn check<R: Read>(&mut self, entry: &tar::Entry<R>) -> Result<Option<PathBuf>> {
+        self.entries += 1;
+        if let Some(max_entries) = self.options.max_entries {
+            if self.entries > max_entries {
+                return Err(Error::LimitExceeded(format!(
+                    "more than {max_entries} entries"
+                )));
+            }
+        }
+        self.size += entry.size();
+        if let Some(max_size) = self.options.max_size {
+            if self.size > max_size {
+                return Err(Error::LimitExceeded(format!("more than {max_size} bytes")));
+            }
+        }
+
+        let entry_type = entry.header().entry_type();
+        let is_device = entry_type.is_character_special()
+            || entry_type.is_block_special()
+            || entry_type.is_fifo();
+        if is_device && self.options.skip_devices {
+            return Ok(None);
+        }
+
+        let path = entry.path()?;
+        if path.has_root() {
+            return Err(Error::InvalidPath(path.into_owned()));
+        }
+        // The `./` entry for the root directory itself
+        if path
+            .components()
+            .all(|component| component == Component::CurDir)
+        {
+            return Ok(None);
+        }
+        normalize(&path).map(Some)
+    }
+
+    // Resolves a path relative to the root to a path on the host, following
+    // symlinks as if the root were a chroot
+    pub(crate) fn resolve(&self, path: &Path) -> Result<PathBuf> {
+        let mut resolved = self.root.clone();
+        let mut pending: Vec<OsString> = path
+            .components()
+            .rev()
+            .map(|component| component.as_os_str().to_owned())
+            .collect();
+        let mut symlinks = 0;
+
+        while let Some(part) = pending.pop() {
+            match Path::new(&part).components().next() {
+                Some(Component::Normal(name)) => {
+                    let candidate = resolved.join(name);
+                    match std::fs::symlink_metadata(&candidate) {
+                        Ok(metadata) if metadata.file_type().is_symlink() => {
+                            symlinks += 1;
+                            if symlinks > MAX_SYMLINKS {
+                                return Err(Error::InvalidPath(path.to_owned()));
+                            }
+                            let target = std::fs::read_link(&candidate)?;
+                            if target.has_root() {
+                                resolved = self.root.clone();
+                            }
+                            pending.extend(
+                                target
+                                    .components()
+                                    .filter(|component| {
+                                        matches!(
+                                            component,
+                                            Component::Normal(_) | Component::ParentDir
+                                        )
+                                    })
+                                    .rev()
+                                    .map(|component| component.as_os_str().to_owned()),
+                            );
+                        }
+                        _ => resolved = candidate,
+                    }
+                }
+                // `..` at the root stays at the root, as in a chroot
+                Some(Component::ParentDir) if resolved != self.root => {
+                    resolved.pop();
+                }
+                _ => (),
+            }
+        }
+        Ok(resolved)
+    }
+
+    // Resolves a path relative to the root to a path on the host, following
+    // symlinks in its parent directories but not the last component
+    pub(crate) fn host_path(&self, path: &Path) -> Result<PathBuf> {
+        let file_name = match path.file_name() {
+            Some(file_name) => file_name,
+            None => return Err(Error::InvalidPath(path.to_owned())),
+        };
+        let parent = self.resolve(path.parent().unwrap_or_else(|| Path::new("")))?;
+        Ok(parent.join(file_name))
+    }
+
+    // Returns where a non-directory entry is written, creating its parent
+    // directories and removing whatever is already there so nothing is ever
+    // written through an existing symlink
+    fn prepare(&self, path: &Path) -> Result<PathBuf> {
+        let dest = self.host_path(path)?;
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        match std::fs::symlink_metadata(&dest) {
+            Ok(metadata) if metadata.is_dir() => Err(Error::InvalidPath(path.to_owned())),
+            Ok(_) => {
+                std::fs::remove_file(&dest)?;
+                Ok(dest)
+            }
+            Err(_) => Ok(dest),
+        }
+    }
+
+    // Unpacks an entry to `path`, which is relative to the root and need not
+    // be the entry's own path
+    pub(crate) fn unpack<R: Read>(&self, entry: &mut tar::Entry<R>, path: &Path) -> Result<()> {
+        let entry_type = entry.header().entry_type();
+        if entry_type.is_dir() {
+            let dest = self.resolve(path)?;
+            std::fs::create_dir_all(&dest)?;
+            entry.unpack(&dest)?;
+        } else if entry_type.is_hard_link() {
+            let target = match entry.link_name()? {
+                Some(target) => normalize(&target)?,
+                None => return Err(Error::InvalidPath(path.to_owned())),
+            };
+            self.hard_link(&target, path)?;
+        } else {
+            if entry_type.is_symlink() {
+                let target = match entry.link_name()? {
+                    Some(target) => target.into_owned(),
+                    None => return Err(Error::InvalidPath(path.to_owned())),
+                };
+                // Absolute targets are relative to the root once it is in use.
+                // Relative targets are checked from where the link really
+                // ends up, which differs from its path when a parent
+                // directory is itself a symlink.
+                let dest = self.host_path(path)?;
+                let base = dest
+                    .parent()
+                    .and_then(|parent| parent.strip_prefix(&self.root).ok())
+                    .unwrap_or_else(|| Path::new(""));
+                if !target.has_root() && escapes(&base.join(&target)) {
+                    return Err(Error::InvalidPath(path.to_owned()));
+                }
+            }
+            entry.unpack(self.prepare(path)?)?;
+        }
+        Ok(())
+    }
+
+    // Creates `path` as a hard link to `target`, both relative to the root
+    pub(crate) fn hard_link(&self, target: &Path, path: &Path) -> Result<()> {
+        let target = self.host_path(target)?;
+        std::fs::hard_link(target, self.prepare(path)?)?;
+        Ok(())
+    }
+}
+
+// Whether a relative path climbs above its starting directory
+fn escapes(path: &Path) -> bool {
+    let mut depth = 0usize;
+    for component in path.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::ParentDir if depth == 0 => return true,
+            Component::ParentDir => depth -= 1,
+            _ => (),
+        }
+    }
+    false
+}
+
+// Unpacks every entry of `archive` below `dir`, returning the paths of the
+// unpacked entries relative to `dir`
+pub(crate) fn extract_archive<R: Read>(
+    mut archive: tar::Archive<R>,
+    dir: &Path,
+    options: &ExtractOptions,
+) -> Result<Vec<PathBuf>> {
+    let mut extractor = Extractor::new(dir, options)?;
+    extractor.configure(&mut archive);
+    let mut paths = Vec::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if let Some(path) = extractor.check(&entry)? {
+            extractor.unpack(&mut entry, &path)?;
+            paths.push(path);
+        }
+    }
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+
+    fn header(entry_type: tar::EntryType, size: u64, mode: u32) -> tar::Header {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(entry_type);
+        header.set_size(size);
+        header.set_mode(mode);
+        header
+    }
+
+    // Builds a tar archive, writing names directly into the header so that
+    // paths the tar crate would refuse to append can be tested
+    fn archive(entries: &[(&str, tar::EntryType, &str)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, entry_type, contents) in entries {
+            let size = if *entry_type == tar::EntryType::Regular {
+                contents.len() as u64
+            } else {
+                0
+            };
+            let mut header = header(*entry_type, size, 0o4755);
+            header.as_old_mut().name[..path.len()].copy_from_slice(path.as_bytes());
+            if *entry_type == tar::EntryType::Symlink || *entry_type == tar::EntryType::Link {
+                header.set_link_name(contents).unwrap();
+            }
+            header.set_cksum();
+            let data = if size == 0 { "" } else { contents };
+            builder.append(&header, data.as_bytes()).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    fn extract(
+        entries: &[(&str, tar::EntryType, &str)],
+        options: &ExtractOptions,
+    ) -> Result<tempfile::TempDir> {
+        let dir = tempfile::TempDir::new().unwrap();
+        let data = archive(entries);
+        extract_archive(tar::Archive::new(&data[..]), dir.path(), options)?;
+        Ok(dir)
+    }
+
+    #[test]
+    fn regular_entries_extract() {
+        let dir = extract(
+            &[
+                ("./", tar::EntryType::Directory, ""),
+                ("./usr/bin/", tar::EntryType::Directory, ""),
+                ("./usr/bin/hello", tar::EntryType::Regular, "hello"),
+                ("./usr/bin/link", tar::EntryType::Link, "./usr/bin/hello"),
+            ],
+            &ExtractOptions::new(),
+        )
+        .unwrap();
+        let hello = std::fs::read_to_string(dir.path().join("usr/bin/hello")).unwrap();
+        assert_eq!(hello, "hello");
+        let link = std::fs::read_to_string(dir.path().join("usr/bin/link")).unwrap();
+        assert_eq!(link, "hello");
+    }
+
+    #[test]
+    fn traversal_is_rejected() {
+        for path in ["../evil", "./usr/../../evil", "/etc/evil"] {
+            let err = extract(
+                &[(path, tar::EntryType::Regular, "evil")],
+                &ExtractOptions::new(),
+            )
+            .unwrap_err();
+            assert_matches!(err, Error::InvalidPath(_));
+        }
+    }
+
+    #[test]
+    fn escaping_symlink_is_rejected() {
+        let err = extract(
+            &[("./usr/link", tar::EntryType::Symlink, "../../etc")],
+            &ExtractOptions::new(),
+        )
+        .unwrap_err();
+        assert_matches!(err, Error::InvalidPath(_));
+
+        // `a/b/c` looks one level deeper than it is since `a/b` is the root
+        #[cfg(unix)]
+        {
+            let err = extract(
+                &[
+                    ("./a/", tar::EntryType::Directory, ""),
+                    ("./a/b", tar::EntryType::Symlink, "/"),
+                    ("./a/b/c", tar::EntryType::Symlink, "../../etc"),
+                ],
+                &ExtractOptions::new(),
+            )
+            .unwrap_err();
+            assert_matches!(err, Error::InvalidPath(_));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn writes_through_symlinks_stay_in_root() {
+        let dir = extract(
+            &[
+                ("./etc", tar::EntryType::Symlink, "/tmp"),
+                ("./etc/passwd", tar::EntryType::Regular, "root"),
+                ("./lib", tar::EntryType::Symlink, "usr/lib"),
+                ("./lib/libc.so", tar::EntryType::Regular, "libc"),
+            ],
+            &ExtractOptions::new(),
+        )
+        .unwrap();
+        // The absolute symlink is followed within the root
+        let passwd = std::fs::read_to_string(dir.path().join("tmp/passwd")).unwrap();
+        assert_eq!(passwd, "root");
+        let libc = std::fs::read_to_string(dir.path().join("usr/lib/libc.so")).unwrap();
+        assert_eq!(libc, "libc");
+    }
+
+    #[test]
+    fn limits_are_enforced() {
+        let entries = [
+            ("./a", tar::EntryType::Regular, "aaaa"),
+            ("./b", tar::EntryType::Regular, "bbbb"),
+        ];
+        let err = extract(&entries, ExtractOptions::new().max_entries(1)).unwrap_err();
+        assert_matches!(err, Error::LimitExceeded(_));
+        let err = extract(&entries, ExtractOptions::new().max_size(6)).unwrap_err();
+        assert_matches!(err, Error::LimitExceeded(_));
+        assert!(extract(&entries, ExtractOptions::new().max_size(8).max_entries(2)).is_ok());
+    }
+
+    #[test]
+    fn devices_are_skipped() {
+        let dir = extract(
+            &[("./dev/null", tar::EntryType::Char, "")],
+            &ExtractOptions::new(),
+        )
+        .unwrap();
+        assert!(!dir.path().join("dev/null").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn setuid_is_stripped() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let entries = [("./su", tar::EntryType::Regular, "su")];
+        let dir = extract(&entries, &ExtractOptions::new()).unwrap();
+        let mode = std::fs::metadata(dir.path().join("su"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o7777, 0o4755);
+
+        let dir = extract(&entries, ExtractOptions::new().strip_setuid(true)).unwrap();
+        let mode = std::fs::metadata(dir.path().join("su"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o7777, 0o755);
+    }
+}
@@ -0,0 +1,43 @@
+use std::path::Path;
+use std::process::{ChildStdin, Command, Stdio};
+
+use crate::{Error, Result};
+
+// Runs `gpgv` against `keyring`, writing the signed data to its stdin. With a
+// detached `signature` the data is the signed content, otherwise it is a
+// clearsigned message.
+pub(crate) fn gpgv<F>(keyring: &Path, signature: Option<&Path>, write_data: F) -> Result<()>
+where
+    F: FnOnce(ChildStdin) -> Result<()>,
+{
+    // gpgv looks up relative keyring paths in its home directory
+    let keyring = std::fs::canonicalize(keyring)?;
+    let mut command = Command::new("gpgv");
+    command.arg("--keyring").arg(keyring);
+    if let Some(signature) = signature {
+        command.arg(signature);
+    }
+    let mut child = command
+        .arg("-")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(stdin) = child.stdin.take() {
+        // gpgv stops reading when it rejects the signature early, in which
+        // case its exit status is what matters
+        match write_data(stdin) {
+            Err(Error::Io(ref err)) if err.kind() == std::io::ErrorKind::BrokenPipe => (),
+            result => result?,
+        }
+    }
+
+    let output = child.wait_with_output()?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(Error::InvalidSignature(
+            String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        ))
+    }
+}
@@ -0,0 +1,116 @@
+use std::io::Read;
+
+use crate::Result;
+
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+
+/// The size and checksums of a file, as listed in APT indices
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileDigests {
+    /// Size of the file in bytes
+    pub size: u64,
+
+    /// Lowercase hex MD5 digest
+    pub md5: String,
+
+    /// Lowercase hex SHA-1 digest
+    pub sha1: String,
+
+    /// Lowercase hex SHA-256 digest
+    pub sha256: String,
+
+    /// Lowercase hex SHA-512 digest
+    pub sha512: String,
+}
+
+impl FileDigests {
+    /// Computes the digests of everything left in `reader`
+    pub fn compute<R: Read>(reader: R) -> Result<FileDigests> {
+        let mut reader = HashingReader::new(reader);
+        std::io::copy(&mut reader, &mut std::io::sink())?;
+        Ok(reader.finish())
+    }
+}
+
+// Returns the lowercase hex MD5 digest of everything left in `reader`, as
+// listed in md5sums control files
+pub(crate) fn md5_digest<R: Read>(mut reader: R) -> Result<String> {
+    let mut hasher = Md5::new();
+    std::io::copy(&mut reader, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+// Wraps a reader and hashes every byte read through it. This lets a file be
+// parsed and hashed in a single pass without needing Seek.
+pub(crate) struct HashingReader<R: Read> {
+    inner: R,
+    size: u64,
+    md5: Md5,
+    sha1: Sha1,
+    sha256: Sha256,
+    sha512: Sha512,
+}
+
+impl<R: Read> HashingReader<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        HashingReader {
+            inner,
+            size: 0,
+            md5: Md5::new(),
+            sha1: Sha1::new(),
+            sha256: Sha256::new(),
+            sha512: Sha512::new(),
+        }
+    }
+
+    pub(crate) fn finish(self) -> FileDigests {
+        FileDigests {
+            size: self.size,
+            md5: format!("{:x}", self.md5.finalize()),
+            sha1: format!("{:x}", self.sha1.finalize()),
+            sha256: format!("{:x}", self.sha256.finalize()),
+            sha512: format!("{:x}", self.sha512.finalize()),
+        }
+    }
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        let data = &buf[..len];
+        self.size += len as u64;
+        self.md5.update(data);
+        self.sha1.update(data);
+        self.sha256.update(data);
+        self.sha512.update(data);
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_input_digests() {
+        let digests = FileDigests::compute(&b""[..]).unwrap();
+        assert_eq!(digests.size, 0);
+        assert_eq!(digests.md5, "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(digests.sha1, "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        assert_eq!(
+            digests.sha256,
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert!(digests.sha512.starts_with("cf83e1357eefb8bd"));
+    }
+
+    #[test]
+    fn abc_digests() {
+        let digests = FileDigests::compute(&b"abc"[..]).unwrap();
+        assert_eq!(digests.size, 3);
+        assert_eq!(digests.md5, "900150983cd24fb0d6963f7d28e17f72");
+        assert_eq!(digests.sha1, "a9993e364706816aba3e25717850c26c9cd0d89d");
+    }
+}
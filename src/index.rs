@@ -0,0 +1,125 @@
+use std::io::Read;
+
+use crate::hash::HashingReader;
+use crate::{Control, DebPkg, Result};
+
+/// Builds the APT `Packages` index stanza for a debian package
+///
+/// The stanza is the package's control file with the `Filename`, `Size`,
+/// `MD5sum`, `SHA1`, `SHA256` and `SHA512` fields added ahead of the
+/// `Description`. The package is hashed while its control archive is being
+/// parsed so it is only read once and `reader` does not need to implement
+/// `Seek`.
+///
+/// # Arguments
+///
+/// * `reader` - The debian package
+///
+/// * `filename` - The package's path relative to the root of the repository,
+///   e.g. `pool/main/h/hello/hello_1.0_all.deb`
+///
+/// # Example
+///
+/// ```no_run
+/// let file = std::fs::File::open("pool/main/h/hello/hello_1.0_all.deb").unwrap();
+/// let entry = debpkg::packages_entry(file, "pool/main/h/hello/hello_1.0_all.deb").unwrap();
+/// println!("{entry}");
+/// ```
+pub fn packages_entry<R: Read>(reader: R, filename: &str) -> Result<Control> {
+    let mut reader = HashingReader::new(reader);
+
+    let mut control = {
+        let mut pkg = DebPkg::parse(&mut reader)?;
+        let control_tar = pkg.control()?;
+        Control::extract(control_tar)?
+    };
+
+    // Hash whatever the control archive parsing did not need to read
+    std::io::copy(&mut reader, &mut std::io::sink())?;
+    let digests = reader.finish();
+
+    let fields = [
+        ("Filename", filename.to_owned()),
+        ("Size", digests.size.to_string()),
+        ("MD5sum", digests.md5),
+        ("SHA1", digests.sha1),
+        ("SHA256", digests.sha256),
+        ("SHA512", digests.sha512),
+    ];
+
+    // Keep the description last, like dpkg-scanpackages and apt-ftparchive
+    let tags: Vec<String> = control.tags().map(str::to_owned).collect();
+    let previous = match tags
+        .iter()
+        .position(|tag| tag.eq_ignore_ascii_case("Description"))
+    {
+        Some(index) if index > 0 => Some(tags[index - 1].clone()),
+        _ => None,
+    };
+
+    match previous {
+        Some(mut previous) => {
+            for (name, value) in fields.iter() {
+                control.insert_after(&previous, name, value)?;
+                previous = (*name).to_owned();
+            }
+        }
+        None => {
+            for (name, value) in fields.iter() {
+                control.set(name, value)?;
+            }
+        }
+    }
+
+    Ok(control)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::FileDigests;
+    use crate::DebPkgBuilder;
+
+    #[test]
+    fn entry_has_digests_before_description() {
+        let control = Control::parse(
+            &b"Package: hello\nVersion: 1.0\nArchitecture: all\nDescription: hi\n there\n"[..],
+        )
+        .unwrap();
+        let mut builder = DebPkgBuilder::new(control);
+        builder.add_file("usr/share/hello/hello.txt", vec![b'a'; 100_000], 0o644);
+        let deb = builder.build(Vec::new()).unwrap();
+        let digests = FileDigests::compute(&deb[..]).unwrap();
+
+        let entry = packages_entry(&deb[..], "pool/main/h/hello/hello_1.0_all.deb").unwrap();
+        let tags: Vec<&str> = entry.tags().collect();
+        assert_eq!(
+            tags,
+            [
+                "Package",
+                "Version",
+                "Architecture",
+                "Filename",
+                "Size",
+                "MD5sum",
+                "SHA1",
+                "SHA256",
+                "SHA512",
+                "Description"
+            ]
+        );
+        assert_eq!(entry.get("Size").unwrap(), deb.len().to_string());
+        assert_eq!(entry.get("MD5sum").unwrap(), digests.md5);
+        assert_eq!(entry.get("SHA512").unwrap(), digests.sha512);
+        assert_eq!(entry.long_description(), Some("there"));
+    }
+
+    #[test]
+    fn entry_without_description_appends_digests() {
+        let control = Control::parse(&b"Package: hello\nVersion: 1.0"[..]).unwrap();
+        let deb = DebPkgBuilder::new(control).build(Vec::new()).unwrap();
+        let entry = packages_entry(&deb[..], "hello.deb").unwrap();
+        assert_eq!(entry.tags().last(), Some("SHA512"));
+        assert_eq!(entry.get("Filename"), Some("hello.deb"));
+    }
+}
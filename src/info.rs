@@ -0,0 +1,308 @@
+use std::fmt;
+use std::io::Read;
+
+use crate::ar_reader::AR_HEADER_LEN;
+use crate::{decompress, Compression, Control, DebPkg, Error, FileKind, ReadState, Result};
+
+// dpkg-deb only shows this much of a `#!` line
+const INTERPRETER_MAX: usize = 210;
+
+/// A member of the ar archive of a debian package and its size
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArMemberInfo {
+    /// The member name, e.g. `control.tar.xz`
+    pub name: String,
+
+    /// The size of the member in bytes
+    pub size: u64,
+}
+
+/// A member of the control archive, as listed by `dpkg-deb --info`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControlMemberInfo {
+    /// The member name, e.g. `postinst`
+    pub name: String,
+
+    /// The type of the member. Only regular files have their contents
+    /// inspected.
+    pub kind: FileKind,
+
+    /// The size of the member in bytes
+    pub size: u64,
+
+    /// The number of newlines in the member
+    pub lines: usize,
+
+    /// Whether the owner may execute the member
+    pub executable: bool,
+
+    /// The interpreter from a `#!` first line without its arguments, e.g.
+    /// `/bin/sh`
+    pub interpreter: Option<String>,
+}
+
+impl ControlMemberInfo {
+    fn new(name: String, kind: FileKind, mode: u32, contents: &[u8]) -> ControlMemberInfo {
+        let interpreter = contents.strip_prefix(b"#!").map(|rest| {
+            let line = rest.split(|&c| c == b'\n').next().unwrap_or_default();
+            let line = &line[..line.len().min(INTERPRETER_MAX - 2)];
+            // Like dpkg-deb, skip spaces after `#!` and drop any arguments
+            let line = String::from_utf8_lossy(line);
+            let line = line.trim_start_matches([' ', '\t']);
+            line.split(char::is_whitespace)
+                .next()
+                .unwrap_or_default()
+                .to_owned()
+        });
+        ControlMemberInfo {
+            name,
+            kind,
+            size: contents.len() as u64,
+            lines: contents.iter().filter(|&&c| c == b'\n').count(),
+            executable: mode & 0o100 != 0,
+            interpreter,
+        }
+    }
+}
+
+/// A summary of a binary debian package, as shown by `dpkg-deb --info`
+///
+/// The `Display` implementation produces the same layout as
+/// `dpkg-deb --info`.
+#[derive(Debug)]
+pub struct PackageInfo {
+    format_version: (u32, u32),
+    members: Vec<ArMemberInfo>,
+    control_compression: Compression,
+    data_compression: Compression,
+    control_members: Vec<ControlMemberInfo>,
+    control_text: String,
+    control: Control,
+}
+
+impl PackageInfo {
+    // Reads the members of the control archive and detects the compression
+    // of the data archive from its first bytes
+    pub(crate) fn read<C: Read>(
+        format_version: (u32, u32),
+        members: Vec<ArMemberInfo>,
+        control_tar: C,
+        data_header: &[u8],
+    ) -> Result<PackageInfo> {
+        let (control_compression, control_tar) = decompress(control_tar)?;
+        let data_compression = Compression::detect(data_header)?;
+
+        let mut control_members = Vec::new();
+        let mut control_text = None;
+        let mut archive = tar::Archive::new(control_tar);
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let path = entry.path()?.to_string_lossy().into_owned();
+            let name = path.trim_start_matches("./").trim_end_matches('/');
+            // Only the top level is listed, like dpkg-deb
+            if name.is_empty() || name == "." || name.contains('/') {
+                continue;
+            }
+            let name = name.to_owned();
+            let kind = FileKind::from(entry.header().entry_type());
+            let mode = entry.header().mode()?;
+            let mut contents = Vec::new();
+            if kind == FileKind::Regular {
+                entry.read_to_end(&mut contents)?;
+            }
+            if name == "control" {
+                control_text = Some(String::from_utf8_lossy(&contents).into_owned());
+            }
+            control_members.push(ControlMemberInfo::new(name, kind, mode, &contents));
+        }
+        control_members.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let control_text = control_text.ok_or(Error::MissingControlFile)?;
+        let control = Control::parse(control_text.as_bytes())?;
+        Ok(PackageInfo {
+            format_version,
+            members,
+            control_compression,
+            data_compression,
+            control_members,
+            control_text,
+            control,
+        })
+    }
+
+    /// Returns the format version from the `debian-binary` member
+    pub fn format_version(&self) -> (u32, u32) {
+        self.format_version
+    }
+
+    /// Returns the size of the package in bytes, computed from the sizes of
+    /// its ar members
+    pub fn size(&self) -> u64 {
+        let members: u64 = self
+            .members
+            .iter()
+            .map(|member| AR_HEADER_LEN as u64 + member.size + member.size % 2)
+            .sum();
+        crate::ar_reader::AR_MAGIC.len() as u64 + members
+    }
+
+    /// Returns every ar member in archive order, including `debian-binary`
+    pub fn members(&self) -> &[ArMemberInfo] {
+        &self.members
+    }
+
+    /// Returns the size of the control archive in bytes
+    pub fn control_size(&self) -> u64 {
+        self.members
+            .iter()
+            .find(|member| member.name.starts_with("control.tar"))
+            .map(|member| member.size)
+            .unwrap_or_default()
+    }
+
+    /// Returns the compression of the control archive
+    pub fn control_compression(&self) -> Compression {
+        self.control_compression
+    }
+
+    /// Returns the compression of the data archive
+    pub fn data_compression(&self) -> Compression {
+        self.data_compression
+    }
+
+    /// Returns the top level members of the control archive sorted by name
+    pub fn control_members(&self) -> &[ControlMemberInfo] {
+        &self.control_members
+    }
+
+    /// Returns the parsed control file
+    pub fn control(&self) -> &Control {
+        &self.control
+    }
+}
+
+impl fmt::Display for PackageInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (major, minor) = self.format_version;
+        writeln!(f, " new Debian package, version {major}.{minor}.")?;
+        writeln!(
+            f,
+            " size {} bytes: control archive={} bytes.",
+            self.size(),
+            self.control_size()
+        )?;
+        for member in &self.control_members {
+            if member.kind != FileKind::Regular {
+                writeln!(f, "     not a plain file          {}", member.name)?;
+                continue;
+            }
+            let interpreter = match member.interpreter {
+                Some(ref interpreter) => format!("#!{interpreter}"),
+                None => String::new(),
+            };
+            writeln!(
+                f,
+                " {:>7} bytes, {:>5} lines   {}  {:<20} {interpreter}",
+                member.size,
+                member.lines,
+                if member.executable { '*' } else { ' ' },
+                member.name,
+            )?;
+        }
+        for line in self.control_text.split_inclusive('\n') {
+            write!(f, " {line}")?;
+        }
+        if !self.control_text.is_empty() && !self.control_text.ends_with('\n') {
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> DebPkg<R> {
+    /// Summarizes the package like `dpkg-deb --info`
+    ///
+    /// The whole package is read, so this must be called before `control`
+    /// or `data`. The data archive is not decompressed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use debpkg::DebPkg;
+    /// let file = std::fs::File::open("test.deb").unwrap();
+    /// let mut pkg = DebPkg::parse(file).unwrap();
+    /// let info = pkg.info().unwrap();
+    /// println!("{:?}", info.data_compression());
+    /// print!("{}", info);
+    /// ```
+    pub fn info(&mut self) -> Result<PackageInfo> {
+        match self.state {
+            ReadState::Opened => (),
+            ReadState::ControlRead | ReadState::DataRead => return Err(Error::ControlAlreadyRead),
+        }
+        if self.next_package_member()?.is_none() {
+            return Err(Error::MissingControlArchive);
+        }
+        self.state = ReadState::ControlRead;
+        let mut control_tar = Vec::new();
+        self.archive.read_to_end(&mut control_tar)?;
+
+        if self.next_package_member()?.is_none() {
+            return Err(Error::MissingDataArchive);
+        }
+        self.state = ReadState::DataRead;
+        let mut data_header = Vec::new();
+        (&mut self.archive)
+            .take(1024)
+            .read_to_end(&mut data_header)?;
+        self.extra_members()?;
+
+        let members = self
+            .archive
+            .members()
+            .iter()
+            .map(|(name, size)| ArMemberInfo {
+                name: name.clone(),
+                size: *size,
+            })
+            .collect();
+        PackageInfo::read(
+            self.format_version(),
+            members,
+            &control_tar[..],
+            &data_header,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn control_members_are_inspected() {
+        let script = ControlMemberInfo::new(
+            "postinst".to_owned(),
+            FileKind::Regular,
+            0o755,
+            b"#!/bin/sh -e\n\nexit 0\n",
+        );
+        assert_eq!(script.lines, 3);
+        assert_eq!(script.size, 21);
+        assert!(script.executable);
+        assert_eq!(script.interpreter.as_deref(), Some("/bin/sh"));
+
+        let script = ControlMemberInfo::new(
+            "prerm".to_owned(),
+            FileKind::Regular,
+            0o755,
+            b"#! /usr/bin/perl -w\n",
+        );
+        assert_eq!(script.interpreter.as_deref(), Some("/usr/bin/perl"));
+
+        let md5sums = ControlMemberInfo::new("md5sums".to_owned(), FileKind::Regular, 0o644, b"");
+        assert_eq!(md5sums.lines, 0);
+        assert!(!md5sums.executable);
+        assert_eq!(md5sums.interpreter, None);
+    }
+}
@@ -0,0 +1,420 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+
+use crate::builder::{normalize, MAINTAINER_SCRIPTS};
+use crate::extract::Extractor;
+use crate::hash::md5_digest;
+use crate::{
+    ControlArchive, Deb822Reader, DebPkg, DpkgDatabase, Error, ExtractOptions, Flag,
+    InstalledConffile, InstalledPackage, PackageState, PackageStatus, Paragraph, Result, Want,
+};
+
+/// Installs binary debian packages into an alternate root directory without
+/// running dpkg, like a minimal `dpkg --root=<root> --install`
+///
+/// The data archive is unpacked into the root and the package is recorded in
+/// `<root>/var/lib/dpkg` the way dpkg does: its file list in
+/// `info/<package>.list`, its control files such as maintainer scripts in
+/// `info/` and its entry in `status`. Maintainer scripts and triggers are
+/// never run.
+///
+/// Configuration files which already exist in the root are replaced if they
+/// still match the version recorded by the previous install of the package.
+/// Otherwise they are left untouched and the packaged version is written
+/// next to them with a `.dpkg-new` suffix. Configuration files which the new
+/// version no longer ships are kept and recorded as obsolete. Files diverted
+/// with `dpkg-divert` are installed to the diverted path.
+///
+/// The data archive is unpacked with the same checks as
+/// [`DebPkg::extract_to`], so packages can not write outside of the root even
+/// through symlinks already in it.
+///
+/// # Example
+///
+/// ```no_run
+/// use debpkg::{DebPkg, Installer};
+/// let file = std::fs::File::open("hello_1.0_amd64.deb").unwrap();
+/// let mut pkg = DebPkg::parse(file).unwrap();
+/// let installed = Installer::new("/srv/rootfs").install(&mut pkg).unwrap();
+/// println!("{} {}", installed.name(), installed.status());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Installer {
+    root: PathBuf,
+    force_overwrite: bool,
+    state: PackageState,
+    extract_options: ExtractOptions,
+}
+
+impl Installer {
+    /// Creates an installer for the system rooted at `root`. The dpkg
+    /// database is created if it does not exist.
+    pub fn new<P: AsRef<Path>>(root: P) -> Installer {
+        Installer {
+            root: root.as_ref().to_owned(),
+            force_overwrite: false,
+            state: PackageState::Installed,
+            extract_options: ExtractOptions::default(),
+        }
+    }
+
+    /// Sets whether files owned by another installed package may be
+    /// overwritten, like `dpkg --force-overwrite`. Defaults to false, in
+    /// which case installing such a file fails with
+    /// [`Error::FileConflict`] unless the package being installed lists the
+    /// owner in its `Replaces` field.
+    pub fn force_overwrite(&mut self, force_overwrite: bool) -> &mut Self {
+        self.force_overwrite = force_overwrite;
+        self
+    }
+
+    /// Sets the state recorded in the package's `Status` field. Defaults to
+    /// [`PackageState::Installed`]; use [`PackageState::Unpacked`] to leave
+    /// configuring the package, including running its `postinst`, to dpkg.
+    pub fn state(&mut self, state: PackageState) -> &mut Self {
+        self.state = state;
+        self
+    }
+
+    /// Sets the limits and policies used to unpack the data archive.
+    /// Defaults to [`ExtractOptions::default`].
+    pub fn extract_options(&mut self, extract_options: ExtractOptions) -> &mut Self {
+        self.extract_options = extract_options;
+        self
+    }
+
+    /// Installs a package, returning its new entry in the status file
+    ///
+    /// A previously installed version of the package is replaced and any of
+    /// its files which are not in the new version are removed. Every file is
+    /// checked for conflicts with other packages before anything is
+    /// unpacked. If unpacking fails part way through, the files unpacked so
+    /// far are left in place and the database is not updated.
+    pub fn install<R: Read>(&self, pkg: &mut DebPkg<R>) -> Result<InstalledPackage> {
+        let admin_dir = self.root.join("var/lib/dpkg");
+        std::fs::create_dir_all(admin_dir.join("info"))?;
+        if !admin_dir.join("status").exists() {
+            std::fs::write(admin_dir.join("status"), "")?;
+        }
+        let db = DpkgDatabase::open(&self.root)?;
+
+        let control_archive = ControlArchive::extract(pkg.control()?)?;
+        let control = control_archive.control();
+        let name = control.name().to_owned();
+        let arch = control.get("Architecture");
+        // Packages which can be co-installed for several architectures
+        // qualify their info files with the architecture
+        let info_name = match (control.get("Multi-Arch"), arch) {
+            (Some("same"), Some(arch)) => format!("{name}:{arch}"),
+            _ => name.clone(),
+        };
+
+        let previous = db
+            .packages()
+            .iter()
+            .find(|package| package.name() == name && package.architecture() == arch);
+        let previous_files = match previous {
+            Some(previous) => db.files(previous)?,
+            None => Vec::new(),
+        };
+
+        // Files of packages listed in Replaces may be overwritten
+        let replaces: HashSet<String> = control
+            .replaces()?
+            .into_iter()
+            .flat_map(|dependency| dependency.alternatives)
+            .map(|relationship| relationship.name)
+            .collect();
+        let mut owners: HashMap<PathBuf, String> = HashMap::new();
+        for package in db
+            .packages()
+            .iter()
+            .filter(|package| package.name() != name && !replaces.contains(package.name()))
+        {
+            for path in db.files(package)? {
+                owners.insert(path, package.name().to_owned());
+            }
+        }
+
+        let diversions: HashMap<PathBuf, PathBuf> = db
+            .diversions()?
+            .into_iter()
+            .filter(|diversion| diversion.package.as_deref() != Some(name.as_str()))
+            .map(|diversion| (diversion.from, diversion.to))
+            .collect();
+
+        let conffiles: HashSet<PathBuf> = control_archive
+            .conffiles()
+            .transpose()?
+            .unwrap_or_default()
+            .iter()
+            .map(|conffile| conffile.path.clone())
+            .collect();
+
+        // Check every file against the other packages before writing
+        // anything, keeping a copy of the data archive to unpack afterwards
+        let mut buffered = tempfile::tempfile()?;
+        let mut checker = Extractor::new(&self.root, &self.extract_options)?;
+        let mut data = tar::Archive::new(Tee {
+            reader: pkg.data()?.into_inner(),
+            writer: &mut buffered,
+        });
+        for entry in data.entries()? {
+            let entry = entry?;
+            let relative = match checker.check(&entry)? {
+                Some(relative) => relative,
+                None => continue,
+            };
+            if entry.header().entry_type().is_dir() {
+                continue;
+            }
+            let path = Path::new("/").join(&relative);
+            let diverted = diversions.get(&path).unwrap_or(&path);
+            if let Some(owner) = owners.get(diverted) {
+                if !self.force_overwrite {
+                    return Err(Error::FileConflict(path, owner.clone()));
+                }
+            }
+        }
+        buffered.rewind()?;
+
+        let mut files = vec![PathBuf::from("/.")];
+        let mut conffile_digests = Vec::new();
+        let mut extractor = Extractor::new(&self.root, &self.extract_options)?;
+        let mut data = tar::Archive::new(buffered);
+        extractor.configure(&mut data);
+        for entry in data.entries()? {
+            let mut entry = entry?;
+            let relative = match extractor.check(&entry)? {
+                Some(relative) => relative,
+                None => continue,
+            };
+            let path = Path::new("/").join(&relative);
+            let entry_type = entry.header().entry_type();
+
+            if entry_type.is_dir() {
+                extractor.unpack(&mut entry, &relative)?;
+                files.push(path);
+                continue;
+            }
+
+            let diverted = diversions.get(&path).unwrap_or(&path);
+            let mut dest = relative_to_root(diverted);
+            let is_conffile = conffiles.contains(&path);
+            if is_conffile && conffile_modified(&extractor.host_path(&dest)?, &path, previous)? {
+                let mut new = dest.into_os_string();
+                new.push(".dpkg-new");
+                dest = PathBuf::from(new);
+            }
+
+            if entry_type.is_hard_link() {
+                let target = match entry.link_name()? {
+                    Some(target) => Path::new("/").join(normalize(&target)?),
+                    None => return Err(Error::InvalidPath(relative)),
+                };
+                let target = relative_to_root(diversions.get(&target).unwrap_or(&target));
+                extractor.hard_link(&target, &dest)?;
+            } else {
+                extractor.unpack(&mut entry, &dest)?;
+            }
+
+            if is_conffile {
+                let digest = md5_digest(std::fs::File::open(extractor.host_path(&dest)?)?)?;
+                conffile_digests.push((path.clone(), digest));
+            }
+            files.push(path);
+        }
+
+        // Configuration files the new version no longer ships are kept and
+        // stay owned by the package, like dpkg does
+        let obsolete: Vec<&InstalledConffile> = previous
+            .map(InstalledPackage::conffiles)
+            .unwrap_or_default()
+            .iter()
+            .filter(|conffile| {
+                !conffiles.contains(&conffile.path) && !files.contains(&conffile.path)
+            })
+            .collect();
+        files.extend(obsolete.iter().map(|conffile| conffile.path.clone()));
+
+        // Remove what the previous version installed but this one does not
+        let installed: HashSet<&PathBuf> = files.iter().collect();
+        for path in &previous_files {
+            if installed.contains(path) || conffiles.contains(path) {
+                continue;
+            }
+            let relative = relative_to_root(diversions.get(path).unwrap_or(path));
+            if relative.as_os_str().is_empty() {
+                continue;
+            }
+            let host_path = extractor.host_path(&relative)?;
+            match std::fs::symlink_metadata(&host_path) {
+                Ok(metadata) if !metadata.is_dir() => std::fs::remove_file(&host_path)?,
+                _ => (),
+            }
+        }
+
+        self.write_info(&admin_dir, &info_name, &control_archive, &files)?;
+
+        let mut paragraph = control.paragraph().clone();
+        let status = PackageStatus {
+            want: Want::Install,
+            flag: Flag::Ok,
+            state: self.state,
+        };
+        paragraph.insert_after("Package", "Status", &status.to_string())?;
+        if !conffile_digests.is_empty() || !obsolete.is_empty() {
+            let mut value = String::new();
+            for (path, digest) in &conffile_digests {
+                value.push_str(&format!("\n{} {digest}", path.display()));
+            }
+            for conffile in &obsolete {
+                value.push_str(&format!(
+                    "\n{} {} obsolete",
+                    conffile.path.display(),
+                    conffile.md5
+                ));
+            }
+            paragraph.set("Conffiles", &value)?;
+        }
+        write_status(&admin_dir, &paragraph)?;
+
+        InstalledPackage::from_paragraph(paragraph)
+    }
+
+    // Replaces the package's files in info/ with its file list and the
+    // members of its control archive
+    fn write_info(
+        &self,
+        admin_dir: &Path,
+        info_name: &str,
+        control_archive: &ControlArchive,
+        files: &[PathBuf],
+    ) -> Result<()> {
+        let info = admin_dir.join("info");
+        let prefix = format!("{info_name}.");
+        for entry in std::fs::read_dir(&info)? {
+            let entry = entry?;
+            let file_name = entry.file_name();
+            let file_name = file_name.to_string_lossy();
+            if let Some(extension) = file_name.strip_prefix(&prefix) {
+                if !extension.contains('.') {
+                    std::fs::remove_file(entry.path())?;
+                }
+            }
+        }
+
+        let mut list = String::new();
+        for path in files {
+            list.push_str(&path.to_string_lossy());
+            list.push('\n');
+        }
+        std::fs::write(info.join(format!("{info_name}.list")), list)?;
+
+        for member in control_archive.member_names() {
+            if member == "control" || member.contains('/') {
+                continue;
+            }
+            let path = info.join(format!("{info_name}.{member}"));
+            std::fs::write(&path, control_archive.member(member).unwrap())?;
+            if MAINTAINER_SCRIPTS.contains(&member) {
+                set_executable(&path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Returns whether an existing configuration file differs from the version
+// the previous install of the package recorded, in which case the admin's
+// copy is kept
+fn conffile_modified(
+    host_path: &Path,
+    path: &Path,
+    previous: Option<&InstalledPackage>,
+) -> Result<bool> {
+    if !host_path.exists() {
+        return Ok(false);
+    }
+    let recorded = previous.and_then(|previous| {
+        previous
+            .conffiles()
+            .iter()
+            .find(|conffile| conffile.path == path)
+    });
+    match recorded {
+        Some(recorded) => Ok(md5_digest(std::fs::File::open(host_path)?)? != recorded.md5),
+        None => Ok(true),
+    }
+}
+
+// Copies everything read from `reader` to `writer`, so an archive can be
+// checked as it streams by and unpacked from the copy afterwards
+struct Tee<R: Read, W: Write> {
+    reader: R,
+    writer: W,
+}
+
+impl<R: Read, W: Write> Read for Tee<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.reader.read(buf)?;
+        self.writer.write_all(&buf[..read])?;
+        Ok(read)
+    }
+}
+
+// Turns an absolute path inside the root into one relative to it
+fn relative_to_root(path: &Path) -> PathBuf {
+    path.strip_prefix("/").unwrap_or(path).to_owned()
+}
+
+// Replaces or appends the package's entry in the status file. The new file is
+// written next to the old one and renamed over it so the status file is
+// never left half written.
+fn write_status(admin_dir: &Path, paragraph: &Paragraph) -> Result<()> {
+    let status_path = admin_dir.join("status");
+    let same_package = |other: &Paragraph| {
+        other.get("Package") == paragraph.get("Package")
+            && other.get("Architecture") == paragraph.get("Architecture")
+    };
+
+    let mut paragraphs = Vec::new();
+    let mut replaced = false;
+    for other in Deb822Reader::new(std::fs::File::open(&status_path)?) {
+        let other = other?;
+        if same_package(&other) {
+            paragraphs.push(paragraph.clone());
+            replaced = true;
+        } else {
+            paragraphs.push(other);
+        }
+    }
+    if !replaced {
+        paragraphs.push(paragraph.clone());
+    }
+
+    let mut text = String::new();
+    for (i, paragraph) in paragraphs.iter().enumerate() {
+        if i != 0 {
+            text.push('\n');
+        }
+        text.push_str(&paragraph.to_string());
+    }
+    let new_path = admin_dir.join("status-new");
+    std::fs::write(&new_path, text)?;
+    std::fs::rename(new_path, status_path)?;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn set_executable(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_executable(_path: &Path) -> Result<()> {
+    Ok(())
+}
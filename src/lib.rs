@@ -7,8 +7,16 @@
 //! abstracted over a reader. This API provides a streaming interface to avoid
 //! loading the entire debian package into RAM.
 //!
-//! This library only parses binary debian packages. It does not attempt to
-//! write binary debian packages.
+//! When the reader also implements `Seek`, [`SeekableDebPkg`] allows the
+//! control and data archives to be read in any order and more than once.
+//!
+//! With the `async` feature, `AsyncDebPkg` reads packages from a
+//! `tokio::io::AsyncRead`. With the `sbom` feature, packages can be described
+//! as SPDX or CycloneDX JSON SBOMs.
+//!
+//! Binary debian packages can also be written with [`DebPkgBuilder`], and
+//! installed into an alternate root directory without dpkg with
+//! [`Installer`].
 //!
 //! # Supported Debian Package Versions
 //!
@@ -29,18 +37,129 @@
 //! let arch = control.get("Architecture").unwrap();
 //! println!("Package Architecture: {}", arch);
 //!
-//! let mut data = pkg.data().unwrap();
 //! let dir = tempfile::TempDir::new().unwrap();
-//! data.unpack(dir).unwrap();
+//! pkg.extract_to(&dir, &debpkg::ExtractOptions::new()).unwrap();
+//! ```
+//!
+//! Building a debian package
+//!
+//! ```no_run
+//! let control = debpkg::Control::parse(&b"Package: hello\nVersion: 1.0"[..]).unwrap();
+//! let file = std::fs::File::create("hello_1.0_all.deb").unwrap();
+//! debpkg::DebPkgBuilder::new(control)
+//!     .add_file("usr/bin/hello", "#!/bin/sh\necho hello\n", 0o755)
+//!     .build(file)
+//!     .unwrap();
 //! ```
 
 use std::io::Read;
 
 mod error;
-pub use error::Error;
+pub use error::{ControlParseError, ControlParseErrorKind, Diagnostic, Error};
+
+mod builder;
+pub use builder::DebPkgBuilder;
+
+mod compression;
+pub use compression::Compression;
 
 mod control;
-pub use control::Control;
+pub use control::{Control, Paragraph, ParseOptions};
+
+mod control_archive;
+pub use control_archive::{
+    parse_conffiles, parse_shlibs, parse_symbols, parse_triggers, Conffile, ControlArchive,
+    Md5Sums, ShlibsEntry, Symbol, SymbolsLibrary, Trigger, TriggerDirective,
+};
+
+mod deb822;
+pub use deb822::Deb822Reader;
+
+mod dependency;
+pub use dependency::{
+    parse_relationships, Dependency, Relationship, Restriction, VersionConstraint, VersionRelation,
+};
+
+mod version;
+pub use version::Version;
+
+mod hash;
+pub use hash::FileDigests;
+
+mod index;
+pub use index::packages_entry;
+
+mod repository;
+pub use repository::RepositoryBuilder;
+
+mod seekable;
+pub use seekable::SeekableDebPkg;
+
+#[cfg(feature = "async")]
+mod async_pkg;
+#[cfg(feature = "async")]
+pub use async_pkg::{AsyncDebPkg, AsyncTarArchive};
+
+#[cfg(feature = "gpgv")]
+mod gpgv;
+
+mod verify;
+pub use verify::{Mismatch, VerifyReport};
+
+mod clearsign;
+pub use clearsign::ClearSigned;
+
+mod release;
+pub use release::{Release, ReleaseFile};
+
+mod dsc;
+pub use dsc::{Dsc, FileChecksum};
+
+mod changes;
+pub use changes::{Changes, ChangesFile};
+
+mod source_control;
+pub use source_control::{
+    parse_source_relationships, BinaryPackage, SourceControl, SourceRelationship,
+};
+
+mod substvars;
+pub use substvars::Substvars;
+
+mod dpkg_db;
+pub use dpkg_db::{
+    Diversion, DpkgDatabase, Flag, InstalledConffile, InstalledPackage, PackageState,
+    PackageStatus, Want,
+};
+
+mod contents;
+pub use contents::{format_contents, FileEntry, FileKind};
+
+mod copyright;
+pub use copyright::{Copyright, CopyrightFiles, License};
+
+#[cfg(feature = "sbom")]
+mod sbom;
+#[cfg(feature = "sbom")]
+pub use sbom::SbomOptions;
+
+mod lint;
+pub use lint::{lint_contents, lint_control, Lint, Severity};
+
+mod info;
+pub use info::{ArMemberInfo, ControlMemberInfo, PackageInfo};
+
+mod extract;
+pub use extract::ExtractOptions;
+
+mod installer;
+pub use installer::Installer;
+
+mod date;
+
+mod ar_reader;
+pub use ar_reader::ArMember;
+use ar_reader::ArReader;
 
 mod debian_binary;
 use debian_binary::{parse_debian_binary_contents, DebianBinaryVersion};
@@ -65,16 +184,17 @@ pub struct DebPkg<R: Read> {
     format_version: DebianBinaryVersion,
 
     /// The ar archive in which the debian package is contained
-    archive: ar::Archive<R>,
+    archive: ArReader<R>,
+
+    /// The members starting with `_` read so far
+    extra_members: Vec<ArMember>,
 }
 
-fn validate_debian_binary<'a, R: 'a + Read>(
-    entry: &mut ar::Entry<'a, R>,
-) -> Result<DebianBinaryVersion> {
+fn validate_debian_binary<R: Read>(name: &str, contents: &mut R) -> Result<DebianBinaryVersion> {
     let identifier = "debian-binary";
 
-    if entry.header().identifier() == identifier.as_bytes() {
-        parse_debian_binary_contents(entry)
+    if name == identifier {
+        parse_debian_binary_contents(contents)
     } else {
         Err(Error::MissingDebianBinary)
     }
@@ -85,8 +205,8 @@ impl<'a, R: 'a + Read> DebPkg<R> {
     ///
     /// # Arguments
     ///
-    /// * `reader` - A type which implements `std::io::Read` and `std::io::Seek`
-    ///   and is formatted as an ar archive
+    /// * `reader` - A type which implements `std::io::Read` and is formatted
+    ///   as an ar archive
     ///
     /// # Example
     ///
@@ -96,19 +216,18 @@ impl<'a, R: 'a + Read> DebPkg<R> {
     /// let pkg = DebPkg::parse(file).unwrap();
     /// ```
     pub fn parse(reader: R) -> Result<DebPkg<R>> {
-        let mut archive = ar::Archive::new(reader);
-        let mut debian_binary_entry = match archive.next_entry() {
-            Some(Ok(entry)) => entry,
-            Some(Err(err)) => return Err(Error::Io(err)),
+        let mut archive = ArReader::new(reader);
+        let name = match archive.next_member()? {
+            Some(name) => name,
             None => return Err(Error::MissingDebianBinary),
         };
-        let format_version = validate_debian_binary(&mut debian_binary_entry)?;
-        drop(debian_binary_entry);
+        let format_version = validate_debian_binary(&name, &mut archive)?;
 
         Ok(DebPkg {
             state: ReadState::Opened,
             format_version,
             archive,
+            extra_members: Vec::new(),
         })
     }
 
@@ -119,6 +238,9 @@ impl<'a, R: 'a + Read> DebPkg<R> {
 
     /// Returns the control tar
     ///
+    /// Members starting with `_` before the control tar are skipped and can
+    /// be retrieved with `extra_members`.
+    ///
     /// # Arguments
     ///
     /// * `self` - A `DebPkg` created by a call to `DebPkg::parse`
@@ -138,13 +260,12 @@ impl<'a, R: 'a + Read> DebPkg<R> {
     pub fn control(&'a mut self) -> Result<tar::Archive<Box<dyn Read + 'a>>> {
         match self.state {
             ReadState::Opened => {
-                let entry = match self.archive.next_entry() {
-                    Some(entry) => entry?,
-                    None => return Err(Error::MissingControlArchive),
-                };
+                if self.next_package_member()?.is_none() {
+                    return Err(Error::MissingControlArchive);
+                }
 
                 self.state = ReadState::ControlRead;
-                get_tar_from_entry(entry)
+                get_tar_from_entry(&mut self.archive)
             }
             ReadState::ControlRead | ReadState::DataRead => Err(Error::ControlAlreadyRead),
         }
@@ -171,87 +292,163 @@ impl<'a, R: 'a + Read> DebPkg<R> {
     /// ```
     ///
     pub fn data(&'a mut self) -> Result<tar::Archive<Box<dyn Read + 'a>>> {
-        match self.control() {
-            Ok(_) => (),
-            Err(Error::ControlAlreadyRead) => (),
-            Err(e) => return Err(e),
-        };
+        if let ReadState::Opened = self.state {
+            if self.next_package_member()?.is_none() {
+                return Err(Error::MissingControlArchive);
+            }
+            self.state = ReadState::ControlRead;
+        }
 
         match self.state {
             ReadState::Opened => unreachable!(),
             ReadState::ControlRead => {
-                let entry = match self.archive.next_entry() {
-                    Some(entry) => entry?,
-                    None => return Err(Error::MissingDataArchive),
-                };
+                if self.next_package_member()?.is_none() {
+                    return Err(Error::MissingDataArchive);
+                }
 
                 self.state = ReadState::DataRead;
-                get_tar_from_entry(entry)
+                get_tar_from_entry(&mut self.archive)
             }
             ReadState::DataRead => Err(Error::DataAlreadyRead),
         }
     }
+
+    /// Unpacks the data tar into `dir`, returning the paths of the unpacked
+    /// entries relative to `dir`
+    ///
+    /// Unlike unpacking the archive returned by `data`, entries with
+    /// absolute paths or `..` components and symlinks pointing outside of
+    /// `dir` are rejected with [`Error::InvalidPath`], and symlinks already
+    /// in `dir` are never followed out of it. The limits and policies in
+    /// `options` are applied as the archive is read; if one is exceeded the
+    /// entries unpacked so far are left in place.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The directory to unpack into, created if it does not exist
+    /// * `options` - The limits to apply
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use debpkg::{DebPkg, ExtractOptions};
+    /// let file = std::fs::File::open("test.deb").unwrap();
+    /// let mut pkg = DebPkg::parse(file).unwrap();
+    /// let mut options = ExtractOptions::new();
+    /// options.max_size(1 << 30).max_entries(100_000);
+    /// for path in pkg.extract_to("/tmp/unpacked", &options).unwrap() {
+    ///     println!("{}", path.display());
+    /// }
+    /// ```
+    pub fn extract_to<P: AsRef<std::path::Path>>(
+        &'a mut self,
+        dir: P,
+        options: &ExtractOptions,
+    ) -> Result<Vec<std::path::PathBuf>> {
+        extract::extract_archive(self.data()?, dir.as_ref(), options)
+    }
+
+    /// Returns the members whose names start with `_`, such as the
+    /// `_gpgorigin` signature added by debsigs, which
+    /// `SeekableDebPkg::verify_signature` can check
+    ///
+    /// This reads through the rest of the package to find members placed
+    /// after the data tar, so the control and data tars can not be read
+    /// afterwards.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use debpkg::DebPkg;
+    /// let file = std::fs::File::open("test.deb").unwrap();
+    /// let mut pkg = DebPkg::parse(file).unwrap();
+    /// for member in pkg.extra_members().unwrap() {
+    ///     println!("{}: {} bytes", member.name, member.contents.len());
+    /// }
+    /// ```
+    pub fn extra_members(&mut self) -> Result<&[ArMember]> {
+        self.state = ReadState::DataRead;
+        while let Some(name) = self.archive.next_member()? {
+            if name.starts_with('_') {
+                let member = self.archive.read_member(name)?;
+                self.extra_members.push(member);
+            }
+        }
+        Ok(&self.extra_members)
+    }
+
+    // Advances to the next member that is not an extra `_` member, keeping
+    // the contents of any extra members skipped over
+    fn next_package_member(&mut self) -> Result<Option<String>> {
+        while let Some(name) = self.archive.next_member()? {
+            if !name.starts_with('_') {
+                return Ok(Some(name));
+            }
+            let member = self.archive.read_member(name)?;
+            self.extra_members.push(member);
+        }
+        Ok(None)
+    }
 }
 
-fn get_tar_from_entry<'a, R: 'a + Read>(
-    entry: ar::Entry<'a, R>,
-) -> Result<tar::Archive<Box<dyn Read + 'a>>> {
+fn get_tar_from_entry<'a, R: 'a + Read>(entry: R) -> Result<tar::Archive<Box<dyn Read + 'a>>> {
+    let (_, reader) = decompress(entry)?;
+    Ok(tar::Archive::new(reader))
+}
+
+// Detects the compression of a control or data tar and returns a reader of
+// the uncompressed tar
+fn decompress<'a, R: 'a + Read>(entry: R) -> Result<(Compression, Box<dyn Read + 'a>)> {
     let mut reader = entry.take(1024);
     let mut first_1kb = vec![];
     reader.read_to_end(&mut first_1kb)?;
 
-    let is_tar = infer::archive::is_tar(&first_1kb);
-    let is_gz = infer::archive::is_gz(&first_1kb);
-    let is_xz = infer::archive::is_xz(&first_1kb);
-    let is_bz2 = infer::archive::is_bz2(&first_1kb);
-    let is_zst = infer::archive::is_zst(&first_1kb);
+    let compression = Compression::detect(&first_1kb)?;
 
     let entry = std::io::Cursor::new(first_1kb).chain(reader.into_inner());
 
-    if is_tar {
-        let entry: Box<dyn Read> = Box::new(entry);
-        Ok(tar::Archive::new(entry))
-    } else if is_gz {
-        #[cfg(feature = "gzip")]
-        {
-            let gz: Box<dyn Read> = Box::new(flate2::read::GzDecoder::new(entry));
-            Ok(tar::Archive::new(gz))
-        }
-        #[cfg(not(feature = "gzip"))]
-        {
-            Err(Error::UnconfiguredFileFormat("gzip".to_string()))
-        }
-    } else if is_xz {
-        #[cfg(feature = "xz")]
-        {
-            let xz: Box<dyn Read> = Box::new(xz2::read::XzDecoder::new_multi_decoder(entry));
-            Ok(tar::Archive::new(xz))
-        }
-        #[cfg(not(feature = "xz"))]
-        {
-            Err(Error::UnconfiguredFileFormat("xz".to_string()))
-        }
-    } else if is_bz2 {
-        #[cfg(feature = "bzip2")]
-        {
-            let bz2: Box<dyn Read> = Box::new(bzip2::read::BzDecoder::new(entry));
-            Ok(tar::Archive::new(bz2))
+    let reader: Box<dyn Read + 'a> = match compression {
+        Compression::None => Box::new(entry),
+        Compression::Gzip => {
+            #[cfg(feature = "gzip")]
+            {
+                Box::new(flate2::read::GzDecoder::new(entry))
+            }
+            #[cfg(not(feature = "gzip"))]
+            {
+                return Err(Error::UnconfiguredFileFormat("gzip".to_string()));
+            }
         }
-        #[cfg(not(feature = "bzip2"))]
-        {
-            Err(Error::UnconfiguredFileFormat("bzip2".to_string()))
+        Compression::Xz => {
+            #[cfg(feature = "xz")]
+            {
+                Box::new(liblzma::read::XzDecoder::new_multi_decoder(entry))
+            }
+            #[cfg(not(feature = "xz"))]
+            {
+                return Err(Error::UnconfiguredFileFormat("xz".to_string()));
+            }
         }
-    } else if is_zst {
-        #[cfg(feature = "zstd")]
-        {
-            let zstd: Box<dyn Read> = Box::new(zstd::stream::read::Decoder::new(entry)?);
-            Ok(tar::Archive::new(zstd))
+        Compression::Bzip2 => {
+            #[cfg(feature = "bzip2")]
+            {
+                Box::new(bzip2::read::BzDecoder::new(entry))
+            }
+            #[cfg(not(feature = "bzip2"))]
+            {
+                return Err(Error::UnconfiguredFileFormat("bzip2".to_string()));
+            }
         }
-        #[cfg(not(feature = "zstd"))]
-        {
-            Err(Error::UnconfiguredFileFormat("zstd".to_string()))
+        Compression::Zstd => {
+            #[cfg(feature = "zstd")]
+            {
+                Box::new(zstd::stream::read::Decoder::new(entry)?)
+            }
+            #[cfg(not(feature = "zstd"))]
+            {
+                return Err(Error::UnconfiguredFileFormat("zstd".to_string()));
+            }
         }
-    } else {
-        Err(Error::UnknownEntryFormat)
-    }
+    };
+    Ok((compression, reader))
 }
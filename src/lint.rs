@@ -0,0 +1,473 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+use crate::{Control, DebPkg, FileEntry, FileKind, Result};
+
+// The longest synopsis Debian Policy allows
+const MAX_SYNOPSIS_LEN: usize = 80;
+
+const PRIORITIES: &[&str] = &["required", "important", "standard", "optional", "extra"];
+
+// The archive areas a section may be prefixed with, Debian's and Ubuntu's
+const AREAS: &[&str] = &[
+    "main",
+    "contrib",
+    "non-free",
+    "non-free-firmware",
+    "restricted",
+    "universe",
+    "multiverse",
+];
+
+// The sections listed in Debian Policy 2.4
+const SECTIONS: &[&str] = &[
+    "admin",
+    "cli-mono",
+    "comm",
+    "database",
+    "debian-installer",
+    "debug",
+    "devel",
+    "doc",
+    "editors",
+    "education",
+    "electronics",
+    "embedded",
+    "fonts",
+    "games",
+    "gnome",
+    "gnu-r",
+    "gnustep",
+    "graphics",
+    "hamradio",
+    "haskell",
+    "httpd",
+    "interpreters",
+    "introspection",
+    "java",
+    "javascript",
+    "kde",
+    "kernel",
+    "libdevel",
+    "libs",
+    "lisp",
+    "localization",
+    "mail",
+    "math",
+    "metapackages",
+    "misc",
+    "net",
+    "news",
+    "ocaml",
+    "oldlibs",
+    "otherosfs",
+    "perl",
+    "php",
+    "python",
+    "ruby",
+    "rust",
+    "science",
+    "shells",
+    "sound",
+    "tasks",
+    "tex",
+    "text",
+    "utils",
+    "vcs",
+    "video",
+    "web",
+    "x11",
+    "xfce",
+    "zope",
+];
+
+/// How serious a [`Lint`] is, ordered from least to most serious
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Worth knowing but usually harmless
+    Info,
+
+    /// Likely a mistake, though the package works
+    Warning,
+
+    /// A violation of Debian Policy
+    Error,
+}
+
+impl Severity {
+    // The one letter code lintian prints for the severity
+    fn code(self) -> char {
+        match self {
+            Severity::Info => 'I',
+            Severity::Warning => 'W',
+            Severity::Error => 'E',
+        }
+    }
+}
+
+/// A problem found by [`lint_control`] or [`lint_contents`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lint {
+    /// The name of the check, such as `synopsis-too-long`. Names follow
+    /// lintian's where there is an equivalent tag.
+    pub tag: &'static str,
+
+    /// How serious the problem is
+    pub severity: Severity,
+
+    /// What was found, such as the offending value or path
+    pub detail: String,
+}
+
+impl Lint {
+    fn new(tag: &'static str, severity: Severity, detail: impl Into<String>) -> Lint {
+        Lint {
+            tag,
+            severity,
+            detail: detail.into(),
+        }
+    }
+}
+
+impl fmt::Display for Lint {
+    /// Formats the problem like lintian, e.g. `W: synopsis-too-long 93`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.severity.code(), self.tag)?;
+        if !self.detail.is_empty() {
+            write!(f, " {}", self.detail)?;
+        }
+        Ok(())
+    }
+}
+
+fn is_valid_package_name(name: &str) -> bool {
+    name.len() >= 2
+        && name.starts_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "+-.".contains(c))
+}
+
+// Checks for the RFC 822 `Full Name <user@example.org>` form
+fn is_valid_maintainer(maintainer: &str) -> bool {
+    let (name, address) = match maintainer.trim().split_once('<') {
+        Some((name, address)) => (name.trim(), address),
+        None => return false,
+    };
+    let address = match address.strip_suffix('>') {
+        Some(address) => address,
+        None => return false,
+    };
+    let valid_address = match address.split_once('@') {
+        Some((local, domain)) => !local.is_empty() && !domain.is_empty() && !domain.contains('@'),
+        None => false,
+    };
+    !name.is_empty() && valid_address && !address.contains(char::is_whitespace)
+}
+
+/// Checks the fields of a binary package's control file against Debian
+/// Policy
+///
+/// The package name, version, `Priority`, `Section` and `Maintainer` fields
+/// are checked for syntax, and the description's synopsis for its length and
+/// for repeating the package name.
+///
+/// # Example
+///
+/// ```
+/// let text = b"Package: Hello\nVersion: 1.0\nMaintainer: nobody\n";
+/// let control = debpkg::Control::parse(&text[..]).unwrap();
+/// for lint in debpkg::lint_control(&control) {
+///     println!("{}", lint);
+/// }
+/// ```
+pub fn lint_control(control: &Control) -> Vec<Lint> {
+    let mut lints = Vec::new();
+
+    let name = control.name();
+    if !is_valid_package_name(name) {
+        lints.push(Lint::new("bad-package-name", Severity::Error, name));
+    }
+    if control.parsed_version().is_err() {
+        lints.push(Lint::new(
+            "bad-version-number",
+            Severity::Error,
+            control.version(),
+        ));
+    }
+
+    match control.get("Priority") {
+        Some("extra") => lints.push(Lint::new(
+            "priority-extra-is-replaced-by-priority-optional",
+            Severity::Warning,
+            "",
+        )),
+        Some(priority) if !PRIORITIES.contains(&priority) => {
+            lints.push(Lint::new("unknown-priority", Severity::Error, priority));
+        }
+        _ => (),
+    }
+
+    if let Some(section) = control.get("Section") {
+        let (area, name) = match section.split_once('/') {
+            Some((area, name)) => (Some(area), name),
+            None => (None, section),
+        };
+        let known_area = area.is_none_or(|area| AREAS.contains(&area));
+        if !known_area || !SECTIONS.contains(&name) {
+            lints.push(Lint::new("unknown-section", Severity::Warning, section));
+        }
+    }
+
+    match control.get("Maintainer") {
+        Some(maintainer) if !is_valid_maintainer(maintainer) => lints.push(Lint::new(
+            "malformed-maintainer-field",
+            Severity::Error,
+            maintainer,
+        )),
+        Some(_) => (),
+        None => lints.push(Lint::new("no-maintainer-field", Severity::Error, "")),
+    }
+
+    match control.short_description() {
+        Some(synopsis) if !synopsis.is_empty() => {
+            let len = synopsis.chars().count();
+            if len > MAX_SYNOPSIS_LEN {
+                lints.push(Lint::new(
+                    "synopsis-too-long",
+                    Severity::Warning,
+                    len.to_string(),
+                ));
+            }
+            let first_word = synopsis.split_whitespace().next().unwrap_or_default();
+            if synopsis.eq_ignore_ascii_case(name) {
+                lints.push(Lint::new(
+                    "description-is-package-name",
+                    Severity::Error,
+                    "",
+                ));
+            } else if first_word.eq_ignore_ascii_case(name) {
+                lints.push(Lint::new(
+                    "description-starts-with-package-name",
+                    Severity::Error,
+                    "",
+                ));
+            }
+        }
+        _ => lints.push(Lint::new("no-description-synopsis", Severity::Error, "")),
+    }
+
+    lints
+}
+
+// Turns a path from the data archive such as `./usr/bin/` into `usr/bin`
+fn archive_path(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| matches!(component, Component::Normal(_)))
+        .collect()
+}
+
+/// Checks the entries of a package's data archive, as returned by
+/// [`DebPkg::contents`], for layout mistakes
+///
+/// Files must not be installed to `/usr/local`, must not be world writable
+/// and `/usr/share/doc/<package>/copyright` must exist, unless
+/// `/usr/share/doc/<package>` is a symlink to another package's directory.
+pub fn lint_contents(control: &Control, entries: &[FileEntry]) -> Vec<Lint> {
+    let mut lints = Vec::new();
+    let doc_dir = Path::new("usr/share/doc").join(control.name());
+    let mut paths = HashSet::new();
+
+    for entry in entries {
+        let path = archive_path(&entry.path);
+        if path.as_os_str().is_empty() {
+            continue;
+        }
+        let display = format!("/{}", path.display());
+
+        if path.starts_with("usr/local") && path != Path::new("usr/local") {
+            let tag = if entry.kind == FileKind::Directory {
+                "dir-in-usr-local"
+            } else {
+                "file-in-usr-local"
+            };
+            lints.push(Lint::new(tag, Severity::Error, display.clone()));
+        }
+
+        // Symlinks are always 0777 and /tmp style directories are fine
+        let sticky_dir = entry.kind == FileKind::Directory && entry.mode & 0o1000 != 0;
+        if entry.mode & 0o002 != 0 && entry.kind != FileKind::Symlink && !sticky_dir {
+            lints.push(Lint::new(
+                "world-writable",
+                Severity::Error,
+                format!("{display} {:04o}", entry.mode),
+            ));
+        }
+
+        if path == doc_dir && entry.kind == FileKind::Symlink {
+            // Points at the docs of another package from the same source
+            paths.insert(doc_dir.join("copyright"));
+        }
+        paths.insert(path);
+    }
+
+    if !paths.contains(&doc_dir.join("copyright")) {
+        lints.push(Lint::new(
+            "no-copyright-file",
+            Severity::Error,
+            format!("/{}", doc_dir.join("copyright").display()),
+        ));
+    }
+
+    lints
+}
+
+impl<R: Read> DebPkg<R> {
+    /// Runs [`lint_control`] and [`lint_contents`] over the package
+    ///
+    /// The control and data archives are both read, so this must be called
+    /// before `control` or `data`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use debpkg::{DebPkg, Severity};
+    /// let file = std::fs::File::open("test.deb").unwrap();
+    /// let mut pkg = DebPkg::parse(file).unwrap();
+    /// let lints = pkg.lint().unwrap();
+    /// for lint in &lints {
+    ///     println!("{}", lint);
+    /// }
+    /// if lints.iter().any(|lint| lint.severity == Severity::Error) {
+    ///     std::process::exit(1);
+    /// }
+    /// ```
+    pub fn lint(&mut self) -> Result<Vec<Lint>> {
+        let control = Control::extract(self.control()?)?;
+        let entries = self.contents()?;
+        let mut lints = lint_control(&control);
+        lints.extend(lint_contents(&control, &entries));
+        Ok(lints)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(lints: &[Lint]) -> Vec<&'static str> {
+        lints.iter().map(|lint| lint.tag).collect()
+    }
+
+    fn control(text: &str) -> Control {
+        Control::parse(text.as_bytes()).unwrap()
+    }
+
+    const GOOD: &str = "Package: hello
+Version: 2.10-3
+Priority: optional
+Section: devel
+Maintainer: Santiago Vila <sanvila@debian.org>
+Description: example package based on GNU hello
+ The GNU hello program produces a familiar, friendly greeting.
+";
+
+    #[test]
+    fn good_control_has_no_lints() {
+        assert!(lint_control(&control(GOOD)).is_empty());
+        let section = GOOD.replace("devel", "non-free/devel");
+        assert!(lint_control(&control(&section)).is_empty());
+    }
+
+    #[test]
+    fn bad_fields_are_reported() {
+        let text = GOOD
+            .replace("hello\n", "Hello_World\n")
+            .replace("2.10-3", "2.10-")
+            .replace("optional", "extra")
+            .replace("devel", "main/stuff")
+            .replace("Santiago Vila <sanvila@debian.org>", "sanvila@debian.org");
+        assert_eq!(
+            tags(&lint_control(&control(&text))),
+            [
+                "bad-package-name",
+                "bad-version-number",
+                "priority-extra-is-replaced-by-priority-optional",
+                "unknown-section",
+                "malformed-maintainer-field",
+            ]
+        );
+    }
+
+    #[test]
+    fn description_rules() {
+        let long = GOOD.replace("example package based on GNU hello", &"x".repeat(81));
+        let lints = lint_control(&control(&long));
+        assert_eq!(tags(&lints), ["synopsis-too-long"]);
+        assert_eq!(lints[0].to_string(), "W: synopsis-too-long 81");
+
+        let name = GOOD.replace("example package based on GNU hello", "Hello");
+        assert_eq!(
+            tags(&lint_control(&control(&name))),
+            ["description-is-package-name"]
+        );
+        let starts = GOOD.replace("example package", "hello package");
+        assert_eq!(
+            tags(&lint_control(&control(&starts))),
+            ["description-starts-with-package-name"]
+        );
+    }
+
+    fn entry(path: &str, kind: FileKind, mode: u32) -> FileEntry {
+        FileEntry {
+            path: PathBuf::from(path),
+            kind,
+            mode,
+            uid: 0,
+            gid: 0,
+            user: None,
+            group: None,
+            size: 0,
+            mtime: 0,
+            link_target: None,
+            device: None,
+        }
+    }
+
+    #[test]
+    fn layout_problems_are_reported() {
+        let entries = [
+            entry("./", FileKind::Directory, 0o755),
+            entry("./usr/local/bin/", FileKind::Directory, 0o755),
+            entry("./usr/local/bin/hello", FileKind::Regular, 0o755),
+            entry("./var/spool/hello/", FileKind::Directory, 0o1777),
+            entry("./var/lib/hello/db", FileKind::Regular, 0o666),
+            entry("./usr/bin/hi", FileKind::Symlink, 0o777),
+        ];
+        let lints = lint_contents(&control(GOOD), &entries);
+        assert_eq!(
+            tags(&lints),
+            [
+                "dir-in-usr-local",
+                "file-in-usr-local",
+                "world-writable",
+                "no-copyright-file",
+            ]
+        );
+        assert_eq!(lints[2].detail, "/var/lib/hello/db 0666");
+    }
+
+    #[test]
+    fn copyright_may_come_from_a_doc_symlink() {
+        let copyright = [entry(
+            "./usr/share/doc/hello/copyright",
+            FileKind::Regular,
+            0o644,
+        )];
+        assert!(lint_contents(&control(GOOD), &copyright).is_empty());
+        let symlink = [entry("./usr/share/doc/hello", FileKind::Symlink, 0o777)];
+        assert!(lint_contents(&control(GOOD), &symlink).is_empty());
+    }
+}
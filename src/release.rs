@@ -0,0 +1,297 @@
+use std::convert::TryFrom;
+use std::io::Read;
+use std::time::SystemTime;
+
+use crate::date::{parse_rfc2822, system_time};
+use crate::deb822::read_signed_paragraphs;
+use crate::{Error, Paragraph, Result};
+
+fn invalid(msg: String) -> Error {
+    Error::InvalidReleaseFile(msg)
+}
+
+/// An index listed in one of the checksum fields of a Release file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReleaseFile {
+    /// Lowercase hex digest of the index
+    pub hash: String,
+
+    /// Size of the index in bytes
+    pub size: u64,
+
+    /// Path of the index relative to the Release file, e.g.
+    /// `main/binary-amd64/Packages.xz`
+    pub path: String,
+}
+
+/// A parsed APT `Release` or `InRelease` file
+#[derive(Debug, Clone)]
+pub struct Release {
+    paragraph: Paragraph,
+    architectures: Vec<String>,
+    components: Vec<String>,
+    date: Option<SystemTime>,
+    valid_until: Option<SystemTime>,
+    md5sum: Vec<ReleaseFile>,
+    sha1: Vec<ReleaseFile>,
+    sha256: Vec<ReleaseFile>,
+    sha512: Vec<ReleaseFile>,
+}
+
+impl Release {
+    /// Parses a `Release` file, or an `InRelease` file after removing its
+    /// cleartext signature. The signature is not verified.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let file = std::fs::File::open("InRelease").unwrap();
+    /// let release = debpkg::Release::parse(file).unwrap();
+    /// println!("{:?}", release.suite());
+    /// for file in release.sha256() {
+    ///     println!("{} {}", file.hash, file.path);
+    /// }
+    /// ```
+    pub fn parse<R: Read>(reader: R) -> Result<Release> {
+        let mut paragraphs = read_signed_paragraphs(reader)?;
+        if paragraphs.len() > 1 {
+            return Err(invalid(
+                "Release file has more than one paragraph".to_owned(),
+            ));
+        }
+        match paragraphs.pop() {
+            Some(paragraph) => Release::try_from(paragraph),
+            None => Err(invalid("empty Release file".to_owned())),
+        }
+    }
+
+    /// Returns the `Origin` field
+    pub fn origin(&self) -> Option<&str> {
+        self.paragraph.get("Origin")
+    }
+
+    /// Returns the `Label` field
+    pub fn label(&self) -> Option<&str> {
+        self.paragraph.get("Label")
+    }
+
+    /// Returns the `Suite` field, e.g. `stable`
+    pub fn suite(&self) -> Option<&str> {
+        self.paragraph.get("Suite")
+    }
+
+    /// Returns the `Codename` field, e.g. `bookworm`
+    pub fn codename(&self) -> Option<&str> {
+        self.paragraph.get("Codename")
+    }
+
+    /// Returns the `Version` field
+    pub fn version(&self) -> Option<&str> {
+        self.paragraph.get("Version")
+    }
+
+    /// Returns the `Date` field
+    pub fn date(&self) -> Option<SystemTime> {
+        self.date
+    }
+
+    /// Returns the `Valid-Until` field, after which clients should consider
+    /// the Release file stale
+    pub fn valid_until(&self) -> Option<SystemTime> {
+        self.valid_until
+    }
+
+    /// Returns the architectures listed in the `Architectures` field
+    pub fn architectures(&self) -> &[String] {
+        &self.architectures
+    }
+
+    /// Returns the components listed in the `Components` field
+    pub fn components(&self) -> &[String] {
+        &self.components
+    }
+
+    /// Returns the `Description` field
+    pub fn description(&self) -> Option<&str> {
+        self.paragraph.get("Description")
+    }
+
+    /// Returns true if the `Acquire-By-Hash` field is `yes`
+    pub fn acquire_by_hash(&self) -> bool {
+        self.paragraph.get("Acquire-By-Hash") == Some("yes")
+    }
+
+    /// Returns the indices listed in the `MD5Sum` field
+    pub fn md5sum(&self) -> &[ReleaseFile] {
+        &self.md5sum
+    }
+
+    /// Returns the indices listed in the `SHA1` field
+    pub fn sha1(&self) -> &[ReleaseFile] {
+        &self.sha1
+    }
+
+    /// Returns the indices listed in the `SHA256` field
+    pub fn sha256(&self) -> &[ReleaseFile] {
+        &self.sha256
+    }
+
+    /// Returns the indices listed in the `SHA512` field
+    pub fn sha512(&self) -> &[ReleaseFile] {
+        &self.sha512
+    }
+
+    /// Returns field value based on field name if it exists
+    pub fn get(&self, field_name: &str) -> Option<&str> {
+        self.paragraph.get(field_name)
+    }
+
+    /// Returns all the fields of the Release file
+    pub fn paragraph(&self) -> &Paragraph {
+        &self.paragraph
+    }
+}
+
+impl TryFrom<Paragraph> for Release {
+    type Error = Error;
+
+    /// Parses the typed fields of a Release paragraph
+    fn try_from(paragraph: Paragraph) -> Result<Release> {
+        let words = |name: &str| -> Vec<String> {
+            paragraph
+                .get(name)
+                .map(|value| value.split_whitespace().map(str::to_owned).collect())
+                .unwrap_or_default()
+        };
+        let date = |name: &str| -> Result<Option<SystemTime>> {
+            let value = match paragraph.get(name) {
+                Some(value) => value,
+                None => return Ok(None),
+            };
+            let date = parse_rfc2822(value)
+                .and_then(system_time)
+                .ok_or_else(|| invalid(format!("invalid {name} date '{value}'")))?;
+            Ok(Some(date))
+        };
+        let files = |name: &str| -> Result<Vec<ReleaseFile>> {
+            let lines = match paragraph.continuation(name) {
+                Some(lines) => lines,
+                None => return Ok(Vec::new()),
+            };
+            lines
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| {
+                    let mut parts = line.split_whitespace();
+                    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+                        (Some(hash), Some(size), Some(path), None) => Ok(ReleaseFile {
+                            hash: hash.to_ascii_lowercase(),
+                            size: size
+                                .parse()
+                                .map_err(|_| invalid(format!("invalid size in {name}: {line}")))?,
+                            path: path.to_owned(),
+                        }),
+                        _ => Err(invalid(format!("invalid {name} line: {line}"))),
+                    }
+                })
+                .collect()
+        };
+
+        Ok(Release {
+            architectures: words("Architectures"),
+            components: words("Components"),
+            date: date("Date")?,
+            valid_until: date("Valid-Until")?,
+            md5sum: files("MD5Sum")?,
+            sha1: files("SHA1")?,
+            sha256: files("SHA256")?,
+            sha512: files("SHA512")?,
+            paragraph,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    const RELEASE: &str = "Origin: Debian
+Label: Debian
+Suite: stable
+Codename: bookworm
+Date: Sat, 26 Apr 2025 09:33:20 UTC
+Valid-Until: Sat, 03 May 2025 09:33:20 UTC
+Acquire-By-Hash: yes
+Architectures: amd64 arm64
+Components: main contrib
+Description: Debian 12.10 Released 15 March 2025
+MD5Sum:
+ 0ed6d4c8891eb86358b94bb35d9e4da4  1484322 contrib/Contents-all
+ d41d8cd98f00b204e9800998ecf8427e        0 main/binary-all/Packages
+SHA256:
+ 3957f28db16e3f28c7b34ae84f1c929c567de6970f3f1b95dac9b498dd80fe63   738242 contrib/Contents-all
+";
+
+    #[test]
+    fn release_parses() {
+        let release = Release::parse(RELEASE.as_bytes()).unwrap();
+        assert_eq!(release.origin(), Some("Debian"));
+        assert_eq!(release.suite(), Some("stable"));
+        assert_eq!(release.codename(), Some("bookworm"));
+        assert_eq!(release.architectures(), ["amd64", "arm64"]);
+        assert_eq!(release.components(), ["main", "contrib"]);
+        assert!(release.acquire_by_hash());
+        assert_eq!(
+            release.date(),
+            Some(UNIX_EPOCH + Duration::from_secs(1_745_660_000))
+        );
+        assert_eq!(
+            release.valid_until(),
+            Some(UNIX_EPOCH + Duration::from_secs(1_745_660_000 + 7 * 86_400))
+        );
+        assert_eq!(release.md5sum().len(), 2);
+        assert_eq!(release.md5sum()[1].size, 0);
+        assert_eq!(release.md5sum()[1].path, "main/binary-all/Packages");
+        assert_eq!(release.sha1().len(), 0);
+        assert_eq!(release.sha256().len(), 1);
+        assert_eq!(release.sha256()[0].size, 738242);
+    }
+
+    #[test]
+    fn inrelease_parses() {
+        let text = format!(
+            "-----BEGIN PGP SIGNED MESSAGE-----\nHash: SHA512\n\n{RELEASE}\
+             -----BEGIN PGP SIGNATURE-----\n\nabcd\n-----END PGP SIGNATURE-----\n"
+        );
+        let release = Release::parse(text.as_bytes()).unwrap();
+        assert_eq!(release.codename(), Some("bookworm"));
+        assert_eq!(release.sha256().len(), 1);
+    }
+
+    #[test]
+    fn bad_checksum_line_fails() {
+        let text = RELEASE.replace("  1484322 ", " ");
+        let err = Release::parse(text.as_bytes()).unwrap_err();
+        assert_matches!(err, Error::InvalidReleaseFile(_));
+    }
+
+    #[test]
+    fn bad_date_fails() {
+        let text = RELEASE.replace("Sat, 03 May", "Sat, 03 Mai");
+        let err = Release::parse(text.as_bytes()).unwrap_err();
+        assert_matches!(err, Error::InvalidReleaseFile(_));
+
+        let text = RELEASE.replace("May 2025", "May 9999999999999");
+        let err = Release::parse(text.as_bytes()).unwrap_err();
+        assert_matches!(err, Error::InvalidReleaseFile(_));
+    }
+
+    #[test]
+    fn multiple_paragraphs_fail() {
+        let text = format!("{RELEASE}\nSuite: other\n");
+        let err = Release::parse(text.as_bytes()).unwrap_err();
+        assert_matches!(err, Error::InvalidReleaseFile(_));
+    }
+}
@@ -0,0 +1,339 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::date::format_rfc2822;
+use crate::{packages_entry, Compression, Control, Error, FileDigests, Paragraph, Result};
+
+/// Generates an APT repository from a pool of debian packages
+///
+/// Every `.deb` below the pool directory is added to
+/// `dists/<suite>/<component>/binary-<arch>/Packages`, which is also written
+/// gzip and xz compressed when those features are enabled. Packages with
+/// `Architecture: all` are added to the index of every architecture. A
+/// `dists/<suite>/Release` file listing the checksums of every index is
+/// written last. Signing the Release file is left to the caller.
+///
+/// # Example
+///
+/// ```no_run
+/// debpkg::RepositoryBuilder::new("/srv/apt", "stable")
+///     .component("main")
+///     .origin("Example")
+///     .by_hash(true)
+///     .generate()
+///     .unwrap();
+/// ```
+#[derive(Debug)]
+pub struct RepositoryBuilder {
+    root: PathBuf,
+    pool: PathBuf,
+    suite: String,
+    codename: Option<String>,
+    component: String,
+    origin: Option<String>,
+    label: Option<String>,
+    description: Option<String>,
+    architectures: Vec<String>,
+    by_hash: bool,
+    date: Option<SystemTime>,
+}
+
+impl RepositoryBuilder {
+    /// Creates a generator for `suite` of the repository rooted at `root`
+    ///
+    /// By default packages are read from `<root>/pool` into the `main`
+    /// component.
+    pub fn new<P: AsRef<Path>>(root: P, suite: &str) -> RepositoryBuilder {
+        RepositoryBuilder {
+            root: root.as_ref().to_owned(),
+            pool: PathBuf::from("pool"),
+            suite: suite.to_owned(),
+            codename: None,
+            component: "main".to_owned(),
+            origin: None,
+            label: None,
+            description: None,
+            architectures: Vec::new(),
+            by_hash: false,
+            date: None,
+        }
+    }
+
+    /// Sets the pool directory, relative to the repository root, to scan for
+    /// packages. [`generate`](Self::generate) fails with
+    /// [`Error::InvalidPath`](crate::Error::InvalidPath) if the pool is
+    /// absolute or outside the root.
+    pub fn pool<P: AsRef<Path>>(&mut self, pool: P) -> &mut Self {
+        self.pool = pool.as_ref().to_owned();
+        self
+    }
+
+    /// Sets the component the packages are published in
+    pub fn component(&mut self, component: &str) -> &mut Self {
+        self.component = component.to_owned();
+        self
+    }
+
+    /// Sets the `Codename` field of the Release file
+    pub fn codename(&mut self, codename: &str) -> &mut Self {
+        self.codename = Some(codename.to_owned());
+        self
+    }
+
+    /// Sets the `Origin` field of the Release file
+    pub fn origin(&mut self, origin: &str) -> &mut Self {
+        self.origin = Some(origin.to_owned());
+        self
+    }
+
+    /// Sets the `Label` field of the Release file
+    pub fn label(&mut self, label: &str) -> &mut Self {
+        self.label = Some(label.to_owned());
+        self
+    }
+
+    /// Sets the `Description` field of the Release file
+    pub fn description(&mut self, description: &str) -> &mut Self {
+        self.description = Some(description.to_owned());
+        self
+    }
+
+    /// Sets the architectures to write indices for. By default these are
+    /// the architectures of the packages found in the pool.
+    pub fn architectures<S: AsRef<str>>(&mut self, architectures: &[S]) -> &mut Self {
+        self.architectures = architectures
+            .iter()
+            .map(|arch| arch.as_ref().to_owned())
+            .collect();
+        self
+    }
+
+    /// Sets whether to also write every index under `by-hash/<hash>/<digest>`
+    /// and announce it with `Acquire-By-Hash: yes`
+    pub fn by_hash(&mut self, by_hash: bool) -> &mut Self {
+        self.by_hash = by_hash;
+        self
+    }
+
+    /// Sets the `Date` field of the Release file. Defaults to now.
+    pub fn date(&mut self, date: SystemTime) -> &mut Self {
+        self.date = Some(date);
+        self
+    }
+
+    /// Scans the pool and writes the indices and Release file
+    pub fn generate(&self) -> Result<()> {
+        if !self
+            .pool
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(Error::InvalidPath(self.pool.clone()));
+        }
+
+        let mut debs = Vec::new();
+        find_debs(&self.root.join(&self.pool), &mut debs)?;
+        debs.sort();
+
+        let mut entries = Vec::new();
+        for deb in debs {
+            let filename = deb
+                .strip_prefix(&self.root)
+                .map_err(|_| Error::InvalidPath(deb.clone()))?
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            entries.push(packages_entry(fs::File::open(&deb)?, &filename)?);
+        }
+        entries.sort_by(|a, b| {
+            a.name()
+                .cmp(b.name())
+                .then_with(|| match (a.parsed_version(), b.parsed_version()) {
+                    (Ok(a), Ok(b)) => a.cmp(&b),
+                    _ => a.version().cmp(b.version()),
+                })
+        });
+
+        let architectures = self.index_architectures(&entries);
+        let dist = self.root.join("dists").join(&self.suite);
+
+        let mut indices = BTreeMap::new();
+        for arch in &architectures {
+            let dir = PathBuf::from(&self.component).join(format!("binary-{arch}"));
+
+            let mut packages = String::new();
+            for entry in entries.iter().filter(|e| {
+                let entry_arch = e.get("Architecture").unwrap_or("all");
+                entry_arch == arch || entry_arch == "all"
+            }) {
+                if !packages.is_empty() {
+                    packages.push('\n');
+                }
+                packages.push_str(&entry.to_string());
+            }
+            self.write_index(
+                &dist,
+                &dir.join("Packages"),
+                packages.as_bytes(),
+                &mut indices,
+            )?;
+
+            let mut release = Paragraph::new();
+            release.set("Archive", &self.suite)?;
+            release.set("Component", &self.component)?;
+            if let Some(ref origin) = self.origin {
+                release.set("Origin", origin)?;
+            }
+            if let Some(ref label) = self.label {
+                release.set("Label", label)?;
+            }
+            release.set("Architecture", arch)?;
+            let release = release.to_string();
+            self.write_index(
+                &dist,
+                &dir.join("Release"),
+                release.as_bytes(),
+                &mut indices,
+            )?;
+        }
+
+        let release = self.release(&architectures, &indices)?;
+        fs::write(dist.join("Release"), release.to_string())?;
+        Ok(())
+    }
+
+    fn index_architectures(&self, entries: &[Control]) -> Vec<String> {
+        if !self.architectures.is_empty() {
+            return self.architectures.clone();
+        }
+        let mut architectures: Vec<String> = entries
+            .iter()
+            .filter_map(|e| e.get("Architecture"))
+            .filter(|arch| *arch != "all")
+            .map(str::to_owned)
+            .collect();
+        architectures.sort();
+        architectures.dedup();
+        if architectures.is_empty() {
+            architectures.push("all".to_owned());
+        }
+        architectures
+    }
+
+    // Writes an index, and its compressed variants if it is a Packages file,
+    // recording the digests of everything written
+    fn write_index(
+        &self,
+        dist: &Path,
+        path: &Path,
+        contents: &[u8],
+        indices: &mut BTreeMap<PathBuf, FileDigests>,
+    ) -> Result<()> {
+        let mut variants = vec![(path.to_owned(), contents.to_vec())];
+        if path.ends_with("Packages") {
+            for compression in [Compression::Gzip, Compression::Xz] {
+                let enabled = match compression {
+                    Compression::Gzip => cfg!(feature = "gzip"),
+                    _ => cfg!(feature = "xz"),
+                };
+                if enabled {
+                    let mut name = path.as_os_str().to_owned();
+                    name.push(compression.extension());
+                    variants.push((PathBuf::from(name), compression.compress(contents)?));
+                }
+            }
+        }
+
+        for (path, contents) in variants {
+            let full_path = dist.join(&path);
+            fs::create_dir_all(full_path.parent().unwrap())?;
+            fs::write(&full_path, &contents)?;
+
+            let digests = FileDigests::compute(&contents[..])?;
+            if self.by_hash {
+                let by_hash = full_path.parent().unwrap().join("by-hash");
+                for (name, digest) in [
+                    ("MD5Sum", &digests.md5),
+                    ("SHA1", &digests.sha1),
+                    ("SHA256", &digests.sha256),
+                    ("SHA512", &digests.sha512),
+                ] {
+                    fs::create_dir_all(by_hash.join(name))?;
+                    fs::write(by_hash.join(name).join(digest), &contents)?;
+                }
+            }
+            indices.insert(path, digests);
+        }
+        Ok(())
+    }
+
+    fn release(
+        &self,
+        architectures: &[String],
+        indices: &BTreeMap<PathBuf, FileDigests>,
+    ) -> Result<Paragraph> {
+        let date = self.date.unwrap_or_else(SystemTime::now);
+        let secs = match date.duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration.as_secs() as i64,
+            Err(err) => -(err.duration().as_secs() as i64),
+        };
+
+        let mut release = Paragraph::new();
+        if let Some(ref origin) = self.origin {
+            release.set("Origin", origin)?;
+        }
+        if let Some(ref label) = self.label {
+            release.set("Label", label)?;
+        }
+        release.set("Suite", &self.suite)?;
+        if let Some(ref codename) = self.codename {
+            release.set("Codename", codename)?;
+        }
+        release.set("Date", &format_rfc2822(secs))?;
+        release.set("Architectures", &architectures.join(" "))?;
+        release.set("Components", &self.component)?;
+        if let Some(ref description) = self.description {
+            release.set("Description", description)?;
+        }
+        if self.by_hash {
+            release.set("Acquire-By-Hash", "yes")?;
+        }
+
+        for name in ["MD5Sum", "SHA1", "SHA256", "SHA512"] {
+            let mut value = String::new();
+            for (path, digests) in indices {
+                let digest = match name {
+                    "MD5Sum" => &digests.md5,
+                    "SHA1" => &digests.sha1,
+                    "SHA256" => &digests.sha256,
+                    _ => &digests.sha512,
+                };
+                let path = path
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                value.push_str(&format!("\n{} {:>16} {}", digest, digests.size, path));
+            }
+            release.set(name, &value)?;
+        }
+
+        Ok(release)
+    }
+}
+
+fn find_debs(dir: &Path, debs: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            find_debs(&path, debs)?;
+        } else if path.extension().is_some_and(|ext| ext == "deb") {
+            debs.push(path);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,915 @@
+use std::collections::HashMap;
+use std::io::{Read, Seek, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value};
+use sha1::{Digest, Sha1};
+
+use crate::control_archive::relative_path;
+use crate::date::format_iso8601;
+use crate::{parse_relationships, Control, Copyright, DebPkg, FileDigests, Result, SeekableDebPkg};
+
+// The relationship fields listed in an SBOM and whether they are required
+const DEPENDENCY_FIELDS: &[(&str, bool)] = &[
+    ("Pre-Depends", true),
+    ("Depends", true),
+    ("Recommends", false),
+    ("Suggests", false),
+];
+
+// License names which are already SPDX identifiers, in SPDX's spelling
+const SPDX_LICENSES: &[&str] = &[
+    "Apache-1.0",
+    "Apache-1.1",
+    "Apache-2.0",
+    "Artistic-1.0",
+    "Artistic-2.0",
+    "BSD-2-Clause",
+    "BSD-3-Clause",
+    "BSD-4-Clause",
+    "BSL-1.0",
+    "CC-BY-3.0",
+    "CC-BY-4.0",
+    "CC-BY-SA-3.0",
+    "CC-BY-SA-4.0",
+    "CC0-1.0",
+    "EPL-1.0",
+    "EPL-2.0",
+    "ISC",
+    "MIT",
+    "MPL-1.1",
+    "MPL-2.0",
+    "OFL-1.1",
+    "Python-2.0",
+    "Unlicense",
+    "Zlib",
+];
+
+/// Settings for [`DebPkg::write_spdx`] and [`DebPkg::write_cyclonedx`].
+/// Requires the `sbom` feature.
+///
+/// Both formats are deterministic apart from the creation time, so setting
+/// it, e.g. from `SOURCE_DATE_EPOCH`, makes the output reproducible.
+///
+/// # Example
+///
+/// ```
+/// let mut options = debpkg::SbomOptions::new();
+/// options
+///     .created(std::time::UNIX_EPOCH)
+///     .vendor("ubuntu")
+///     .namespace("https://sbom.example.org/spdx");
+/// ```
+#[derive(Debug, Clone)]
+pub struct SbomOptions {
+    created: Option<SystemTime>,
+    vendor: String,
+    namespace: String,
+}
+
+impl Default for SbomOptions {
+    fn default() -> SbomOptions {
+        SbomOptions {
+            created: None,
+            vendor: "debian".to_owned(),
+            namespace: "https://spdx.org/spdxdocs".to_owned(),
+        }
+    }
+}
+
+impl SbomOptions {
+    /// Creates the default options
+    pub fn new() -> SbomOptions {
+        SbomOptions::default()
+    }
+
+    /// Sets the creation time recorded in the SBOM. Defaults to the current
+    /// time.
+    pub fn created(&mut self, created: SystemTime) -> &mut Self {
+        self.created = Some(created);
+        self
+    }
+
+    /// Sets the distribution used as the namespace of package URLs, such as
+    /// `pkg:deb/debian/xz-utils`. Defaults to `debian`.
+    pub fn vendor(&mut self, vendor: &str) -> &mut Self {
+        self.vendor = vendor.to_owned();
+        self
+    }
+
+    /// Sets the URI the SPDX document namespace starts with. Defaults to
+    /// `https://spdx.org/spdxdocs`.
+    pub fn namespace(&mut self, namespace: &str) -> &mut Self {
+        self.namespace = namespace.trim_end_matches('/').to_owned();
+        self
+    }
+
+    fn timestamp(&self) -> String {
+        let created = self.created.unwrap_or_else(SystemTime::now);
+        let secs = match created.duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration.as_secs() as i64,
+            Err(err) => -(err.duration().as_secs() as i64),
+        };
+        format_iso8601(secs)
+    }
+}
+
+// A regular file of the data archive, or a hard link to one
+struct PackageFile {
+    // The path as SPDX writes it, e.g. `./usr/bin/xz`
+    name: String,
+    digests: FileDigests,
+}
+
+// A package named in a relationship field
+struct DependencyRef {
+    name: String,
+    required: bool,
+
+    // The whole entry, e.g. `Depends: libc6 (>= 2.17) | libc6.1`
+    relationship: String,
+}
+
+// Everything an SBOM describes, gathered in a single pass over the data
+// archive without writing anything to disk
+struct SbomInput {
+    control: Control,
+    files: Vec<PackageFile>,
+    copyright: Option<Copyright>,
+}
+
+impl SbomInput {
+    fn read<R: Read>(control: Control, mut data: tar::Archive<R>) -> Result<SbomInput> {
+        let copyright_path: PathBuf = ["usr", "share", "doc", control.name(), "copyright"]
+            .iter()
+            .collect();
+        let mut files = Vec::new();
+        let mut digests: HashMap<PathBuf, FileDigests> = HashMap::new();
+        let mut copyright = None;
+
+        for entry in data.entries()? {
+            let mut entry = entry?;
+            let path = relative_path(&entry.path()?.to_string_lossy());
+            let file_digests = match entry.header().entry_type() {
+                tar::EntryType::Regular | tar::EntryType::Continuous if path == copyright_path => {
+                    let mut contents = Vec::new();
+                    entry.read_to_end(&mut contents)?;
+                    // A copyright file which can not be read leaves the
+                    // licenses as NOASSERTION rather than failing the SBOM
+                    copyright = Copyright::parse(&contents[..]).ok();
+                    FileDigests::compute(&contents[..])?
+                }
+                tar::EntryType::Regular | tar::EntryType::Continuous => {
+                    FileDigests::compute(entry)?
+                }
+                tar::EntryType::Link => {
+                    let target = match entry.link_name()? {
+                        Some(target) => relative_path(&target.to_string_lossy()),
+                        None => continue,
+                    };
+                    match digests.get(&target) {
+                        Some(target_digests) => target_digests.clone(),
+                        None => continue,
+                    }
+                }
+                _ => continue,
+            };
+            files.push(PackageFile {
+                name: format!("./{}", path.display()),
+                digests: file_digests.clone(),
+            });
+            digests.insert(path, file_digests);
+        }
+
+        Ok(SbomInput {
+            control,
+            files,
+            copyright,
+        })
+    }
+
+    fn architecture(&self) -> &str {
+        self.control.get("Architecture").unwrap_or("all")
+    }
+
+    fn purl(&self, vendor: &str) -> String {
+        format!(
+            "pkg:deb/{}/{}@{}?arch={}",
+            purl_encode(vendor),
+            purl_encode(self.control.name()),
+            purl_encode(self.control.version()),
+            purl_encode(self.architecture())
+        )
+    }
+
+    // The packages of the relationship fields, each listed once under the
+    // first field that names it
+    fn dependencies(&self) -> Result<Vec<DependencyRef>> {
+        let mut dependencies: Vec<DependencyRef> = Vec::new();
+        for (field, required) in DEPENDENCY_FIELDS {
+            let value = match self.control.get(field) {
+                Some(value) => value,
+                None => continue,
+            };
+            for dependency in parse_relationships(value)? {
+                for alternative in &dependency.alternatives {
+                    if dependencies.iter().any(|dep| dep.name == alternative.name) {
+                        continue;
+                    }
+                    dependencies.push(DependencyRef {
+                        name: alternative.name.clone(),
+                        required: *required,
+                        relationship: format!("{field}: {dependency}"),
+                    });
+                }
+            }
+        }
+        Ok(dependencies)
+    }
+
+    // The SPDX package verification code, the SHA-1 of the sorted SHA-1
+    // digests of every file
+    fn verification_code(&self) -> String {
+        let mut sha1s: Vec<&str> = self
+            .files
+            .iter()
+            .map(|file| file.digests.sha1.as_str())
+            .collect();
+        sha1s.sort_unstable();
+        let mut hasher = Sha1::new();
+        for sha1 in sha1s {
+            hasher.update(sha1.as_bytes());
+        }
+        format!("{:x}", hasher.finalize())
+    }
+
+    // The license names of a machine-readable copyright file, each listed
+    // once
+    fn license_names(&self) -> Vec<&str> {
+        let copyright = match self.copyright {
+            Some(ref copyright) => copyright,
+            None => return Vec::new(),
+        };
+        let mut names: Vec<&str> = Vec::new();
+        for files in copyright.files() {
+            let name = files.license.name.as_str();
+            if !name.is_empty() && !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    // The distinct lines of every `Copyright` field
+    fn copyright_text(&self) -> Option<String> {
+        let mut lines: Vec<&str> = Vec::new();
+        for files in self.copyright.as_ref()?.files() {
+            for line in files.copyright.as_deref().unwrap_or_default().lines() {
+                if !lines.contains(&line) {
+                    lines.push(line);
+                }
+            }
+        }
+        if lines.is_empty() {
+            None
+        } else {
+            Some(lines.join("\n"))
+        }
+    }
+
+    fn license_text(&self, name: &str) -> Option<&str> {
+        self.copyright.as_ref()?.license_text(name)
+    }
+
+    fn spdx(&self, options: &SbomOptions) -> Result<Value> {
+        let name = self.control.name();
+        let document_name = format!("{name}_{}_{}", self.control.version(), self.architecture());
+        let verification_code = self.verification_code();
+        let package_id = "SPDXRef-Package";
+
+        // Converting the license expressions also collects the non-SPDX
+        // licenses they refer to
+        let mut extracted = Vec::new();
+        let mut declared: Vec<String> = self
+            .license_names()
+            .into_iter()
+            .filter_map(|name| spdx_expression(name, &mut extracted))
+            .collect();
+        // Files without a license do not change the package's license
+        if declared.iter().any(|expression| expression != "NONE") {
+            declared.retain(|expression| expression != "NONE");
+        }
+        let license_declared = match declared.len() {
+            0 => "NOASSERTION".to_owned(),
+            1 => declared.remove(0),
+            _ => declared
+                .iter()
+                .map(|expression| {
+                    if expression.contains(' ') {
+                        format!("({expression})")
+                    } else {
+                        expression.clone()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" AND "),
+        };
+        let extracted: Vec<Value> = extracted
+            .iter()
+            .map(|(id, name)| {
+                json!({
+                    "licenseId": id,
+                    "name": name,
+                    "extractedText": self.license_text(name).unwrap_or(name),
+                })
+            })
+            .collect();
+
+        let mut package = json!({
+            "SPDXID": package_id,
+            "name": name,
+            "versionInfo": self.control.version(),
+            "downloadLocation": "NOASSERTION",
+            "filesAnalyzed": true,
+            "packageVerificationCode": {
+                "packageVerificationCodeValue": verification_code,
+            },
+            "licenseConcluded": "NOASSERTION",
+            "licenseDeclared": license_declared,
+            "copyrightText": self.copyright_text().unwrap_or_else(|| "NOASSERTION".to_owned()),
+            "externalRefs": [{
+                "referenceCategory": "PACKAGE-MANAGER",
+                "referenceType": "purl",
+                "referenceLocator": self.purl(&options.vendor),
+            }],
+        });
+        if let Some(maintainer) = self.control.get("Maintainer") {
+            package["supplier"] = json!(spdx_person(maintainer));
+        }
+        if let Some(summary) = self.control.short_description() {
+            package["summary"] = json!(summary);
+        }
+
+        let mut packages = vec![package];
+        let mut relationships = vec![json!({
+            "spdxElementId": "SPDXRef-DOCUMENT",
+            "relationshipType": "DESCRIBES",
+            "relatedSpdxElement": package_id,
+        })];
+        for dependency in self.dependencies()? {
+            let id = format!("SPDXRef-Package-{}", spdx_id(&dependency.name));
+            packages.push(json!({
+                "SPDXID": id,
+                "name": dependency.name,
+                "downloadLocation": "NOASSERTION",
+                "filesAnalyzed": false,
+                "externalRefs": [{
+                    "referenceCategory": "PACKAGE-MANAGER",
+                    "referenceType": "purl",
+                    "referenceLocator": format!(
+                        "pkg:deb/{}/{}",
+                        purl_encode(&options.vendor),
+                        purl_encode(&dependency.name)
+                    ),
+                }],
+            }));
+            let mut relationship = if dependency.required {
+                json!({
+                    "spdxElementId": package_id,
+                    "relationshipType": "DEPENDS_ON",
+                    "relatedSpdxElement": id,
+                })
+            } else {
+                json!({
+                    "spdxElementId": id,
+                    "relationshipType": "OPTIONAL_DEPENDENCY_OF",
+                    "relatedSpdxElement": package_id,
+                })
+            };
+            relationship["comment"] = json!(dependency.relationship);
+            relationships.push(relationship);
+        }
+
+        let mut files = Vec::with_capacity(self.files.len());
+        for (i, file) in self.files.iter().enumerate() {
+            let id = format!("SPDXRef-File-{}", i + 1);
+            files.push(json!({
+                "SPDXID": id,
+                "fileName": file.name,
+                "checksums": [
+                    { "algorithm": "SHA1", "checksumValue": file.digests.sha1 },
+                    { "algorithm": "SHA256", "checksumValue": file.digests.sha256 },
+                    { "algorithm": "MD5", "checksumValue": file.digests.md5 },
+                ],
+                "licenseConcluded": "NOASSERTION",
+                "copyrightText": "NOASSERTION",
+            }));
+            relationships.push(json!({
+                "spdxElementId": package_id,
+                "relationshipType": "CONTAINS",
+                "relatedSpdxElement": id,
+            }));
+        }
+
+        let mut document = json!({
+            "spdxVersion": "SPDX-2.3",
+            "dataLicense": "CC0-1.0",
+            "SPDXID": "SPDXRef-DOCUMENT",
+            "name": document_name,
+            "documentNamespace": format!(
+                "{}/{document_name}-{verification_code}",
+                options.namespace
+            ),
+            "creationInfo": {
+                "created": options.timestamp(),
+                "creators": [concat!("Tool: debpkg-", env!("CARGO_PKG_VERSION"))],
+            },
+            "packages": packages,
+            "files": files,
+            "relationships": relationships,
+        });
+        if !extracted.is_empty() {
+            document["hasExtractedLicensingInfos"] = json!(extracted);
+        }
+        Ok(document)
+    }
+
+    fn cyclonedx(&self, options: &SbomOptions) -> Result<Value> {
+        let purl = self.purl(&options.vendor);
+        let section = self.control.get("Section").unwrap_or_default();
+        let kind = if section.ends_with("libs") || section.ends_with("libdevel") {
+            "library"
+        } else {
+            "application"
+        };
+
+        let mut component = json!({
+            "type": kind,
+            "bom-ref": purl,
+            "name": self.control.name(),
+            "version": self.control.version(),
+            "purl": purl,
+            "properties": [{
+                "name": "debian:architecture",
+                "value": self.architecture(),
+            }],
+        });
+        if let Some(maintainer) = self.control.get("Maintainer") {
+            component["publisher"] = json!(maintainer);
+        }
+        if let Some(summary) = self.control.short_description() {
+            component["description"] = json!(summary);
+        }
+        let licenses = self.cyclonedx_licenses();
+        if !licenses.is_empty() {
+            component["licenses"] = json!(licenses);
+        }
+        if let Some(copyright) = self.copyright_text() {
+            component["copyright"] = json!(copyright);
+        }
+
+        let mut components = Vec::new();
+        let mut depends_on = Vec::new();
+        for dependency in self.dependencies()? {
+            let dependency_purl = format!(
+                "pkg:deb/{}/{}",
+                purl_encode(&options.vendor),
+                purl_encode(&dependency.name)
+            );
+            components.push(json!({
+                "type": "library",
+                "bom-ref": dependency_purl,
+                "name": dependency.name,
+                "purl": dependency_purl,
+                "scope": if dependency.required { "required" } else { "optional" },
+                "properties": [{
+                    "name": "debian:relationship",
+                    "value": dependency.relationship,
+                }],
+            }));
+            depends_on.push(dependency_purl);
+        }
+        for file in &self.files {
+            components.push(json!({
+                "type": "file",
+                "bom-ref": format!("{purl}#{}", file.name.trim_start_matches("./")),
+                "name": file.name,
+                "hashes": [
+                    { "alg": "SHA-1", "content": file.digests.sha1 },
+                    { "alg": "SHA-256", "content": file.digests.sha256 },
+                    { "alg": "SHA-512", "content": file.digests.sha512 },
+                    { "alg": "MD5", "content": file.digests.md5 },
+                ],
+            }));
+        }
+
+        Ok(json!({
+            "bomFormat": "CycloneDX",
+            "specVersion": "1.5",
+            "serialNumber": format!("urn:uuid:{}", uuid_from(&self.verification_code(), &purl)),
+            "version": 1,
+            "metadata": {
+                "timestamp": options.timestamp(),
+                "tools": {
+                    "components": [{
+                        "type": "library",
+                        "name": "debpkg",
+                        "version": env!("CARGO_PKG_VERSION"),
+                    }],
+                },
+                "component": component,
+            },
+            "components": components,
+            "dependencies": [{
+                "ref": purl,
+                "dependsOn": depends_on,
+            }],
+        }))
+    }
+
+    // Lists each license named in the copyright file once. CycloneDX can
+    // not mix expressions with single licenses, so expressions such as
+    // `GPL-2+ or Artistic` are split into their licenses. A license with an
+    // exception stays a single named license.
+    fn cyclonedx_licenses(&self) -> Vec<Value> {
+        let mut seen = Vec::new();
+        let mut licenses = Vec::new();
+        for name in self.license_names() {
+            for term in license_terms(name) {
+                if seen.contains(&term) {
+                    continue;
+                }
+                seen.push(term.clone());
+                let id = spdx_license_id(&term);
+                if id == "NONE" {
+                    continue;
+                }
+                if !id.starts_with("LicenseRef-") {
+                    licenses.push(json!({ "license": { "id": id } }));
+                    continue;
+                }
+                let mut license = json!({ "name": term });
+                if let Some(text) = self.license_text(&term) {
+                    license["text"] = json!({ "content": text });
+                }
+                licenses.push(json!({ "license": license }));
+            }
+        }
+        licenses
+    }
+}
+
+// Percent encodes a component of a package URL
+fn purl_encode(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'.' | b'-' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+// Replaces the characters SPDX does not allow in identifiers
+fn spdx_id(s: &str) -> String {
+    s.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+// Turns a `Name <email>` maintainer into SPDX's `Person: Name (email)`
+fn spdx_person(maintainer: &str) -> String {
+    match maintainer.split_once('<') {
+        Some((name, email)) => format!(
+            "Person: {} ({})",
+            name.trim(),
+            email.trim().trim_end_matches('>')
+        ),
+        None => format!("Person: {}", maintainer.trim()),
+    }
+}
+
+// A part of a DEP-5 license expression
+#[derive(Debug, PartialEq, Eq)]
+enum LicenseToken {
+    // `OR` or `AND`
+    Operator(String),
+
+    // A license, including any exception such as `GPL-2+ with OpenSSL
+    // exception`
+    License(String),
+}
+
+// Splits a DEP-5 license expression such as `GPL-2+ or Artistic` into its
+// licenses and the operators between them
+fn license_tokens(name: &str) -> Vec<LicenseToken> {
+    let mut tokens = Vec::new();
+    let mut words: Vec<&str> = Vec::new();
+    for word in name.split_whitespace() {
+        let term = word.trim_end_matches(',');
+        if term.eq_ignore_ascii_case("or") || term.eq_ignore_ascii_case("and") {
+            if !words.is_empty() {
+                tokens.push(LicenseToken::License(words.join(" ")));
+                words.clear();
+            }
+            tokens.push(LicenseToken::Operator(term.to_ascii_uppercase()));
+        } else if !term.is_empty() {
+            words.push(term);
+        }
+    }
+    if !words.is_empty() {
+        tokens.push(LicenseToken::License(words.join(" ")));
+    }
+    tokens
+}
+
+// Returns the licenses of a DEP-5 license expression
+fn license_terms(name: &str) -> impl Iterator<Item = String> {
+    license_tokens(name)
+        .into_iter()
+        .filter_map(|token| match token {
+            LicenseToken::License(license) => Some(license),
+            LicenseToken::Operator(_) => None,
+        })
+}
+
+// Converts a DEP-5 short license name to an SPDX identifier, using a
+// `LicenseRef-` for licenses SPDX does not know
+fn spdx_license_id(name: &str) -> String {
+    let (base, or_later) = match name.strip_suffix('+') {
+        Some(base) => (base, true),
+        None => (name, false),
+    };
+    if name.eq_ignore_ascii_case("none") {
+        return "NONE".to_owned();
+    }
+    if base.eq_ignore_ascii_case("Expat") {
+        return "MIT".to_owned();
+    }
+    if let Some(id) = SPDX_LICENSES
+        .iter()
+        .find(|id| id.eq_ignore_ascii_case(name))
+    {
+        return (*id).to_owned();
+    }
+    for family in ["AGPL", "LGPL", "GPL", "GFDL"] {
+        let version = base
+            .strip_prefix(family)
+            .and_then(|rest| rest.strip_prefix('-'))
+            .filter(|version| version.chars().all(|c| c.is_ascii_digit() || c == '.'));
+        if let Some(version) = version.filter(|version| !version.is_empty()) {
+            let version = if version.contains('.') {
+                version.to_owned()
+            } else {
+                format!("{version}.0")
+            };
+            let suffix = if or_later { "or-later" } else { "only" };
+            return format!("{family}-{version}-{suffix}");
+        }
+    }
+    format!("LicenseRef-{}", spdx_id(name))
+}
+
+// Converts a DEP-5 license expression to an SPDX expression, recording the
+// `LicenseRef-` identifiers it uses along with their DEP-5 names. A license
+// with an exception becomes a single `LicenseRef-`, as the exceptions DEP-5
+// names do not map onto SPDX's. Returns None if the expression is empty.
+fn spdx_expression(name: &str, extracted: &mut Vec<(String, String)>) -> Option<String> {
+    let mut expression = Vec::new();
+    for token in license_tokens(name) {
+        match token {
+            LicenseToken::Operator(operator) => expression.push(operator),
+            LicenseToken::License(license) => {
+                let id = spdx_license_id(&license);
+                if id.starts_with("LicenseRef-") && !extracted.iter().any(|(known, _)| *known == id)
+                {
+                    extracted.push((id.clone(), license));
+                }
+                expression.push(id);
+            }
+        }
+    }
+    if expression.is_empty() {
+        None
+    } else {
+        Some(expression.join(" "))
+    }
+}
+
+// Builds a name based (version 5) UUID, so the same package always gets the
+// same serial number
+fn uuid_from(verification_code: &str, purl: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(purl.as_bytes());
+    hasher.update(verification_code.as_bytes());
+    let mut bytes = hasher.finalize();
+    bytes[6] = (bytes[6] & 0x0f) | 0x50;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes[..16].iter().map(|b| format!("{b:02x}")).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+fn write_json<W: Write>(writer: W, value: &Value) -> Result<()> {
+    serde_json::to_writer_pretty(writer, value).map_err(std::io::Error::from)?;
+    Ok(())
+}
+
+impl<R: Read> DebPkg<R> {
+    /// Writes an [SPDX 2.3](https://spdx.github.io/spdx-spec/v2.3/) JSON
+    /// document describing the package. Requires the `sbom` feature.
+    ///
+    /// The document holds the name, version and architecture of the package,
+    /// a package and a relationship for every package named in `Depends`,
+    /// `Pre-Depends`, `Recommends` and `Suggests`, the checksums of every
+    /// file and the licenses of a machine-readable copyright file. If the
+    /// copyright file is missing or not machine-readable, the licenses and
+    /// copyright text are `NOASSERTION`. The data archive is read once and
+    /// nothing is written to disk.
+    ///
+    /// The control and data archives are both read, so this must be called
+    /// before `control` or `data`.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Where the JSON document is written
+    /// * `options` - The creation time and namespaces to use
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use debpkg::{DebPkg, SbomOptions};
+    /// let file = std::fs::File::open("test.deb").unwrap();
+    /// let mut pkg = DebPkg::parse(file).unwrap();
+    /// let out = std::fs::File::create("test.spdx.json").unwrap();
+    /// pkg.write_spdx(out, &SbomOptions::new()).unwrap();
+    /// ```
+    pub fn write_spdx<W: Write>(&mut self, writer: W, options: &SbomOptions) -> Result<()> {
+        let control = Control::extract(self.control()?)?;
+        let input = SbomInput::read(control, self.data()?)?;
+        write_json(writer, &input.spdx(options)?)
+    }
+
+    /// Writes a [CycloneDX 1.5](https://cyclonedx.org/docs/1.5/json/) JSON
+    /// BOM describing the package. Requires the `sbom` feature.
+    ///
+    /// The BOM holds the same information as [`DebPkg::write_spdx`], with
+    /// the package as the metadata component and its dependencies and files
+    /// as components.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Where the JSON document is written
+    /// * `options` - The creation time and namespaces to use
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use debpkg::{DebPkg, SbomOptions};
+    /// let file = std::fs::File::open("test.deb").unwrap();
+    /// let mut pkg = DebPkg::parse(file).unwrap();
+    /// pkg.write_cyclonedx(std::io::stdout(), &SbomOptions::new()).unwrap();
+    /// ```
+    pub fn write_cyclonedx<W: Write>(&mut self, writer: W, options: &SbomOptions) -> Result<()> {
+        let control = Control::extract(self.control()?)?;
+        let input = SbomInput::read(control, self.data()?)?;
+        write_json(writer, &input.cyclonedx(options)?)
+    }
+}
+
+impl<R: Read + Seek> SeekableDebPkg<R> {
+    /// Writes an SPDX 2.3 JSON document describing the package. See
+    /// [`DebPkg::write_spdx`].
+    pub fn write_spdx<W: Write>(&mut self, writer: W, options: &SbomOptions) -> Result<()> {
+        let control = Control::extract(self.control()?)?;
+        let input = SbomInput::read(control, self.data()?)?;
+        write_json(writer, &input.spdx(options)?)
+    }
+
+    /// Writes a CycloneDX 1.5 JSON BOM describing the package. See
+    /// [`DebPkg::write_cyclonedx`].
+    pub fn write_cyclonedx<W: Write>(&mut self, writer: W, options: &SbomOptions) -> Result<()> {
+        let control = Control::extract(self.control()?)?;
+        let input = SbomInput::read(control, self.data()?)?;
+        write_json(writer, &input.cyclonedx(options)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn licenses_convert_to_spdx() {
+        assert_eq!(spdx_license_id("GPL-2+"), "GPL-2.0-or-later");
+        assert_eq!(spdx_license_id("LGPL-2.1"), "LGPL-2.1-only");
+        assert_eq!(spdx_license_id("Expat"), "MIT");
+        assert_eq!(spdx_license_id("bsd-3-clause"), "BSD-3-Clause");
+        assert_eq!(spdx_license_id("none"), "NONE");
+        assert_eq!(spdx_license_id("public domain"), "LicenseRef-public-domain");
+
+        let mut extracted = Vec::new();
+        assert_eq!(
+            spdx_expression("GPL-2+ or Artistic", &mut extracted).as_deref(),
+            Some("GPL-2.0-or-later OR LicenseRef-Artistic")
+        );
+        assert_eq!(
+            extracted,
+            [("LicenseRef-Artistic".to_owned(), "Artistic".to_owned())]
+        );
+        assert_eq!(
+            spdx_expression("GPL-2+ with OpenSSL exception, or MIT", &mut extracted).as_deref(),
+            Some("LicenseRef-GPL-2--with-OpenSSL-exception OR MIT")
+        );
+        assert_eq!(
+            extracted[1],
+            (
+                "LicenseRef-GPL-2--with-OpenSSL-exception".to_owned(),
+                "GPL-2+ with OpenSSL exception".to_owned()
+            )
+        );
+        assert_eq!(spdx_expression("", &mut extracted), None);
+    }
+
+    #[test]
+    fn exceptions_are_kept_in_both_formats() {
+        let control = Control::parse(&b"Package: a\nVersion: 1.0\n"[..]).unwrap();
+        let copyright = "Format: dep5
+
+Files: *
+Copyright: 2001 Alice
+License: GPL-2+ with OpenSSL exception
+
+Files: debian/*
+Copyright: 2020 Carol
+License: MIT
+
+License: GPL-2+ with OpenSSL exception
+ This program is free software.
+";
+        let input = SbomInput {
+            control,
+            files: Vec::new(),
+            copyright: Some(Copyright::parse(copyright.as_bytes()).unwrap()),
+        };
+        let options = SbomOptions::new();
+
+        let spdx = input.spdx(&options).unwrap();
+        let id = "LicenseRef-GPL-2--with-OpenSSL-exception";
+        assert_eq!(
+            spdx["packages"][0]["licenseDeclared"],
+            format!("{id} AND MIT")
+        );
+        assert_eq!(spdx["hasExtractedLicensingInfos"][0]["licenseId"], id);
+        assert_eq!(
+            spdx["hasExtractedLicensingInfos"][0]["extractedText"],
+            "This program is free software."
+        );
+
+        let cyclonedx = input.cyclonedx(&options).unwrap();
+        assert_eq!(
+            cyclonedx["metadata"]["component"]["licenses"],
+            json!([
+                { "license": {
+                    "name": "GPL-2+ with OpenSSL exception",
+                    "text": { "content": "This program is free software." },
+                } },
+                { "license": { "id": "MIT" } },
+            ])
+        );
+    }
+
+    #[test]
+    fn purls_are_encoded() {
+        assert_eq!(purl_encode("1:2.0+dfsg-1"), "1%3A2.0%2Bdfsg-1");
+        assert_eq!(purl_encode("libstdc++6"), "libstdc%2B%2B6");
+    }
+
+    #[test]
+    fn helpers_format_ids() {
+        assert_eq!(
+            spdx_person("Jonathan Nieder <jrnieder@gmail.com>"),
+            "Person: Jonathan Nieder (jrnieder@gmail.com)"
+        );
+        assert_eq!(spdx_id("libstdc++6"), "libstdc--6");
+        let uuid = uuid_from("abc", "pkg:deb/debian/a@1?arch=all");
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(&uuid[14..15], "5");
+    }
+}
@@ -0,0 +1,243 @@
+use std::io::{Read, Seek, Write};
+
+use crate::debian_binary::DebianBinaryVersion;
+use crate::{
+    extract, get_tar_from_entry, validate_debian_binary, ArMember, ArMemberInfo, Error,
+    ExtractOptions, PackageInfo, Result,
+};
+
+/// A debian package backed by a reader that can seek
+///
+/// Unlike [`DebPkg`](crate::DebPkg), the offsets of the ar members are
+/// indexed up front so the control and data archives can be opened in any
+/// order and any number of times.
+pub struct SeekableDebPkg<R: Read + Seek> {
+    /// The major and minor fomat version of the debian package
+    format_version: DebianBinaryVersion,
+
+    /// The ar archive in which the debian package is contained
+    archive: ar::Archive<R>,
+
+    /// The index of the control archive among the ar members
+    control_index: usize,
+
+    /// The index of the data archive among the ar members
+    data_index: usize,
+
+    /// The indices and names of the members starting with `_`
+    extra_indices: Vec<(usize, String)>,
+}
+
+fn member_name(header: &ar::Header) -> String {
+    String::from_utf8_lossy(header.identifier()).into_owned()
+}
+
+impl<R: Read + Seek> SeekableDebPkg<R> {
+    /// Parses a debian package out of reader, indexing its ar members
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - A type which implements `std::io::Read` and `std::io::Seek`
+    ///   and is formatted as an ar archive
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use debpkg::{Control, SeekableDebPkg};
+    /// let file = std::fs::File::open("test.deb").unwrap();
+    /// let mut pkg = SeekableDebPkg::parse(file).unwrap();
+    /// let data_tar = pkg.data().unwrap();
+    /// drop(data_tar);
+    /// let control = Control::extract(pkg.control().unwrap()).unwrap();
+    /// ```
+    pub fn parse(reader: R) -> Result<SeekableDebPkg<R>> {
+        let mut archive = ar::Archive::new(reader);
+        let count = archive.count_entries()?;
+        if count == 0 {
+            return Err(Error::MissingDebianBinary);
+        }
+        let format_version = {
+            let mut entry = archive.jump_to_entry(0)?;
+            let name = member_name(entry.header());
+            validate_debian_binary(&name, &mut entry)?
+        };
+
+        let mut control_index = None;
+        let mut data_index = None;
+        let mut extra_indices = Vec::new();
+        for index in 1..count {
+            let entry = archive.jump_to_entry(index)?;
+            let name = member_name(entry.header());
+            if name.starts_with('_') {
+                extra_indices.push((index, name));
+            } else if control_index.is_none() && name.starts_with("control.tar") {
+                control_index = Some(index);
+            } else if data_index.is_none() && name.starts_with("data.tar") {
+                data_index = Some(index);
+            }
+        }
+
+        Ok(SeekableDebPkg {
+            format_version,
+            archive,
+            control_index: control_index.ok_or(Error::MissingControlArchive)?,
+            data_index: data_index.ok_or(Error::MissingDataArchive)?,
+            extra_indices,
+        })
+    }
+
+    /// Returns the format version of the binary debian package
+    pub fn format_version(&self) -> (u32, u32) {
+        (self.format_version.major, self.format_version.minor)
+    }
+
+    /// Returns the control tar. May be called any number of times.
+    pub fn control(&mut self) -> Result<tar::Archive<Box<dyn Read + '_>>> {
+        let entry = self.archive.jump_to_entry(self.control_index)?;
+        get_tar_from_entry(entry)
+    }
+
+    /// Returns the data tar. May be called any number of times, before or
+    /// after `control`.
+    pub fn data(&mut self) -> Result<tar::Archive<Box<dyn Read + '_>>> {
+        let entry = self.archive.jump_to_entry(self.data_index)?;
+        get_tar_from_entry(entry)
+    }
+
+    /// Unpacks the data tar into `dir`, rejecting entries which would be
+    /// written outside of it. See [`DebPkg::extract_to`](crate::DebPkg::extract_to).
+    pub fn extract_to<P: AsRef<std::path::Path>>(
+        &mut self,
+        dir: P,
+        options: &ExtractOptions,
+    ) -> Result<Vec<std::path::PathBuf>> {
+        extract::extract_archive(self.data()?, dir.as_ref(), options)
+    }
+
+    /// Summarizes the package like `dpkg-deb --info`. See
+    /// [`DebPkg::info`](crate::DebPkg::info).
+    pub fn info(&mut self) -> Result<PackageInfo> {
+        let mut members = Vec::new();
+        for index in 0..self.archive.count_entries()? {
+            let entry = self.archive.jump_to_entry(index)?;
+            members.push(ArMemberInfo {
+                name: member_name(entry.header()),
+                size: entry.header().size(),
+            });
+        }
+
+        let mut control_tar = Vec::new();
+        self.archive
+            .jump_to_entry(self.control_index)?
+            .read_to_end(&mut control_tar)?;
+        let mut data_header = Vec::new();
+        self.archive
+            .jump_to_entry(self.data_index)?
+            .take(1024)
+            .read_to_end(&mut data_header)?;
+        PackageInfo::read(
+            self.format_version(),
+            members,
+            &control_tar[..],
+            &data_header,
+        )
+    }
+
+    /// Returns the names of the members starting with `_`, such as the
+    /// `_gpgorigin` signature added by debsigs
+    pub fn extra_member_names(&self) -> impl Iterator<Item = &str> {
+        self.extra_indices.iter().map(|(_, name)| name.as_str())
+    }
+
+    /// Reads the contents of every member starting with `_`
+    pub fn extra_members(&mut self) -> Result<Vec<ArMember>> {
+        let mut members = Vec::new();
+        for (index, name) in &self.extra_indices {
+            let mut contents = Vec::new();
+            self.archive
+                .jump_to_entry(*index)?
+                .read_to_end(&mut contents)?;
+            members.push(ArMember {
+                name: name.clone(),
+                contents,
+            });
+        }
+        Ok(members)
+    }
+
+    /// Writes the byte stream covered by a debsigs signature, which is the
+    /// contents of `debian-binary`, the control tar and the data tar
+    /// concatenated. Returns the number of bytes written.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use debpkg::SeekableDebPkg;
+    /// let file = std::fs::File::open("test.deb").unwrap();
+    /// let mut pkg = SeekableDebPkg::parse(file).unwrap();
+    /// let signed = std::fs::File::create("test.deb.signed").unwrap();
+    /// pkg.write_signed_data(signed).unwrap();
+    /// ```
+    pub fn write_signed_data<W: Write>(&mut self, mut writer: W) -> Result<u64> {
+        let mut written = 0;
+        for index in [0, self.control_index, self.data_index] {
+            let mut entry = self.archive.jump_to_entry(index)?;
+            written += std::io::copy(&mut entry, &mut writer)?;
+        }
+        Ok(written)
+    }
+
+    /// Verifies a debsigs signature member, such as `_gpgorigin`, against a
+    /// keyring by running `gpgv`. Requires the `gpgv` feature and `gpgv` to
+    /// be installed.
+    ///
+    /// Only detached signatures over [`write_signed_data`](Self::write_signed_data)
+    /// are supported. The clearsigned manifests of dpkg-sig, stored in a
+    /// `_gpgbuilder` member, are rejected.
+    ///
+    /// # Arguments
+    ///
+    /// * `member` - The name of the signature member
+    /// * `keyring` - The path of the keyring containing the trusted keys
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use debpkg::SeekableDebPkg;
+    /// let file = std::fs::File::open("test.deb").unwrap();
+    /// let mut pkg = SeekableDebPkg::parse(file).unwrap();
+    /// pkg.verify_signature("_gpgorigin", "/usr/share/keyrings/trusted.gpg")
+    ///     .unwrap();
+    /// ```
+    #[cfg(feature = "gpgv")]
+    pub fn verify_signature<P: AsRef<std::path::Path>>(
+        &mut self,
+        member: &str,
+        keyring: P,
+    ) -> Result<()> {
+        if member == "_gpgbuilder" {
+            return Err(Error::InvalidSignature(
+                "dpkg-sig signatures are not supported".to_owned(),
+            ));
+        }
+        let index = match self.extra_indices.iter().find(|(_, name)| name == member) {
+            Some((index, _)) => *index,
+            None => {
+                return Err(Error::InvalidSignature(format!(
+                    "package has no {member} member"
+                )))
+            }
+        };
+        let mut signature = tempfile::NamedTempFile::new()?;
+        std::io::copy(&mut self.archive.jump_to_entry(index)?, &mut signature)?;
+
+        crate::gpgv::gpgv(keyring.as_ref(), Some(signature.path()), |stdin| {
+            self.write_signed_data(stdin).map(|_| ())
+        })
+    }
+
+    /// Consumes the package, returning the underlying reader
+    pub fn into_inner(self) -> Result<R> {
+        Ok(self.archive.into_inner()?)
+    }
+}
@@ -0,0 +1,378 @@
+use std::fmt;
+use std::io::Read;
+
+use crate::substvars::{has_substvar, substvar_name};
+use crate::{Deb822Reader, Dependency, Error, Paragraph, Result};
+
+fn invalid(msg: String) -> Error {
+    Error::InvalidSourceControl(msg)
+}
+
+/// One comma separated entry of a relationship field in a `debian/control`
+/// file, where substitution variables have not been replaced yet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourceRelationship {
+    /// A fully specified entry such as `libfoo (>= 1.0) | libbar`
+    Dependency(Dependency),
+
+    /// An entry consisting of a single substitution variable such as
+    /// `${shlibs:Depends}`. Holds the variable name.
+    Substvar(String),
+
+    /// An entry using substitution variables within it, such as
+    /// `hello-data (= ${source:Version})`. Holds the entry as written; it
+    /// can be parsed once the variables are substituted.
+    Template(String),
+}
+
+impl fmt::Display for SourceRelationship {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SourceRelationship::Dependency(ref dependency) => write!(f, "{dependency}"),
+            SourceRelationship::Substvar(ref name) => write!(f, "${{{name}}}"),
+            SourceRelationship::Template(ref entry) => f.write_str(entry),
+        }
+    }
+}
+
+/// Parses the value of a relationship field from a `debian/control` file,
+/// keeping substitution variables as separate entries
+///
+/// # Arguments
+///
+/// * `value` - The field value
+///
+/// # Example
+///
+/// ```
+/// use debpkg::SourceRelationship;
+/// let deps = debpkg::parse_source_relationships("${misc:Depends}, libc6").unwrap();
+/// assert_eq!(deps[0], SourceRelationship::Substvar("misc:Depends".to_owned()));
+/// assert!(matches!(deps[1], SourceRelationship::Dependency(_)));
+/// ```
+pub fn parse_source_relationships(value: &str) -> Result<Vec<SourceRelationship>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            if let Some(name) = substvar_name(entry) {
+                Ok(SourceRelationship::Substvar(name.to_owned()))
+            } else if has_substvar(entry) {
+                Ok(SourceRelationship::Template(entry.to_owned()))
+            } else {
+                entry.parse().map(SourceRelationship::Dependency)
+            }
+        })
+        .collect()
+}
+
+fn relationships(paragraph: &Paragraph, field_name: &str) -> Result<Vec<SourceRelationship>> {
+    match paragraph.get(field_name) {
+        Some(value) => parse_source_relationships(value),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// A binary package paragraph of a `debian/control` file
+#[derive(Debug, Clone)]
+pub struct BinaryPackage {
+    paragraph: Paragraph,
+}
+
+impl BinaryPackage {
+    /// Returns the binary package name
+    pub fn name(&self) -> &str {
+        self.paragraph.get("Package").unwrap()
+    }
+
+    /// Returns the architectures listed in the `Architecture` field
+    pub fn architecture(&self) -> Vec<&str> {
+        self.paragraph
+            .get("Architecture")
+            .map(|value| value.split_whitespace().collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the parsed `Depends` field, or an empty list if it is absent
+    pub fn depends(&self) -> Result<Vec<SourceRelationship>> {
+        relationships(&self.paragraph, "Depends")
+    }
+
+    /// Returns the parsed `Pre-Depends` field, or an empty list if it is
+    /// absent
+    pub fn pre_depends(&self) -> Result<Vec<SourceRelationship>> {
+        relationships(&self.paragraph, "Pre-Depends")
+    }
+
+    /// Returns the parsed `Recommends` field, or an empty list if it is
+    /// absent
+    pub fn recommends(&self) -> Result<Vec<SourceRelationship>> {
+        relationships(&self.paragraph, "Recommends")
+    }
+
+    /// Returns the parsed `Suggests` field, or an empty list if it is absent
+    pub fn suggests(&self) -> Result<Vec<SourceRelationship>> {
+        relationships(&self.paragraph, "Suggests")
+    }
+
+    /// Returns the parsed `Breaks` field, or an empty list if it is absent
+    pub fn breaks(&self) -> Result<Vec<SourceRelationship>> {
+        relationships(&self.paragraph, "Breaks")
+    }
+
+    /// Returns the parsed `Conflicts` field, or an empty list if it is absent
+    pub fn conflicts(&self) -> Result<Vec<SourceRelationship>> {
+        relationships(&self.paragraph, "Conflicts")
+    }
+
+    /// Returns the parsed `Replaces` field, or an empty list if it is absent
+    pub fn replaces(&self) -> Result<Vec<SourceRelationship>> {
+        relationships(&self.paragraph, "Replaces")
+    }
+
+    /// Returns the parsed `Provides` field, or an empty list if it is absent
+    pub fn provides(&self) -> Result<Vec<SourceRelationship>> {
+        relationships(&self.paragraph, "Provides")
+    }
+
+    /// Returns the parsed `Enhances` field, or an empty list if it is absent
+    pub fn enhances(&self) -> Result<Vec<SourceRelationship>> {
+        relationships(&self.paragraph, "Enhances")
+    }
+
+    /// Returns field value based on field name if it exists
+    pub fn get(&self, field_name: &str) -> Option<&str> {
+        self.paragraph.get(field_name)
+    }
+
+    /// Returns all the fields of the binary package paragraph
+    pub fn paragraph(&self) -> &Paragraph {
+        &self.paragraph
+    }
+}
+
+/// A parsed source package `debian/control` file, made of a `Source`
+/// paragraph followed by one paragraph per binary package
+///
+/// Substitution variables such as `${shlibs:Depends}` are kept as written;
+/// see [`Substvars`](crate::Substvars) to replace them.
+#[derive(Debug, Clone)]
+pub struct SourceControl {
+    source: Paragraph,
+    binaries: Vec<BinaryPackage>,
+}
+
+impl SourceControl {
+    /// Parses a `debian/control` file. Comment lines starting with `#` are
+    /// ignored.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let file = std::fs::File::open("debian/control").unwrap();
+    /// let control = debpkg::SourceControl::parse(file).unwrap();
+    /// println!("{}", control.source());
+    /// for dependency in control.build_depends().unwrap() {
+    ///     println!("{}", dependency);
+    /// }
+    /// for binary in control.binaries() {
+    ///     println!("{}", binary.name());
+    /// }
+    /// ```
+    pub fn parse<R: Read>(reader: R) -> Result<SourceControl> {
+        let mut paragraphs = Deb822Reader::new(reader);
+        let source = match paragraphs.next() {
+            Some(paragraph) => paragraph?,
+            None => return Err(invalid("empty debian/control file".to_owned())),
+        };
+        if source.get("Source").is_none() {
+            return Err(invalid("first paragraph has no Source field".to_owned()));
+        }
+
+        let binaries = paragraphs
+            .map(|paragraph| {
+                let paragraph = paragraph?;
+                if paragraph.get("Package").is_none() {
+                    return Err(invalid(
+                        "binary package paragraph has no Package field".to_owned(),
+                    ));
+                }
+                Ok(BinaryPackage { paragraph })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if binaries.is_empty() {
+            return Err(invalid("no binary package paragraphs".to_owned()));
+        }
+
+        Ok(SourceControl { source, binaries })
+    }
+
+    /// Returns the source package name
+    pub fn source(&self) -> &str {
+        self.source.get("Source").unwrap()
+    }
+
+    /// Returns the `Maintainer` field
+    pub fn maintainer(&self) -> Option<&str> {
+        self.source.get("Maintainer")
+    }
+
+    /// Returns the parsed `Build-Depends` field, or an empty list if it is
+    /// absent
+    pub fn build_depends(&self) -> Result<Vec<SourceRelationship>> {
+        relationships(&self.source, "Build-Depends")
+    }
+
+    /// Returns the parsed `Build-Depends-Indep` field, or an empty list if it
+    /// is absent
+    pub fn build_depends_indep(&self) -> Result<Vec<SourceRelationship>> {
+        relationships(&self.source, "Build-Depends-Indep")
+    }
+
+    /// Returns the parsed `Build-Depends-Arch` field, or an empty list if it
+    /// is absent
+    pub fn build_depends_arch(&self) -> Result<Vec<SourceRelationship>> {
+        relationships(&self.source, "Build-Depends-Arch")
+    }
+
+    /// Returns the parsed `Build-Conflicts` field, or an empty list if it is
+    /// absent
+    pub fn build_conflicts(&self) -> Result<Vec<SourceRelationship>> {
+        relationships(&self.source, "Build-Conflicts")
+    }
+
+    /// Returns the parsed `Build-Conflicts-Indep` field, or an empty list if
+    /// it is absent
+    pub fn build_conflicts_indep(&self) -> Result<Vec<SourceRelationship>> {
+        relationships(&self.source, "Build-Conflicts-Indep")
+    }
+
+    /// Returns the parsed `Build-Conflicts-Arch` field, or an empty list if
+    /// it is absent
+    pub fn build_conflicts_arch(&self) -> Result<Vec<SourceRelationship>> {
+        relationships(&self.source, "Build-Conflicts-Arch")
+    }
+
+    /// Returns the fields of the `Source` paragraph
+    pub fn source_paragraph(&self) -> &Paragraph {
+        &self.source
+    }
+
+    /// Returns the binary package paragraphs in the order they were listed
+    pub fn binaries(&self) -> &[BinaryPackage] {
+        &self.binaries
+    }
+
+    /// Returns the binary package with the given name if it exists
+    pub fn binary(&self, name: &str) -> Option<&BinaryPackage> {
+        self.binaries.iter().find(|binary| binary.name() == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Control, Substvars};
+    use assert_matches::assert_matches;
+    use std::convert::TryFrom;
+
+    const CONTROL: &str = "# Generated from control.in
+Source: hello
+Section: devel
+Priority: optional
+Maintainer: Santiago Vila <sanvila@debian.org>
+Build-Depends: debhelper-compat (= 13),
+               help2man <!nocheck>
+Build-Depends-Indep: texinfo
+Build-Conflicts: autoconf2.13
+
+Package: hello
+Architecture: any
+# Keep in sync with hello-data
+Depends: ${shlibs:Depends}, ${misc:Depends},
+ hello-data (= ${source:Version})
+Description: example package based on GNU hello
+ The GNU hello program produces a familiar, friendly greeting.
+
+Package: hello-data
+Architecture: all
+Depends: ${misc:Depends}
+Description: data for hello
+";
+
+    #[test]
+    fn source_control_parses() {
+        let control = SourceControl::parse(CONTROL.as_bytes()).unwrap();
+        assert_eq!(control.source(), "hello");
+        assert_eq!(
+            control.maintainer(),
+            Some("Santiago Vila <sanvila@debian.org>")
+        );
+        let build_depends = control.build_depends().unwrap();
+        assert_eq!(build_depends.len(), 2);
+        assert_matches!(&build_depends[1], SourceRelationship::Dependency(dep)
+            if dep.alternatives[0].name == "help2man");
+        assert_eq!(control.build_depends_indep().unwrap().len(), 1);
+        assert_eq!(control.build_conflicts().unwrap().len(), 1);
+        assert_eq!(control.build_conflicts_arch().unwrap().len(), 0);
+
+        assert_eq!(control.binaries().len(), 2);
+        let hello = control.binary("hello").unwrap();
+        assert_eq!(hello.architecture(), ["any"]);
+        assert_eq!(
+            hello.depends().unwrap(),
+            [
+                SourceRelationship::Substvar("shlibs:Depends".to_owned()),
+                SourceRelationship::Substvar("misc:Depends".to_owned()),
+                SourceRelationship::Template("hello-data (= ${source:Version})".to_owned()),
+            ]
+        );
+        assert_eq!(control.binaries()[1].name(), "hello-data");
+    }
+
+    #[test]
+    fn relationships_display_as_written() {
+        let deps = parse_source_relationships("${misc:Depends}, a (>= 1), b (= ${binary:Version})")
+            .unwrap();
+        let written: Vec<String> = deps.iter().map(|dep| dep.to_string()).collect();
+        assert_eq!(
+            written,
+            ["${misc:Depends}", "a (>= 1)", "b (= ${binary:Version})"]
+        );
+    }
+
+    #[test]
+    fn substituted_binary_paragraph_parses_as_control() {
+        let control = SourceControl::parse(CONTROL.as_bytes()).unwrap();
+        let mut substvars = Substvars::new();
+        substvars.set("shlibs:Depends", "libc6 (>= 2.34)");
+        substvars.set("misc:Depends", "");
+        substvars.set("source:Version", "2.10-3");
+
+        let mut paragraph = substvars
+            .substitute_paragraph(control.binaries()[0].paragraph())
+            .unwrap();
+        paragraph.set("Version", "2.10-3").unwrap();
+        let binary = Control::try_from(paragraph).unwrap();
+        assert_eq!(
+            binary.get("Depends"),
+            Some("libc6 (>= 2.34), hello-data (= 2.10-3)")
+        );
+        assert_eq!(binary.depends().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn missing_source_fails() {
+        let text = CONTROL.replace("Source: hello\n", "");
+        let err = SourceControl::parse(text.as_bytes()).unwrap_err();
+        assert_matches!(err, Error::InvalidSourceControl(_));
+    }
+
+    #[test]
+    fn missing_binaries_fails() {
+        let text = &CONTROL[..CONTROL.find("\n\nPackage").unwrap()];
+        let err = SourceControl::parse(text.as_bytes()).unwrap_err();
+        assert_matches!(err, Error::InvalidSourceControl(_));
+    }
+}
@@ -0,0 +1,243 @@
+use std::io::{BufRead, BufReader, Read};
+
+use indexmap::IndexMap;
+use log::warn;
+
+use crate::control::{field_kind, FieldKind};
+use crate::{Error, Paragraph, Result};
+
+// Returns the name of a substitution variable if `token` consists of exactly
+// one `${name}` reference
+pub(crate) fn substvar_name(token: &str) -> Option<&str> {
+    let name = token.strip_prefix("${")?.strip_suffix('}')?;
+    if is_substvar_name(name) {
+        Some(name)
+    } else {
+        None
+    }
+}
+
+// Whether `text` contains a `${...}` reference anywhere
+pub(crate) fn has_substvar(text: &str) -> bool {
+    text.match_indices("${")
+        .any(|(start, _)| text[start..].contains('}'))
+}
+
+fn is_substvar_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == ':')
+}
+
+/// The substitution variables of a `debian/*.substvars` file, such as
+/// `shlibs:Depends=libc6 (>= 2.34)`
+///
+/// Variables are referenced as `${name}` in `debian/control` and replaced by
+/// [`Substvars::substitute`]. The built in `Newline`, `Space` and `Tab`
+/// variables are always defined.
+#[derive(Debug, Default, Clone)]
+pub struct Substvars {
+    vars: IndexMap<String, String>,
+}
+
+impl Substvars {
+    /// Creates an empty set of variables
+    pub fn new() -> Substvars {
+        Substvars::default()
+    }
+
+    /// Parses a substvars file. Each line is `name=value`, or `name?=value`
+    /// for a variable which may go unused. Empty lines and lines starting
+    /// with `#` are ignored.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let file = std::fs::File::open("debian/hello.substvars").unwrap();
+    /// let substvars = debpkg::Substvars::parse(file).unwrap();
+    /// println!("{:?}", substvars.get("shlibs:Depends"));
+    /// ```
+    pub fn parse<R: Read>(reader: R) -> Result<Substvars> {
+        let mut substvars = Substvars::new();
+        for line in BufReader::new(reader).lines() {
+            let line = line?;
+            let line = line.trim_end();
+            if line.trim_start().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, value) = line
+                .split_once('=')
+                .ok_or_else(|| Error::InvalidSubstvars(format!("missing '=' in '{line}'")))?;
+            let name = name.strip_suffix('?').unwrap_or(name);
+            if !is_substvar_name(name) {
+                return Err(Error::InvalidSubstvars(format!(
+                    "invalid variable name '{name}'"
+                )));
+            }
+            substvars.set(name, value);
+        }
+        Ok(substvars)
+    }
+
+    /// Returns the value of a variable if it is defined
+    pub fn get(&self, name: &str) -> Option<&str> {
+        match self.vars.get(name) {
+            Some(value) => Some(value),
+            None => match name {
+                "Newline" => Some("\n"),
+                "Space" => Some(" "),
+                "Tab" => Some("\t"),
+                _ => None,
+            },
+        }
+    }
+
+    /// Sets the value of a variable, replacing any previous value
+    pub fn set(&mut self, name: &str, value: &str) {
+        self.vars.insert(name.to_owned(), value.to_owned());
+    }
+
+    /// Returns an iterator over the variables from the file in the order
+    /// they were defined
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.vars
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// Replaces every `${name}` reference in `text` with the variable's
+    /// value. Like dpkg, undefined variables are replaced with nothing and a
+    /// warning is logged. Values are not substituted again.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut substvars = debpkg::Substvars::new();
+    /// substvars.set("misc:Depends", "debconf");
+    /// assert_eq!(substvars.substitute("${misc:Depends}, foo"), "debconf, foo");
+    /// ```
+    pub fn substitute(&self, text: &str) -> String {
+        let mut result = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find("${") {
+            let (before, reference) = rest.split_at(start);
+            result.push_str(before);
+            let end = match reference.find('}') {
+                Some(end) => end,
+                None => {
+                    rest = reference;
+                    break;
+                }
+            };
+            let name = &reference[2..end];
+            match self.get(name) {
+                Some(value) => result.push_str(value),
+                None => warn!("Substitution variable ${{{name}}} is not defined"),
+            }
+            rest = &reference[end + 1..];
+        }
+        result.push_str(rest);
+        result
+    }
+
+    /// Returns a copy of `paragraph` with the variables substituted in every
+    /// field value
+    ///
+    /// Empty entries left in comma separated fields such as `Depends` are
+    /// dropped, and fields whose value is empty after substitution are
+    /// removed, the way `dpkg-gencontrol` does.
+    pub fn substitute_paragraph(&self, paragraph: &Paragraph) -> Result<Paragraph> {
+        let mut substituted = Paragraph::new();
+        for tag in paragraph.tags() {
+            let value = match paragraph.continuation(tag) {
+                Some(other) if !other.is_empty() => {
+                    format!("{}\n{}", paragraph.get(tag).unwrap(), other)
+                }
+                _ => paragraph.get(tag).unwrap().to_owned(),
+            };
+            let mut value = self.substitute(&value);
+            if field_kind(tag) == FieldKind::Folded {
+                value = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|entry| !entry.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+            }
+            if !value.trim().is_empty() {
+                substituted.set(tag, value.trim())?;
+            }
+        }
+        Ok(substituted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+
+    #[test]
+    fn substvars_parse() {
+        let text = b"# generated\nshlibs:Depends=libc6 (>= 2.34)\nmisc:Depends=\nmisc:Pre-Depends?=dpkg (>= 1.15)\n\n";
+        let substvars = Substvars::parse(&text[..]).unwrap();
+        assert_eq!(substvars.get("shlibs:Depends"), Some("libc6 (>= 2.34)"));
+        assert_eq!(substvars.get("misc:Depends"), Some(""));
+        assert_eq!(substvars.get("misc:Pre-Depends"), Some("dpkg (>= 1.15)"));
+        assert_eq!(substvars.get("Newline"), Some("\n"));
+        assert_eq!(substvars.get("other"), None);
+        assert_eq!(substvars.iter().count(), 3);
+    }
+
+    #[test]
+    fn missing_equals_fails() {
+        let err = Substvars::parse(&b"shlibs:Depends libc6\n"[..]).unwrap_err();
+        assert_matches!(err, Error::InvalidSubstvars(_));
+    }
+
+    #[test]
+    fn substitute_replaces_references() {
+        let mut substvars = Substvars::new();
+        substvars.set("shlibs:Depends", "libc6");
+        substvars.set("binary:Version", "1.0-1");
+        assert_eq!(
+            substvars.substitute("${shlibs:Depends}, foo (= ${binary:Version}), ${unset}"),
+            "libc6, foo (= 1.0-1), "
+        );
+        assert_eq!(
+            substvars.substitute("$5 ${unterminated"),
+            "$5 ${unterminated"
+        );
+    }
+
+    #[test]
+    fn substitute_paragraph_drops_empty_fields() {
+        let mut paragraph = Paragraph::new();
+        paragraph.set("Package", "hello").unwrap();
+        paragraph.set("Depends", "${misc:Depends}").unwrap();
+        paragraph
+            .set("Recommends", "${misc:Recommends}, foo")
+            .unwrap();
+        paragraph
+            .set("Description", "hi\n${Space}indented")
+            .unwrap();
+
+        let mut substvars = Substvars::new();
+        substvars.set("misc:Depends", "");
+        let substituted = substvars.substitute_paragraph(&paragraph).unwrap();
+        assert_eq!(substituted.get("Package"), Some("hello"));
+        assert_eq!(substituted.get("Depends"), None);
+        assert_eq!(substituted.get("Recommends"), Some("foo"));
+        assert_eq!(substituted.continuation("Description"), Some(" indented"));
+    }
+
+    #[test]
+    fn substvar_names() {
+        assert_eq!(substvar_name("${shlibs:Depends}"), Some("shlibs:Depends"));
+        assert_eq!(substvar_name("${}"), None);
+        assert_eq!(substvar_name("foo ${a}"), None);
+        assert!(has_substvar("foo (= ${binary:Version})"));
+        assert!(!has_substvar("foo ${"));
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::path::PathBuf;
+
+use crate::control_archive::relative_path;
+use crate::hash::md5_digest;
+use crate::{ControlArchive, DebPkg, Error, Result};
+
+/// A file whose contents do not match its md5sums entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    /// The path of the file relative to the package root
+    pub path: PathBuf,
+
+    /// The digest listed in md5sums
+    pub expected: String,
+
+    /// The digest of the file in the data archive
+    pub actual: String,
+}
+
+/// The result of verifying a package's data archive against its md5sums
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    /// Files whose digest differs from md5sums
+    pub mismatched: Vec<Mismatch>,
+
+    /// Files listed in md5sums that are not in the data archive
+    pub missing: Vec<PathBuf>,
+
+    /// Regular files in the data archive that are not listed in md5sums.
+    /// Conffiles are not reported since md5sums normally omits them.
+    pub extra: Vec<PathBuf>,
+}
+
+impl VerifyReport {
+    /// Returns true if no mismatched, missing or extra files were found
+    pub fn is_ok(&self) -> bool {
+        self.mismatched.is_empty() && self.missing.is_empty() && self.extra.is_empty()
+    }
+}
+
+impl<R: Read> DebPkg<R> {
+    /// Verifies every file in the data archive against the package's md5sums,
+    /// like `debsums` does for installed packages
+    ///
+    /// The control and data archives are both read, so this must be called
+    /// before `control` or `data`. Hard links are checked against the digest
+    /// of the file they link to.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use debpkg::DebPkg;
+    /// let file = std::fs::File::open("test.deb").unwrap();
+    /// let mut pkg = DebPkg::parse(file).unwrap();
+    /// let report = pkg.verify().unwrap();
+    /// for mismatch in &report.mismatched {
+    ///     println!("{} is corrupted", mismatch.path.display());
+    /// }
+    /// ```
+    pub fn verify(&mut self) -> Result<VerifyReport> {
+        let archive = ControlArchive::extract(self.control()?)?;
+        let md5sums = archive.md5sums().ok_or(Error::MissingMd5sums)??;
+        let conffiles: HashSet<PathBuf> = archive
+            .conffiles()
+            .transpose()?
+            .unwrap_or_default()
+            .iter()
+            .map(|conffile| relative_path(&conffile.path.to_string_lossy()))
+            .collect();
+
+        let mut digests: HashMap<PathBuf, String> = HashMap::new();
+        let mut data = self.data()?;
+        for entry in data.entries()? {
+            let entry = entry?;
+            let path = relative_path(&entry.path()?.to_string_lossy());
+            let digest = match entry.header().entry_type() {
+                tar::EntryType::Regular | tar::EntryType::Continuous => md5_digest(entry)?,
+                tar::EntryType::Link => {
+                    let target = match entry.link_name()? {
+                        Some(target) => relative_path(&target.to_string_lossy()),
+                        None => continue,
+                    };
+                    match digests.get(&target) {
+                        Some(digest) => digest.clone(),
+                        None => continue,
+                    }
+                }
+                _ => continue,
+            };
+            digests.insert(path, digest);
+        }
+
+        let mut report = VerifyReport::default();
+        for (path, expected) in md5sums.iter() {
+            match digests.remove(path) {
+                Some(actual) if actual != expected => report.mismatched.push(Mismatch {
+                    path: path.to_owned(),
+                    expected: expected.to_owned(),
+                    actual,
+                }),
+                Some(_) => (),
+                None => report.missing.push(path.to_owned()),
+            }
+        }
+        report.extra = digests
+            .into_keys()
+            .filter(|path| !conffiles.contains(path))
+            .collect();
+        report.extra.sort();
+        Ok(report)
+    }
+}
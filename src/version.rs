@@ -0,0 +1,352 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{Error, Result};
+
+use log::warn;
+
+/// A Debian package version as described in Debian Policy 5.6.12
+///
+/// Versions are ordered the same way as `dpkg --compare-versions`. Note that
+/// this means two versions which are written differently may compare as
+/// equal, e.g. `1.0` and `0:1.0-0`.
+///
+/// # Example
+///
+/// ```
+/// use debpkg::Version;
+/// let old: Version = "1.0~rc1-1".parse().unwrap();
+/// let new: Version = "1.0-1".parse().unwrap();
+/// assert!(old < new);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Version {
+    epoch: u32,
+    upstream_version: String,
+    debian_revision: Option<String>,
+}
+
+impl Version {
+    /// Returns the epoch, or 0 if the version did not have one
+    pub fn epoch(&self) -> u32 {
+        self.epoch
+    }
+
+    /// Returns the upstream version
+    pub fn upstream_version(&self) -> &str {
+        &self.upstream_version
+    }
+
+    /// Returns the Debian revision if the version has one
+    pub fn debian_revision(&self) -> Option<&str> {
+        self.debian_revision.as_deref()
+    }
+}
+
+fn invalid(version: &str, msg: &str) -> Error {
+    Error::InvalidPackageVersion(format!("{msg} in '{version}'"))
+}
+
+impl FromStr for Version {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Version> {
+        let version = s.trim();
+        if version.is_empty() {
+            return Err(invalid(s, "version string is empty"));
+        }
+        if version.contains(char::is_whitespace) {
+            return Err(invalid(version, "version string has embedded spaces"));
+        }
+
+        let (epoch, rest) = match version.split_once(':') {
+            Some((epoch, rest)) => {
+                if epoch.is_empty() {
+                    return Err(invalid(version, "epoch is empty"));
+                }
+                if !epoch.bytes().all(|c| c.is_ascii_digit()) {
+                    return Err(invalid(version, "epoch is not a number"));
+                }
+                let epoch = epoch
+                    .parse::<u32>()
+                    .map_err(|_| invalid(version, "epoch is too big"))?;
+                (epoch, rest)
+            }
+            None => (0, version),
+        };
+
+        let (upstream_version, debian_revision) = match rest.rsplit_once('-') {
+            Some((upstream, revision)) => {
+                if revision.is_empty() {
+                    return Err(invalid(version, "revision is empty"));
+                }
+                (upstream, Some(revision))
+            }
+            None => (rest, None),
+        };
+
+        if upstream_version.is_empty() {
+            return Err(invalid(version, "upstream version is empty"));
+        }
+        if !upstream_version.starts_with(|c: char| c.is_ascii_digit()) {
+            // dpkg only warns about this, so do the same
+            warn!("Version '{version}' does not start with a digit");
+        }
+        if !upstream_version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '+' | '-' | '~'))
+        {
+            return Err(invalid(version, "invalid character in upstream version"));
+        }
+        if let Some(revision) = debian_revision {
+            if !revision
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '+' | '~'))
+            {
+                return Err(invalid(version, "invalid character in revision"));
+            }
+        }
+
+        Ok(Version {
+            epoch,
+            upstream_version: upstream_version.to_owned(),
+            debian_revision: debian_revision.map(str::to_owned),
+        })
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.epoch != 0 {
+            write!(f, "{}:", self.epoch)?;
+        }
+        f.write_str(&self.upstream_version)?;
+        if let Some(ref revision) = self.debian_revision {
+            write!(f, "-{revision}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.epoch
+            .cmp(&other.epoch)
+            .then_with(|| verrevcmp(&self.upstream_version, &other.upstream_version))
+            .then_with(|| {
+                verrevcmp(
+                    self.debian_revision.as_deref().unwrap_or(""),
+                    other.debian_revision.as_deref().unwrap_or(""),
+                )
+            })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Version {}
+
+// The weight of a non digit character when comparing versions. This mirrors
+// `order()` in dpkg's lib/dpkg/version.c: '~' sorts before everything, even
+// the end of the string, and letters sort before all other characters.
+fn order(c: Option<u8>) -> i32 {
+    match c {
+        None => 0,
+        Some(b'~') => -1,
+        Some(c) if c.is_ascii_digit() => 0,
+        Some(c) if c.is_ascii_alphabetic() => i32::from(c),
+        Some(c) => i32::from(c) + 256,
+    }
+}
+
+// Compares two upstream versions or Debian revisions the same way as
+// `verrevcmp()` in dpkg's lib/dpkg/version.c
+fn verrevcmp(a: &str, b: &str) -> Ordering {
+    let a = a.as_bytes();
+    let b = b.as_bytes();
+    let mut i = 0;
+    let mut j = 0;
+    let is_digit = |s: &[u8], idx: usize| s.get(idx).is_some_and(u8::is_ascii_digit);
+
+    while i < a.len() || j < b.len() {
+        while (i < a.len() && !is_digit(a, i)) || (j < b.len() && !is_digit(b, j)) {
+            let ac = order(a.get(i).copied());
+            let bc = order(b.get(j).copied());
+            if ac != bc {
+                return ac.cmp(&bc);
+            }
+            i += 1;
+            j += 1;
+        }
+
+        while a.get(i) == Some(&b'0') {
+            i += 1;
+        }
+        while b.get(j) == Some(&b'0') {
+            j += 1;
+        }
+
+        let mut first_diff = Ordering::Equal;
+        while is_digit(a, i) && is_digit(b, j) {
+            if first_diff == Ordering::Equal {
+                first_diff = a[i].cmp(&b[j]);
+            }
+            i += 1;
+            j += 1;
+        }
+        if is_digit(a, i) {
+            return Ordering::Greater;
+        }
+        if is_digit(b, j) {
+            return Ordering::Less;
+        }
+        if first_diff != Ordering::Equal {
+            return first_diff;
+        }
+    }
+
+    Ordering::Equal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+    use std::cmp::Ordering::{Equal, Greater, Less};
+
+    // Each entry was checked against `dpkg --compare-versions`
+    const COMPARISONS: &[(&str, &str, Ordering)] = &[
+        ("1.0", "1.0", Equal),
+        ("1.0", "1.0-0", Equal),
+        ("0:1.0", "1.0", Equal),
+        ("1.0-1", "1.0-1", Equal),
+        ("1.0", "1.1", Less),
+        ("1.1", "1.0", Greater),
+        ("1.0", "1.0.1", Less),
+        ("1.0", "1.00", Equal),
+        ("1.01", "1.1", Equal),
+        ("1.9", "1.10", Less),
+        ("1.10", "1.9", Greater),
+        ("1.0~rc1", "1.0", Less),
+        ("1.0~rc1", "1.0~rc2", Less),
+        ("1.0~~", "1.0~", Less),
+        ("1.0~~a", "1.0~", Less),
+        ("1.0~", "1.0", Less),
+        ("1.0", "1.0+1", Less),
+        ("1.0+1", "1.0.1", Less),
+        ("1.0a", "1.0", Greater),
+        ("1.0a", "1.0+", Less),
+        ("1.0a", "1.0.", Less),
+        ("1.0a", "1.0b", Less),
+        ("1.0A", "1.0a", Less),
+        ("1.0Z", "1.0a", Less),
+        ("1.0-1", "1.0-2", Less),
+        ("1.0-10", "1.0-9", Greater),
+        ("1.0-1", "1.0-1ubuntu1", Less),
+        ("1.0-1ubuntu1", "1.0-1.1", Less),
+        ("1.0-1~bpo1", "1.0-1", Less),
+        ("1.0-1+b1", "1.0-1", Greater),
+        ("1.0-1+deb12u1", "1.0-1+deb12u2", Less),
+        ("1:1.0", "2.0", Greater),
+        ("1:1.0", "1:2.0", Less),
+        ("2:0.1", "1:9.9", Greater),
+        ("10:1.0", "9:1.0", Greater),
+        ("1.2.3-4-5", "1.2.3-4-6", Less),
+        ("1.2.3-4-5", "1.2.3-5", Greater),
+        ("0.0", "0", Greater),
+        ("0", "00", Equal),
+        ("a", "b", Less),
+        ("1.0.0", "1.0", Greater),
+        ("2.0.0-1", "2.0-1", Greater),
+        ("5.2.4-1", "5.2.2", Greater),
+        ("2.17", "2.7", Greater),
+        ("9.22-1", "9.22", Greater),
+        ("1.8~rc2", "1.8", Less),
+        ("1.8~rc2", "1.8~rc10", Less),
+        ("1.8rc2", "1.8", Greater),
+        ("3.0.0+dfsg-1", "3.0.0-1", Greater),
+        ("3.0.0+dfsg", "3.0.0.1", Less),
+        ("0.3-4", "0.11-1", Less),
+        ("0.11-1", "0.3-4", Greater),
+        ("1.0+git20200101", "1.0+git20191231", Greater),
+        ("1.0+1~", "1.0+1", Less),
+        ("20200101", "2020.01.01", Greater),
+        ("7.6p2-4", "7.6-0", Greater),
+        ("1.2a3", "1.2a10", Less),
+        ("1.2~a3", "1.2a", Less),
+        ("1.0-0", "1.0-00", Equal),
+        ("1.0-a", "1.0-~", Greater),
+    ];
+
+    #[test]
+    fn comparisons_match_dpkg() {
+        for &(a, b, expected) in COMPARISONS {
+            let va: Version = a.parse().unwrap();
+            let vb: Version = b.parse().unwrap();
+            assert_eq!(va.cmp(&vb), expected, "{a} vs {b}");
+            assert_eq!(vb.cmp(&va), expected.reverse(), "{b} vs {a}");
+        }
+    }
+
+    #[test]
+    fn components_parse() {
+        let version: Version = "2:1.0.3-0ubuntu1".parse().unwrap();
+        assert_eq!(version.epoch(), 2);
+        assert_eq!(version.upstream_version(), "1.0.3");
+        assert_eq!(version.debian_revision(), Some("0ubuntu1"));
+    }
+
+    #[test]
+    fn last_hyphen_splits_revision() {
+        let version: Version = "1.2-3-4".parse().unwrap();
+        assert_eq!(version.upstream_version(), "1.2-3");
+        assert_eq!(version.debian_revision(), Some("4"));
+    }
+
+    #[test]
+    fn native_version_has_no_revision() {
+        let version: Version = "1.2".parse().unwrap();
+        assert_eq!(version.epoch(), 0);
+        assert_eq!(version.debian_revision(), None);
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for s in ["1.0", "1:1.0-1", "1.0~rc1+dfsg-1ubuntu0.1"] {
+            assert_eq!(s.parse::<Version>().unwrap().to_string(), s);
+        }
+    }
+
+    #[test]
+    fn invalid_versions_fail() {
+        for s in [
+            "", "1.0 1", ":1.0", "a:1.0", "1:", "-1", "1.0-", "1.0_1", "1:1.0:2", "1.0-1_2",
+            "1.0-1-",
+        ] {
+            assert_matches!(
+                s.parse::<Version>(),
+                Err(Error::InvalidPackageVersion(_)),
+                "{s}"
+            );
+        }
+    }
+
+    #[test]
+    fn huge_epoch_fails() {
+        assert_matches!(
+            "99999999999:1.0".parse::<Version>(),
+            Err(Error::InvalidPackageVersion(_))
+        );
+    }
+}
@@ -129,7 +129,49 @@ fn ar_with_empty_control_fails_extract() {
     let mut pkg = debpkg::DebPkg::parse(&reader).unwrap();
     let control_tar = pkg.control().unwrap();
     let control_result = debpkg::Control::extract(control_tar).err().unwrap();
-    assert_matches!(control_result, debpkg::Error::InvalidControlFile);
+    assert_matches!(control_result, debpkg::Error::InvalidControlFile(_));
+}
+
+#[test]
+fn ar_with_broken_control_parses_leniently() {
+    let control_file_contents = b"Package: name\nVersion: 1.0\nVersion: 2.0\n";
+
+    let mut header = tar::Header::new_ustar();
+    header.set_size(u64::try_from(control_file_contents.len()).unwrap());
+    header.set_cksum();
+
+    let mut control_tar = tar::Builder::new(std::vec::Vec::new());
+    control_tar
+        .append_data(
+            &mut header,
+            std::path::Path::new("./control"),
+            &control_file_contents[..],
+        )
+        .unwrap();
+    let control_tar = control_tar.into_inner().unwrap();
+
+    let mut archive = ar::Builder::new(std::vec::Vec::new());
+    let header = ar::Header::new(b"debian-binary".to_vec(), 4);
+    archive.append(&header, "2.0\n".as_bytes()).unwrap();
+    let header = ar::Header::new(
+        b"control.tar".to_vec(),
+        u64::try_from(control_tar.len()).unwrap(),
+    );
+    archive.append(&header, &control_tar[..]).unwrap();
+    let deb = archive.into_inner().unwrap();
+
+    let mut pkg = debpkg::DebPkg::parse(&deb[..]).unwrap();
+    let err = pkg.parse_control(&debpkg::ParseOptions::new()).unwrap_err();
+    assert_matches!(err, debpkg::Error::InvalidControlFile(_));
+
+    let mut options = debpkg::ParseOptions::new();
+    options.lenient(true);
+    let mut pkg = debpkg::DebPkg::parse(&deb[..]).unwrap();
+    let (control, diagnostics) = pkg.parse_control(&options).unwrap();
+    assert!(control.name() == "name");
+    assert!(control.version() == "1.0");
+    assert!(diagnostics.len() == 1);
+    assert!(diagnostics[0].kind == debpkg::ControlParseErrorKind::DuplicateField);
 }
 
 #[test]
@@ -141,6 +183,10 @@ fn xz_utils_parses() {
     let control_tar = pkg.control().unwrap();
     let control = debpkg::Control::extract(control_tar).unwrap();
     assert!(control.name() == "xz-utils");
+    let depends = control.depends().unwrap();
+    assert!(depends.len() == 2);
+    assert!(depends[1].alternatives[0].name == "liblzma5");
+    assert!(control.provides().unwrap()[0].alternatives[0].name == "lzma");
 
     let mut data = pkg.data().unwrap();
 
@@ -177,3 +223,1076 @@ fn libgssglue_utils_parses() {
     assert!(major == 2);
     assert!(minor == 0);
 }
+
+#[test]
+fn libgssglue_control_archive() {
+    let libgssglue_deb_path = get_deb_path("libgssglue1_0.3-4_amd64.deb");
+    let libgssglue_deb = std::fs::File::open(libgssglue_deb_path).unwrap();
+
+    let mut pkg = debpkg::DebPkg::parse(libgssglue_deb).unwrap();
+    let control_tar = pkg.control().unwrap();
+    let archive = debpkg::ControlArchive::extract(control_tar).unwrap();
+    assert!(archive.control().name() == "libgssglue1");
+
+    let md5sums = archive.md5sums().unwrap().unwrap();
+    assert!(md5sums.len() == 3);
+    assert!(md5sums.get("/lib/libgssglue.so.1.0.0") == Some("8afe70866eafeae9e4ba2f3e8ee0464e"));
+
+    let conffiles = archive.conffiles().unwrap().unwrap();
+    assert!(conffiles.len() == 1);
+    assert!(conffiles[0].path == std::path::Path::new("/etc/gssapi_mech.conf"));
+
+    let shlibs = archive.shlibs().unwrap().unwrap();
+    assert!(shlibs.len() == 1);
+    assert!(shlibs[0].library == "libgssglue");
+    assert!(shlibs[0].version == "1");
+    assert!(shlibs[0].dependencies == "libgssglue1");
+
+    assert!(archive.symbols().is_none());
+    assert!(archive.triggers().is_none());
+    assert!(archive.member("postinst").is_some());
+    assert!(archive.member("preinst").is_none());
+}
+
+#[test]
+fn xz_utils_seekable_reads_in_any_order() {
+    let xz_deb_path = get_deb_path("xz-utils_5.2.4-1_amd64.deb");
+    let xz_deb = std::fs::File::open(xz_deb_path).unwrap();
+
+    let mut pkg = debpkg::SeekableDebPkg::parse(xz_deb).unwrap();
+    assert!(pkg.format_version() == (2, 0));
+
+    let count_entries = |pkg: &mut debpkg::SeekableDebPkg<std::fs::File>| {
+        pkg.data().unwrap().entries().unwrap().count()
+    };
+    let data_entries = count_entries(&mut pkg);
+    assert!(data_entries > 0);
+
+    let control = debpkg::Control::extract(pkg.control().unwrap()).unwrap();
+    assert!(control.name() == "xz-utils");
+    let control = debpkg::Control::extract(pkg.control().unwrap()).unwrap();
+    assert!(control.version() == "5.2.4-1");
+
+    assert!(count_entries(&mut pkg) == data_entries);
+}
+
+#[test]
+fn xz_utils_contents_match_dpkg_deb() {
+    let xz_deb_path = get_deb_path("xz-utils_5.2.4-1_amd64.deb");
+    let xz_deb = std::fs::File::open(xz_deb_path).unwrap();
+
+    let mut pkg = debpkg::DebPkg::parse(xz_deb).unwrap();
+    let entries = pkg.contents().unwrap();
+    let unxz = entries
+        .iter()
+        .find(|entry| entry.path == std::path::Path::new("./usr/bin/unxz"))
+        .unwrap();
+    assert!(unxz.kind == debpkg::FileKind::Symlink);
+    assert!(unxz.link_target == Some(std::path::PathBuf::from("xz")));
+
+    // Output of `TZ=UTC dpkg-deb -c`
+    let listing = debpkg::format_contents(&entries);
+    let lines: Vec<&str> = listing.lines().collect();
+    assert!(lines[0] == "drwxr-xr-x root/root         0 2019-01-28 01:09 ./");
+    assert!(lines[4] == "-rwxr-xr-x root/root     80224 2019-01-28 01:09 ./usr/bin/xz");
+    assert!(lines.contains(&"lrwxrwxrwx root/root         0 2019-01-28 01:09 ./usr/bin/unxz -> xz"));
+}
+
+#[test]
+fn xz_utils_info_matches_dpkg_deb() {
+    let xz_deb_path = get_deb_path("xz-utils_5.2.4-1_amd64.deb");
+    let mut pkg = debpkg::DebPkg::parse(std::fs::File::open(&xz_deb_path).unwrap()).unwrap();
+    let info = pkg.info().unwrap();
+    assert!(info.size() == 82468);
+    assert!(info.control_size() == 1904);
+    assert!(info.data_compression() == debpkg::Compression::Xz);
+    assert!(info.control().name() == "xz-utils");
+    let postinst = &info.control_members()[2];
+    assert!(postinst.name == "postinst");
+    assert!(postinst.executable);
+    assert!(postinst.interpreter.as_deref() == Some("/bin/sh"));
+
+    // Output of `dpkg-deb -I`
+    let text = info.to_string();
+    let lines: Vec<&str> = text.lines().collect();
+    assert!(lines[0] == " new Debian package, version 2.0.");
+    assert!(lines[1] == " size 82468 bytes: control archive=1904 bytes.");
+    assert!(lines[2] == "    1294 bytes,    34 lines      control              ");
+    assert!(lines[4] == "     458 bytes,    19 lines   *  postinst             #!/bin/sh");
+    assert!(lines[6] == " Package: xz-utils");
+
+    let mut pkg =
+        debpkg::SeekableDebPkg::parse(std::fs::File::open(&xz_deb_path).unwrap()).unwrap();
+    assert!(pkg.info().unwrap().to_string() == text);
+}
+
+#[test]
+fn xz_utils_copyright_is_machine_readable() {
+    let xz_deb_path = get_deb_path("xz-utils_5.2.4-1_amd64.deb");
+    let mut pkg = debpkg::DebPkg::parse(std::fs::File::open(xz_deb_path).unwrap()).unwrap();
+    let copyright = pkg.copyright().unwrap().unwrap();
+    assert!(copyright.is_machine_readable());
+    assert!(copyright.upstream_name() == Some("XZ Utils"));
+    let license = |path| copyright.license_for(path).unwrap().name.as_str();
+    assert!(license("src/xz/main.c") == "PD");
+    assert!(license("lib/getopt.c") == "LGPL-2.1+");
+    assert!(license("src/liblzma/check/crc32_table_le.h") == "none");
+    assert!(license("m4/ax_check_capsicum.m4") == "permissive-nowarranty");
+    assert!(copyright
+        .license_text("GPL-2+")
+        .unwrap()
+        .contains("GNU General Public License"));
+}
+
+#[test]
+fn libgssglue_copyright_without_license_names() {
+    let deb_path = get_deb_path("libgssglue1_0.3-4_amd64.deb");
+    let mut pkg = debpkg::SeekableDebPkg::parse(std::fs::File::open(deb_path).unwrap()).unwrap();
+    let copyright = pkg.copyright().unwrap().unwrap();
+    assert!(copyright.is_machine_readable());
+    let files = copyright.files_for("src/g_initialize.c").unwrap();
+    assert!(files.license.name.is_empty());
+    assert!(files
+        .license
+        .text
+        .as_deref()
+        .unwrap()
+        .starts_with("License of The Regents of the University of Michigan"));
+}
+
+#[cfg(feature = "sbom")]
+#[test]
+fn xz_utils_sbom_lists_files_and_dependencies() {
+    let xz_deb_path = get_deb_path("xz-utils_5.2.4-1_amd64.deb");
+    let mut options = debpkg::SbomOptions::new();
+    options.created(std::time::UNIX_EPOCH);
+
+    let mut pkg = debpkg::DebPkg::parse(std::fs::File::open(&xz_deb_path).unwrap()).unwrap();
+    let mut spdx = Vec::new();
+    pkg.write_spdx(&mut spdx, &options).unwrap();
+    let spdx: serde_json::Value = serde_json::from_slice(&spdx).unwrap();
+    assert!(spdx["spdxVersion"] == "SPDX-2.3");
+    assert!(spdx["creationInfo"]["created"] == "1970-01-01T00:00:00Z");
+    let package = &spdx["packages"][0];
+    assert!(package["name"] == "xz-utils");
+    assert!(package["versionInfo"] == "5.2.4-1");
+    assert!(
+        package["externalRefs"][0]["referenceLocator"]
+            == "pkg:deb/debian/xz-utils@5.2.4-1?arch=amd64"
+    );
+    let declared = package["licenseDeclared"].as_str().unwrap();
+    assert!(declared.contains("GPL-2.0-or-later"));
+    assert!(spdx["packages"][1]["name"] == "libc6");
+    let xz = spdx["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|file| file["fileName"] == "./usr/bin/xz")
+        .unwrap();
+    assert!(xz["checksums"][2]["checksumValue"] == "40a89558d90ceeb5793a518a81e5476f");
+
+    let mut pkg =
+        debpkg::SeekableDebPkg::parse(std::fs::File::open(&xz_deb_path).unwrap()).unwrap();
+    let mut cyclonedx = Vec::new();
+    pkg.write_cyclonedx(&mut cyclonedx, &options).unwrap();
+    let cyclonedx: serde_json::Value = serde_json::from_slice(&cyclonedx).unwrap();
+    assert!(cyclonedx["specVersion"] == "1.5");
+    let component = &cyclonedx["metadata"]["component"];
+    assert!(component["purl"] == "pkg:deb/debian/xz-utils@5.2.4-1?arch=amd64");
+    assert!(
+        cyclonedx["dependencies"][0]["dependsOn"]
+            == serde_json::json!(["pkg:deb/debian/libc6", "pkg:deb/debian/liblzma5"])
+    );
+    let files = cyclonedx["components"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|component| component["type"] == "file")
+        .count();
+    assert!(files == spdx["files"].as_array().unwrap().len());
+}
+
+#[cfg(feature = "sbom")]
+#[test]
+fn sbom_of_malformed_copyright_has_no_assertion() {
+    let deb = build_package(
+        "Package: a\nVersion: 1.0",
+        &[(
+            "usr/share/doc/a/copyright",
+            "Format: dep5\n\nFiles: *\nCopyright: 2001 Alice\n",
+        )],
+    );
+    let options = debpkg::SbomOptions::new();
+
+    let mut spdx = Vec::new();
+    debpkg::DebPkg::parse(&deb[..])
+        .unwrap()
+        .write_spdx(&mut spdx, &options)
+        .unwrap();
+    let spdx: serde_json::Value = serde_json::from_slice(&spdx).unwrap();
+    assert!(spdx["packages"][0]["licenseDeclared"] == "NOASSERTION");
+    assert!(spdx["packages"][0]["copyrightText"] == "NOASSERTION");
+
+    let mut cyclonedx = Vec::new();
+    debpkg::DebPkg::parse(&deb[..])
+        .unwrap()
+        .write_cyclonedx(&mut cyclonedx, &options)
+        .unwrap();
+    let cyclonedx: serde_json::Value = serde_json::from_slice(&cyclonedx).unwrap();
+    assert!(cyclonedx["metadata"]["component"]["licenses"].is_null());
+}
+
+#[test]
+fn xz_utils_lints_clean() {
+    let xz_deb_path = get_deb_path("xz-utils_5.2.4-1_amd64.deb");
+    let mut pkg = debpkg::DebPkg::parse(std::fs::File::open(xz_deb_path).unwrap()).unwrap();
+    let lints = pkg.lint().unwrap();
+    assert!(lints.is_empty(), "{:?}", lints);
+}
+
+#[test]
+fn built_package_lints_layout() {
+    let deb = build_package(
+        "Package: a\nVersion: 1.0\nMaintainer: A <a@example.org>\nDescription: a tool",
+        &[("usr/local/bin/a", "a\n")],
+    );
+    let mut pkg = debpkg::DebPkg::parse(&deb[..]).unwrap();
+    let lints = pkg.lint().unwrap();
+    let tags: Vec<&str> = lints.iter().map(|lint| lint.tag).collect();
+    assert!(tags.contains(&"bad-package-name"));
+    assert!(tags.contains(&"file-in-usr-local"));
+    assert!(tags.contains(&"no-copyright-file"));
+    assert!(lints
+        .iter()
+        .all(|lint| lint.severity == debpkg::Severity::Error));
+}
+
+#[test]
+fn seekable_without_data_fails_parse() {
+    let mut deb = Vec::new();
+    {
+        let mut builder = ar::Builder::new(&mut deb);
+        let header = ar::Header::new(b"debian-binary".to_vec(), 4);
+        builder.append(&header, &b"2.0\n"[..]).unwrap();
+        let header = ar::Header::new(b"control.tar".to_vec(), 0);
+        builder.append(&header, &b""[..]).unwrap();
+    }
+
+    let err = debpkg::SeekableDebPkg::parse(std::io::Cursor::new(deb))
+        .err()
+        .unwrap();
+    assert_matches!(err, debpkg::Error::MissingDataArchive);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn xz_utils_parses_async() {
+    use tokio_stream::StreamExt;
+
+    let xz_deb_path = get_deb_path("xz-utils_5.2.4-1_amd64.deb");
+    let xz_deb = tokio::fs::File::open(xz_deb_path).await.unwrap();
+
+    let mut pkg = debpkg::AsyncDebPkg::parse(xz_deb).await.unwrap();
+    assert!(pkg.format_version() == (2, 0));
+
+    let control_tar = pkg.control().await.unwrap();
+    let control = debpkg::Control::extract_async(control_tar).await.unwrap();
+    assert!(control.name() == "xz-utils");
+    assert!(control.version() == "5.2.4-1");
+
+    let mut data = pkg.data().await.unwrap();
+    let mut entries = data.entries().unwrap();
+    let mut paths = Vec::new();
+    while let Some(entry) = entries.next().await {
+        paths.push(entry.unwrap().path().unwrap().display().to_string());
+    }
+    assert!(paths.contains(&"./usr/bin/xz".to_string()));
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn truncated_package_fails_async() {
+    let control = debpkg::Control::parse(&b"Package: hello\nVersion: 1.0"[..]).unwrap();
+    let mut deb = debpkg::DebPkgBuilder::new(control)
+        .add_file("usr/bin/hello", "#!/bin/sh\necho hello\n", 0o755)
+        .build(Vec::new())
+        .unwrap();
+    // A signature member claiming 100 bytes of which only 40 are present
+    deb.extend_from_slice(
+        format!(
+            "{:<16}{:<12}{:<6}{:<6}{:<8}{:<10}`\n",
+            "_gpgorigin", 0, 0, 0, 644, 100
+        )
+        .as_bytes(),
+    );
+    deb.extend_from_slice(&[0; 40]);
+
+    let mut pkg = debpkg::AsyncDebPkg::parse(&deb[..]).await.unwrap();
+    let err = pkg.extra_members().await.unwrap_err();
+    assert_matches!(err, debpkg::Error::Io(err) if err.kind() == std::io::ErrorKind::UnexpectedEof);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn built_package_data_async_skips_control() {
+    use tokio_stream::StreamExt;
+
+    for compression in [
+        debpkg::Compression::None,
+        debpkg::Compression::Gzip,
+        debpkg::Compression::Xz,
+        debpkg::Compression::Bzip2,
+        debpkg::Compression::Zstd,
+    ] {
+        let control = debpkg::Control::parse(&b"Package: hello\nVersion: 1.0"[..]).unwrap();
+        let deb = debpkg::DebPkgBuilder::new(control)
+            .add_file("usr/bin/hello", "#!/bin/sh\necho hello\n", 0o755)
+            .control_compression(compression)
+            .data_compression(compression)
+            .build(Vec::new())
+            .unwrap();
+
+        let mut pkg = debpkg::AsyncDebPkg::parse(&deb[..]).await.unwrap();
+        let mut data = pkg.data().await.unwrap();
+        let mut entries = data.entries().unwrap();
+        let mut count = 0;
+        while let Some(entry) = entries.next().await {
+            entry.unwrap();
+            count += 1;
+        }
+        assert!(count == 4);
+        drop(entries);
+        drop(data);
+        let err = pkg.control().await.err().unwrap();
+        assert_matches!(err, debpkg::Error::ControlAlreadyRead);
+    }
+}
+
+// Builds a package and rebuilds its ar archive with the given extra members
+// inserted after `debian-binary` and appended after `data.tar`
+fn build_with_extra_members(
+    before: &[(&str, &[u8])],
+    after: &[(&str, &[u8])],
+) -> (Vec<u8>, Vec<u8>) {
+    let control = debpkg::Control::parse(&b"Package: hello\nVersion: 1.0"[..]).unwrap();
+    let deb = debpkg::DebPkgBuilder::new(control)
+        .add_file("usr/bin/hello", "#!/bin/sh\necho hello\n", 0o755)
+        .build(Vec::new())
+        .unwrap();
+
+    let mut members = Vec::new();
+    let mut archive = ar::Archive::new(&deb[..]);
+    while let Some(entry) = archive.next_entry() {
+        let mut entry = entry.unwrap();
+        let name = entry.header().identifier().to_vec();
+        let mut contents = Vec::new();
+        std::io::Read::read_to_end(&mut entry, &mut contents).unwrap();
+        members.push((name, contents));
+    }
+    let signed_data: Vec<u8> = members
+        .iter()
+        .flat_map(|(_, contents)| contents.clone())
+        .collect();
+
+    let extra = |members: &[(&str, &[u8])]| -> Vec<(Vec<u8>, Vec<u8>)> {
+        members
+            .iter()
+            .map(|(name, contents)| (name.as_bytes().to_vec(), contents.to_vec()))
+            .collect()
+    };
+    let mut all = vec![members[0].clone()];
+    all.extend(extra(before));
+    all.extend(members[1..].iter().cloned());
+    all.extend(extra(after));
+
+    let mut builder = ar::Builder::new(Vec::new());
+    for (name, contents) in all {
+        let header = ar::Header::new(name, contents.len() as u64);
+        builder.append(&header, &contents[..]).unwrap();
+    }
+    (builder.into_inner().unwrap(), signed_data)
+}
+
+#[test]
+fn extra_members_are_skipped_and_collected() {
+    let (deb, _) = build_with_extra_members(&[("_first", b"1")], &[("_gpgorigin", b"sig")]);
+
+    let mut pkg = debpkg::DebPkg::parse(&deb[..]).unwrap();
+    let control = debpkg::Control::extract(pkg.control().unwrap()).unwrap();
+    assert!(control.name() == "hello");
+    let count = pkg.data().unwrap().entries().unwrap().count();
+    assert!(count == 4);
+
+    let members = pkg.extra_members().unwrap();
+    assert!(members.len() == 2);
+    assert!(members[0].name == "_first");
+    assert!(members[0].contents == b"1");
+    assert!(members[1].name == "_gpgorigin");
+    assert!(members[1].contents == b"sig");
+}
+
+#[test]
+fn seekable_extra_members_and_signed_data() {
+    let (deb, signed_data) =
+        build_with_extra_members(&[("_first", b"1")], &[("_gpgorigin", b"sig")]);
+
+    let mut pkg = debpkg::SeekableDebPkg::parse(std::io::Cursor::new(deb)).unwrap();
+    let names: Vec<&str> = pkg.extra_member_names().collect();
+    assert!(names == ["_first", "_gpgorigin"]);
+    let members = pkg.extra_members().unwrap();
+    assert!(members[1].contents == b"sig");
+
+    let mut written = Vec::new();
+    let len = pkg.write_signed_data(&mut written).unwrap();
+    assert!(len == signed_data.len() as u64);
+    assert!(written == signed_data);
+
+    let control = debpkg::Control::extract(pkg.control().unwrap()).unwrap();
+    assert!(control.name() == "hello");
+}
+
+#[cfg(feature = "gpgv")]
+#[test]
+fn seekable_verifies_gpg_signature() {
+    use std::process::Command;
+
+    let home = tempfile::TempDir::new().unwrap();
+    let gpg = |args: &[&str]| {
+        let status = Command::new("gpg")
+            .arg("--homedir")
+            .arg(home.path())
+            .args([
+                "--batch",
+                "--quiet",
+                "--pinentry-mode",
+                "loopback",
+                "--passphrase",
+                "",
+            ])
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    gpg(&[
+        "--quick-gen-key",
+        "Test <test@example.com>",
+        "ed25519",
+        "sign",
+        "never",
+    ]);
+
+    let (_, signed_data) = build_with_extra_members(&[], &[]);
+    let data_path = home.path().join("data");
+    std::fs::write(&data_path, &signed_data).unwrap();
+    gpg(&["--detach-sign", data_path.to_str().unwrap()]);
+    let signature = std::fs::read(home.path().join("data.sig")).unwrap();
+    let keyring = home.path().join("keyring.gpg");
+    gpg(&["--output", keyring.to_str().unwrap(), "--export"]);
+
+    let (deb, _) = build_with_extra_members(&[], &[("_gpgorigin", &signature)]);
+    let mut pkg = debpkg::SeekableDebPkg::parse(std::io::Cursor::new(deb)).unwrap();
+    pkg.verify_signature("_gpgorigin", &keyring).unwrap();
+
+    let (deb, _) = build_with_extra_members(&[], &[("_gpgorigin", b"not a signature")]);
+    let mut pkg = debpkg::SeekableDebPkg::parse(std::io::Cursor::new(deb)).unwrap();
+    let err = pkg.verify_signature("_gpgorigin", &keyring).unwrap_err();
+    assert_matches!(err, debpkg::Error::InvalidSignature(_));
+    let err = pkg.verify_signature("_gpgbuilder", &keyring).unwrap_err();
+    assert_matches!(err, debpkg::Error::InvalidSignature(_));
+}
+
+#[test]
+fn built_package_parses() {
+    let control = debpkg::Control::parse(
+        &b"Package: hello\nVersion: 1.0-1\nArchitecture: all\nDescription: greeting\n says hello\n"
+            [..],
+    )
+    .unwrap();
+
+    let mut builder = debpkg::DebPkgBuilder::new(control);
+    builder
+        .add_file("/usr/bin/hello", "#!/bin/sh\necho hello\n", 0o755)
+        .add_symlink("usr/bin/hi", "hello")
+        .maintainer_script("postinst", "#!/bin/sh\nset -e\n")
+        .data_compression(debpkg::Compression::Gzip);
+    let deb = builder.build(Vec::new()).unwrap();
+
+    let mut pkg = debpkg::DebPkg::parse(&deb[..]).unwrap();
+    let control_tar = pkg.control().unwrap();
+    let control = debpkg::Control::extract(control_tar).unwrap();
+    assert!(control.name() == "hello");
+    assert!(control.version() == "1.0-1");
+    assert!(control.long_description().unwrap() == "says hello");
+
+    let mut data = pkg.data().unwrap();
+    let paths: Vec<String> = data
+        .entries()
+        .unwrap()
+        .map(|e| e.unwrap().path().unwrap().display().to_string())
+        .collect();
+    assert!(
+        paths
+            == [
+                "./",
+                "./usr/",
+                "./usr/bin/",
+                "./usr/bin/hello",
+                "./usr/bin/hi"
+            ]
+    );
+}
+
+#[test]
+fn xz_utils_verifies() {
+    let xz_deb_path = get_deb_path("xz-utils_5.2.4-1_amd64.deb");
+    let xz_deb = std::fs::File::open(xz_deb_path).unwrap();
+
+    let mut pkg = debpkg::DebPkg::parse(xz_deb).unwrap();
+    let report = pkg.verify().unwrap();
+    assert!(report.is_ok());
+}
+
+#[test]
+fn built_package_verify_reports_differences() {
+    let control = debpkg::Control::parse(&b"Package: hello\nVersion: 1.0"[..]).unwrap();
+    let mut builder = debpkg::DebPkgBuilder::new(control);
+    builder
+        .add_file("usr/bin/hello", "#!/bin/sh\necho hello\n", 0o755)
+        .add_file("usr/bin/bye", "#!/bin/sh\necho bye\n", 0o755)
+        .add_file("usr/bin/extra", "", 0o755)
+        .add_file("etc/hello.conf", "", 0o644)
+        .control_file("conffiles", "/etc/hello.conf\n", 0o644)
+        .control_file(
+            "md5sums",
+            "d604a220708aa59433ba410986cd4ffa  usr/bin/hello\n\
+             00000000000000000000000000000000  usr/bin/bye\n\
+             00000000000000000000000000000000  usr/bin/gone\n",
+            0o644,
+        );
+    let deb = builder.build(Vec::new()).unwrap();
+
+    let mut pkg = debpkg::DebPkg::parse(&deb[..]).unwrap();
+    let report = pkg.verify().unwrap();
+    assert!(!report.is_ok());
+    assert!(report.mismatched.len() == 1);
+    assert!(report.mismatched[0].path == std::path::Path::new("usr/bin/bye"));
+    assert!(report.missing == [std::path::PathBuf::from("usr/bin/gone")]);
+    assert!(report.extra == [std::path::PathBuf::from("usr/bin/extra")]);
+}
+
+#[test]
+fn built_package_without_md5sums_fails_verify() {
+    let control = debpkg::Control::parse(&b"Package: hello\nVersion: 1.0"[..]).unwrap();
+    let deb = debpkg::DebPkgBuilder::new(control)
+        .build(Vec::new())
+        .unwrap();
+
+    let mut pkg = debpkg::DebPkg::parse(&deb[..]).unwrap();
+    assert_matches!(pkg.verify(), Err(debpkg::Error::MissingMd5sums));
+}
+
+#[test]
+fn built_package_with_all_compressions_parses() {
+    let control = debpkg::Control::parse(&b"Package: hello\nVersion: 1.0"[..]).unwrap();
+    let mut builder = debpkg::DebPkgBuilder::new(control);
+    builder.add_file("etc/hello.conf", "greeting=hi\n", 0o644);
+
+    for compression in [
+        debpkg::Compression::None,
+        debpkg::Compression::Gzip,
+        debpkg::Compression::Xz,
+        debpkg::Compression::Bzip2,
+        debpkg::Compression::Zstd,
+    ] {
+        builder
+            .control_compression(compression)
+            .data_compression(compression);
+        let deb = builder.build(Vec::new()).unwrap();
+
+        let mut pkg = debpkg::DebPkg::parse(&deb[..]).unwrap();
+        let control = debpkg::Control::extract(pkg.control().unwrap()).unwrap();
+        assert!(control.name() == "hello");
+        let mut data = pkg.data().unwrap();
+        assert!(data.entries().unwrap().count() == 3);
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn built_package_from_dir_skips_fifos() {
+    let staging = tempfile::TempDir::new().unwrap();
+    std::fs::create_dir(staging.path().join("etc")).unwrap();
+    std::fs::write(staging.path().join("etc/hello.conf"), "greeting=hi\n").unwrap();
+    let status = std::process::Command::new("mkfifo")
+        .arg(staging.path().join("etc/pipe"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let control = debpkg::Control::parse(&b"Package: hello\nVersion: 1.0"[..]).unwrap();
+    let mut builder = debpkg::DebPkgBuilder::new(control);
+    builder.add_dir_all(staging.path(), "/").unwrap();
+    let deb = builder.build(Vec::new()).unwrap();
+
+    let mut pkg = debpkg::DebPkg::parse(&deb[..]).unwrap();
+    let mut data = pkg.data().unwrap();
+    let paths: Vec<String> = data
+        .entries()
+        .unwrap()
+        .map(|e| e.unwrap().path().unwrap().display().to_string())
+        .collect();
+    assert!(paths == ["./", "./etc/", "./etc/hello.conf"]);
+}
+
+#[test]
+fn xz_utils_packages_entry() {
+    let xz_deb_path = get_deb_path("xz-utils_5.2.4-1_amd64.deb");
+    let xz_deb = std::fs::File::open(xz_deb_path).unwrap();
+
+    let entry =
+        debpkg::packages_entry(xz_deb, "pool/main/x/xz-utils/xz-utils_5.2.4-1_amd64.deb").unwrap();
+    assert!(entry.name() == "xz-utils");
+    assert!(entry.get("Filename").unwrap() == "pool/main/x/xz-utils/xz-utils_5.2.4-1_amd64.deb");
+    assert!(entry.get("Size").unwrap() == "82468");
+    assert!(entry.get("MD5sum").unwrap() == "83180f715199306653347739c8808aa0");
+    assert!(entry.get("SHA1").unwrap() == "d01ba955c2a4c6bcdb1c4575cb171053dc197ebb");
+    assert!(
+        entry.get("SHA256").unwrap()
+            == "23298ca419d2e34e54ee74c472de1102da9cabc66b59a470f1a8a5405e4a7c14"
+    );
+}
+
+// Generates a repository holding the xz-utils package and returns its root
+fn generate_repository() -> tempfile::TempDir {
+    let root = tempfile::TempDir::new().unwrap();
+    let pool = root.path().join("pool").join("main");
+    std::fs::create_dir_all(&pool).unwrap();
+    std::fs::copy(
+        get_deb_path("xz-utils_5.2.4-1_amd64.deb"),
+        pool.join("xz-utils_5.2.4-1_amd64.deb"),
+    )
+    .unwrap();
+    debpkg::RepositoryBuilder::new(root.path(), "stable")
+        .codename("test")
+        .date(std::time::UNIX_EPOCH)
+        .generate()
+        .unwrap();
+    root
+}
+
+#[test]
+fn generated_release_parses() {
+    let root = generate_repository();
+    let dist = root.path().join("dists").join("stable");
+
+    let release = std::fs::File::open(dist.join("Release")).unwrap();
+    let release = debpkg::Release::parse(release).unwrap();
+    assert!(release.suite() == Some("stable"));
+    assert!(release.codename() == Some("test"));
+    assert!(release.architectures() == ["amd64"]);
+    assert!(release.components() == ["main"]);
+    assert!(release.date() == Some(std::time::UNIX_EPOCH));
+    assert!(release.valid_until().is_none());
+    assert!(!release.acquire_by_hash());
+    assert!(!release.sha256().is_empty());
+    for file in release.sha256() {
+        let contents = std::fs::read(dist.join(&file.path)).unwrap();
+        let digests = debpkg::FileDigests::compute(&contents[..]).unwrap();
+        assert!(digests.size == file.size);
+        assert!(digests.sha256 == file.hash);
+    }
+}
+
+#[cfg(feature = "gpgv")]
+#[test]
+fn clearsigned_release_verifies() {
+    use std::process::Command;
+
+    let root = generate_repository();
+    let dist = root.path().join("dists").join("stable");
+    let home = tempfile::TempDir::new().unwrap();
+    let gpg = |args: &[&str]| {
+        let status = Command::new("gpg")
+            .arg("--homedir")
+            .arg(home.path())
+            .args([
+                "--batch",
+                "--quiet",
+                "--pinentry-mode",
+                "loopback",
+                "--passphrase",
+                "",
+            ])
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    gpg(&[
+        "--quick-gen-key",
+        "Test <test@example.com>",
+        "ed25519",
+        "sign",
+        "never",
+    ]);
+    let keyring = home.path().join("keyring.gpg");
+    gpg(&["--output", keyring.to_str().unwrap(), "--export"]);
+    let in_release = dist.join("InRelease");
+    gpg(&[
+        "--clearsign",
+        "--output",
+        in_release.to_str().unwrap(),
+        dist.join("Release").to_str().unwrap(),
+    ]);
+
+    let text = std::fs::read_to_string(&in_release).unwrap();
+    let signed = debpkg::ClearSigned::verify(&text, &keyring).unwrap();
+    assert!(signed.body() == std::fs::read_to_string(dist.join("Release")).unwrap());
+    let release = debpkg::Release::parse(text.as_bytes()).unwrap();
+    assert!(release.codename() == Some("test"));
+
+    let tampered = text.replace("Codename: test", "Codename: evil");
+    let err = debpkg::ClearSigned::verify(&tampered, &keyring).unwrap_err();
+    assert_matches!(err, debpkg::Error::InvalidSignature(_));
+}
+
+#[test]
+fn repository_generates_indices() {
+    let root = tempfile::TempDir::new().unwrap();
+    let pool = root.path().join("pool").join("main");
+    std::fs::create_dir_all(&pool).unwrap();
+    std::fs::copy(
+        get_deb_path("xz-utils_5.2.4-1_amd64.deb"),
+        pool.join("xz-utils_5.2.4-1_amd64.deb"),
+    )
+    .unwrap();
+    let control =
+        debpkg::Control::parse(&b"Package: hello\nVersion: 1.0\nArchitecture: all"[..]).unwrap();
+    let hello = std::fs::File::create(pool.join("hello_1.0_all.deb")).unwrap();
+    debpkg::DebPkgBuilder::new(control).build(hello).unwrap();
+
+    debpkg::RepositoryBuilder::new(root.path(), "stable")
+        .codename("test")
+        .by_hash(true)
+        .date(std::time::UNIX_EPOCH)
+        .generate()
+        .unwrap();
+
+    let dist = root.path().join("dists").join("stable");
+    let packages = std::fs::read(dist.join("main/binary-amd64/Packages")).unwrap();
+    let entries: Vec<debpkg::Paragraph> = debpkg::Deb822Reader::new(&packages[..])
+        .map(|p| p.unwrap())
+        .collect();
+    assert!(entries.len() == 2);
+    assert!(entries[0].get("Package").unwrap() == "hello");
+    assert!(entries[1].get("Filename").unwrap() == "pool/main/xz-utils_5.2.4-1_amd64.deb");
+    assert!(dist.join("main/binary-amd64/Packages.gz").exists());
+    assert!(dist.join("main/binary-amd64/Packages.xz").exists());
+
+    let release = std::fs::read(dist.join("Release")).unwrap();
+    let release = debpkg::Deb822Reader::new(&release[..])
+        .next()
+        .unwrap()
+        .unwrap();
+    assert!(release.get("Suite").unwrap() == "stable");
+    assert!(release.get("Codename").unwrap() == "test");
+    assert!(release.get("Architectures").unwrap() == "amd64");
+    assert!(release.get("Date").unwrap() == "Thu, 01 Jan 1970 00:00:00 UTC");
+    assert!(release.get("Acquire-By-Hash").unwrap() == "yes");
+
+    let digests = debpkg::FileDigests::compute(&packages[..]).unwrap();
+    let expected = format!(
+        " {} {:>16} main/binary-amd64/Packages\n",
+        digests.sha256, digests.size
+    );
+    let release_text = std::fs::read_to_string(dist.join("Release")).unwrap();
+    assert!(release_text.contains(&expected));
+    assert!(dist
+        .join("main/binary-amd64/by-hash/SHA256")
+        .join(&digests.sha256)
+        .exists());
+}
+
+#[test]
+fn repository_pool_outside_root_fails() {
+    let root = tempfile::TempDir::new().unwrap();
+    let outside = tempfile::TempDir::new().unwrap();
+    for pool in [outside.path(), std::path::Path::new("../pool")] {
+        let err = debpkg::RepositoryBuilder::new(root.path(), "stable")
+            .pool(pool)
+            .generate()
+            .unwrap_err();
+        assert_matches!(err, debpkg::Error::InvalidPath(_));
+    }
+}
+
+fn build_package(control: &str, files: &[(&str, &str)]) -> Vec<u8> {
+    let control = debpkg::Control::parse(control.as_bytes()).unwrap();
+    let mut builder = debpkg::DebPkgBuilder::new(control);
+    for (path, contents) in files {
+        builder.add_file(path, *contents, 0o644);
+    }
+    builder.build(Vec::new()).unwrap()
+}
+
+#[test]
+fn installer_records_package_in_dpkg_database() {
+    let root = tempfile::TempDir::new().unwrap();
+    let control = debpkg::Control::parse(
+        &b"Package: hello\nVersion: 1.0\nArchitecture: all\nDescription: greeting\n"[..],
+    )
+    .unwrap();
+    let deb = debpkg::DebPkgBuilder::new(control)
+        .add_file("usr/bin/hello", "#!/bin/sh\necho hello\n", 0o755)
+        .add_file("etc/hello.conf", "greeting=hi\n", 0o644)
+        .control_file("conffiles", "/etc/hello.conf\n", 0o644)
+        .maintainer_script("postinst", "#!/bin/sh\nset -e\n")
+        .build(Vec::new())
+        .unwrap();
+
+    let mut pkg = debpkg::DebPkg::parse(&deb[..]).unwrap();
+    let installed = debpkg::Installer::new(root.path())
+        .install(&mut pkg)
+        .unwrap();
+    assert!(installed.is_installed());
+    assert!(installed.conffiles().len() == 1);
+    assert!(installed.conffiles()[0].md5 == "196bdbd60cbe13cc013f592941f52f52");
+
+    let hello = std::fs::read_to_string(root.path().join("usr/bin/hello")).unwrap();
+    assert!(hello == "#!/bin/sh\necho hello\n");
+    let info = root.path().join("var/lib/dpkg/info");
+    assert!(info.join("hello.postinst").exists());
+    assert!(info.join("hello.conffiles").exists());
+    let list = std::fs::read_to_string(info.join("hello.list")).unwrap();
+    assert!(list == "/.\n/etc\n/etc/hello.conf\n/usr\n/usr/bin\n/usr/bin/hello\n");
+
+    let db = debpkg::DpkgDatabase::open(root.path()).unwrap();
+    let hello = db.package("hello").unwrap();
+    assert!(hello.status().to_string() == "install ok installed");
+    assert!(hello.get("Description") == Some("greeting"));
+    assert!(db.owners("/usr/bin/hello").unwrap()[0].name() == "hello");
+
+    // A modified conffile is kept and the new version written next to it
+    std::fs::write(root.path().join("etc/hello.conf"), "greeting=hey\n").unwrap();
+    let mut pkg = debpkg::DebPkg::parse(&deb[..]).unwrap();
+    debpkg::Installer::new(root.path())
+        .install(&mut pkg)
+        .unwrap();
+    let conf = std::fs::read_to_string(root.path().join("etc/hello.conf")).unwrap();
+    assert!(conf == "greeting=hey\n");
+    assert!(root.path().join("etc/hello.conf.dpkg-new").exists());
+    let db = debpkg::DpkgDatabase::open(root.path()).unwrap();
+    assert!(db.packages().len() == 1);
+}
+
+#[test]
+fn installer_refuses_to_overwrite_other_packages() {
+    let root = tempfile::TempDir::new().unwrap();
+    let a = build_package("Package: a\nVersion: 1.0", &[("usr/bin/tool", "a\n")]);
+    let b = build_package(
+        "Package: b\nVersion: 1.0",
+        &[("usr/bin/b", "b\n"), ("usr/bin/tool", "b\n")],
+    );
+    let c = build_package(
+        "Package: c\nVersion: 1.0\nReplaces: a, b",
+        &[("usr/bin/tool", "c\n")],
+    );
+
+    let mut installer = debpkg::Installer::new(root.path());
+    installer
+        .install(&mut debpkg::DebPkg::parse(&a[..]).unwrap())
+        .unwrap();
+    let err = installer
+        .install(&mut debpkg::DebPkg::parse(&b[..]).unwrap())
+        .unwrap_err();
+    assert_matches!(err, debpkg::Error::FileConflict(ref path, ref owner)
+        if path == std::path::Path::new("/usr/bin/tool") && owner == "a");
+    // Nothing is unpacked when any file conflicts
+    assert!(!root.path().join("usr/bin/b").exists());
+
+    installer
+        .install(&mut debpkg::DebPkg::parse(&c[..]).unwrap())
+        .unwrap();
+    installer
+        .force_overwrite(true)
+        .install(&mut debpkg::DebPkg::parse(&b[..]).unwrap())
+        .unwrap();
+    let tool = std::fs::read_to_string(root.path().join("usr/bin/tool")).unwrap();
+    assert!(tool == "b\n");
+}
+
+#[test]
+fn installer_follows_diversions_and_removes_old_files() {
+    let root = tempfile::TempDir::new().unwrap();
+    let admin_dir = root.path().join("var/lib/dpkg");
+    std::fs::create_dir_all(&admin_dir).unwrap();
+    std::fs::write(
+        admin_dir.join("diversions"),
+        "/usr/bin/tool\n/usr/bin/tool.distrib\nother\n",
+    )
+    .unwrap();
+
+    let v1 = build_package(
+        "Package: a\nVersion: 1.0",
+        &[("usr/bin/tool", "1\n"), ("usr/bin/old", "1\n")],
+    );
+    let v2 = build_package("Package: a\nVersion: 2.0", &[("usr/bin/tool", "2\n")]);
+    let installer = debpkg::Installer::new(root.path());
+    installer
+        .install(&mut debpkg::DebPkg::parse(&v1[..]).unwrap())
+        .unwrap();
+    assert!(!root.path().join("usr/bin/tool").exists());
+    assert!(root.path().join("usr/bin/tool.distrib").exists());
+    assert!(root.path().join("usr/bin/old").exists());
+
+    installer
+        .install(&mut debpkg::DebPkg::parse(&v2[..]).unwrap())
+        .unwrap();
+    let tool = std::fs::read_to_string(root.path().join("usr/bin/tool.distrib")).unwrap();
+    assert!(tool == "2\n");
+    assert!(!root.path().join("usr/bin/old").exists());
+    let db = debpkg::DpkgDatabase::open(root.path()).unwrap();
+    assert!(db.package("a").unwrap().version() == Some("2.0"));
+}
+
+#[test]
+fn installer_diverted_file_does_not_conflict_with_diverter() {
+    let root = tempfile::TempDir::new().unwrap();
+    let admin_dir = root.path().join("var/lib/dpkg");
+    std::fs::create_dir_all(&admin_dir).unwrap();
+    std::fs::write(
+        admin_dir.join("diversions"),
+        "/usr/bin/tool\n/usr/bin/tool.distrib\nwrapper\n",
+    )
+    .unwrap();
+
+    let wrapper = build_package("Package: wrapper\nVersion: 1.0", &[("usr/bin/tool", "w\n")]);
+    let a = build_package("Package: a\nVersion: 1.0", &[("usr/bin/tool", "a\n")]);
+    let installer = debpkg::Installer::new(root.path());
+    installer
+        .install(&mut debpkg::DebPkg::parse(&wrapper[..]).unwrap())
+        .unwrap();
+    installer
+        .install(&mut debpkg::DebPkg::parse(&a[..]).unwrap())
+        .unwrap();
+    let tool = std::fs::read_to_string(root.path().join("usr/bin/tool")).unwrap();
+    assert!(tool == "w\n");
+    let distrib = std::fs::read_to_string(root.path().join("usr/bin/tool.distrib")).unwrap();
+    assert!(distrib == "a\n");
+}
+
+#[test]
+fn installer_upgrades_unmodified_conffile() {
+    let root = tempfile::TempDir::new().unwrap();
+    let build = |version: &str, conf: &str| {
+        let control = debpkg::Control::parse(
+            format!("Package: hello\nVersion: {version}\nArchitecture: all\n").as_bytes(),
+        )
+        .unwrap();
+        debpkg::DebPkgBuilder::new(control)
+            .add_file("etc/hello.conf", conf, 0o644)
+            .control_file("conffiles", "/etc/hello.conf\n", 0o644)
+            .build(Vec::new())
+            .unwrap()
+    };
+    let v1 = build("1.0", "greeting=hi\n");
+    let v2 = build("2.0", "greeting=hello\n");
+
+    let installer = debpkg::Installer::new(root.path());
+    installer
+        .install(&mut debpkg::DebPkg::parse(&v1[..]).unwrap())
+        .unwrap();
+    let installed = installer
+        .install(&mut debpkg::DebPkg::parse(&v2[..]).unwrap())
+        .unwrap();
+    let conf = std::fs::read_to_string(root.path().join("etc/hello.conf")).unwrap();
+    assert!(conf == "greeting=hello\n");
+    assert!(!root.path().join("etc/hello.conf.dpkg-new").exists());
+    let digest = debpkg::FileDigests::compute(conf.as_bytes()).unwrap();
+    assert!(installed.conffiles()[0].md5 == digest.md5);
+}
+
+#[test]
+fn installer_keeps_dropped_conffiles_as_obsolete() {
+    let root = tempfile::TempDir::new().unwrap();
+    let v1 = debpkg::DebPkgBuilder::new(
+        debpkg::Control::parse(&b"Package: hello\nVersion: 1.0\n"[..]).unwrap(),
+    )
+    .add_file("etc/hello.conf", "greeting=hi\n", 0o644)
+    .add_file("etc/old.conf", "old=yes\n", 0o644)
+    .control_file("conffiles", "/etc/hello.conf\n/etc/old.conf\n", 0o644)
+    .build(Vec::new())
+    .unwrap();
+    let v2 = debpkg::DebPkgBuilder::new(
+        debpkg::Control::parse(&b"Package: hello\nVersion: 2.0\n"[..]).unwrap(),
+    )
+    .add_file("etc/hello.conf", "greeting=hi\n", 0o644)
+    .control_file("conffiles", "/etc/hello.conf\n", 0o644)
+    .build(Vec::new())
+    .unwrap();
+
+    let installer = debpkg::Installer::new(root.path());
+    installer
+        .install(&mut debpkg::DebPkg::parse(&v1[..]).unwrap())
+        .unwrap();
+    let installed = installer
+        .install(&mut debpkg::DebPkg::parse(&v2[..]).unwrap())
+        .unwrap();
+    let old = std::fs::read_to_string(root.path().join("etc/old.conf")).unwrap();
+    assert!(old == "old=yes\n");
+    assert!(installed.conffiles().len() == 2);
+    assert!(!installed.conffiles()[0].obsolete);
+    let obsolete = &installed.conffiles()[1];
+    assert!(obsolete.path == std::path::Path::new("/etc/old.conf"));
+    assert!(obsolete.obsolete);
+    let digest = debpkg::FileDigests::compute(&b"old=yes\n"[..]).unwrap();
+    assert!(obsolete.md5 == digest.md5);
+
+    let db = debpkg::DpkgDatabase::open(root.path()).unwrap();
+    assert!(db.owners("/etc/old.conf").unwrap()[0].name() == "hello");
+    assert!(db.package("hello").unwrap().conffiles()[1].obsolete);
+}
+
+#[test]
+fn extract_to_unpacks_data_archive() {
+    let deb = build_package(
+        "Package: a\nVersion: 1.0",
+        &[("usr/bin/a", "a\n"), ("usr/share/doc/a/README", "readme\n")],
+    );
+    let dir = tempfile::TempDir::new().unwrap();
+    let mut pkg = debpkg::DebPkg::parse(&deb[..]).unwrap();
+    let paths = pkg
+        .extract_to(dir.path(), &debpkg::ExtractOptions::new())
+        .unwrap();
+    assert!(paths.contains(&std::path::PathBuf::from("usr/bin/a")));
+    let readme = std::fs::read_to_string(dir.path().join("usr/share/doc/a/README")).unwrap();
+    assert!(readme == "readme\n");
+
+    let mut pkg = debpkg::SeekableDebPkg::parse(std::io::Cursor::new(&deb)).unwrap();
+    let mut options = debpkg::ExtractOptions::new();
+    options.max_size(4);
+    assert_matches!(
+        pkg.extract_to(dir.path(), &options),
+        Err(debpkg::Error::LimitExceeded(_))
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn installer_keeps_symlinks_in_root() {
+    let root = tempfile::TempDir::new().unwrap();
+    let outside = tempfile::TempDir::new().unwrap();
+    std::os::unix::fs::symlink(outside.path(), root.path().join("etc")).unwrap();
+
+    let deb = build_package("Package: a\nVersion: 1.0", &[("etc/a.conf", "a\n")]);
+    debpkg::Installer::new(root.path())
+        .install(&mut debpkg::DebPkg::parse(&deb[..]).unwrap())
+        .unwrap();
+    assert!(!outside.path().join("a.conf").exists());
+    let inside = root
+        .path()
+        .join(outside.path().strip_prefix("/").unwrap())
+        .join("a.conf");
+    assert!(inside.exists());
+}